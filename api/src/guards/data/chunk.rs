@@ -0,0 +1,32 @@
+use rocket::data::{self, Data, FromData, ToByteUnit};
+use rocket::http::Status;
+use rocket::outcome::Outcome;
+use rocket::request::Request;
+
+/// Part of a resumable upload, any content type is accepted, the file type is checked once the upload is complete
+pub struct Chunk(Vec<u8>);
+
+impl From<Chunk> for Vec<u8> {
+    fn from(chunk: Chunk) -> Self {
+        chunk.0
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromData<'r> for Chunk {
+    type Error = Option<String>;
+
+    async fn from_data(_req: &'r Request<'_>, data: Data<'r>) -> data::Outcome<'r, Self> {
+        let data = data.open(11.megabytes());
+
+        let Ok(data_bytes) = data.into_bytes().await else {
+            return Outcome::Failure((Status::BadRequest, None))
+        };
+
+        if !data_bytes.is_complete() {
+            return Outcome::Failure((Status::PayloadTooLarge, None))
+        }
+
+        Outcome::Success(Chunk(data_bytes.into_inner()))
+    }
+}
@@ -0,0 +1,35 @@
+use rocket::data::{self, Data, FromData, ToByteUnit};
+use rocket::http::{ContentType, Status};
+use rocket::outcome::Outcome;
+use rocket::request::Request;
+
+pub struct CsvFile(Vec<u8>);
+
+impl From<CsvFile> for Vec<u8> {
+    fn from(csv: CsvFile) -> Self {
+        csv.0
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromData<'r> for CsvFile {
+    type Error = Option<String>;
+
+    async fn from_data(req: &'r Request<'_>, data: Data<'r>) -> data::Outcome<'r, Self> {
+        if req.content_type() != Some(&ContentType::CSV) {
+            return Outcome::Failure((Status::BadRequest, None))
+        }
+
+        let data = data.open(2.megabytes());
+
+        let Ok(data_bytes) = data.into_bytes().await else {
+            return Outcome::Failure((Status::BadRequest, None))
+        };
+
+        if !data_bytes.is_complete() {
+            return Outcome::Failure((Status::PayloadTooLarge, None))
+        }
+
+        Outcome::Success(CsvFile(data_bytes.into_inner()))
+    }
+}
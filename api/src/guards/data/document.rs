@@ -0,0 +1,35 @@
+use portfolio_core::models::admission_cycle::AdmissionCycle;
+use rocket::data::{self, Data, FromData, ToByteUnit};
+use rocket::http::Status;
+use rocket::outcome::Outcome;
+use rocket::request::Request;
+
+/// Portfolio document uploaded in one request.
+/// The document is not known yet, so the data are read up to the largest configured document
+/// and the type and size are checked against the document by the service
+pub struct Document(Vec<u8>);
+
+impl From<Document> for Vec<u8> {
+    fn from(document: Document) -> Self {
+        document.0
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromData<'r> for Document {
+    type Error = Option<String>;
+
+    async fn from_data(_req: &'r Request<'_>, data: Data<'r>) -> data::Outcome<'r, Self> {
+        let data = data.open(AdmissionCycle::get().max_document_size().bytes());
+
+        let Ok(data_bytes) = data.into_bytes().await else {
+            return Outcome::Failure((Status::BadRequest, None))
+        };
+
+        if !data_bytes.is_complete() {
+            return Outcome::Failure((Status::PayloadTooLarge, None))
+        }
+
+        Outcome::Success(Document(data_bytes.into_inner()))
+    }
+}
@@ -1,2 +1,3 @@
-pub mod portfolio;
-pub mod letter;
\ No newline at end of file
+pub mod csv_file;
+pub mod chunk;
+pub mod document;
\ No newline at end of file
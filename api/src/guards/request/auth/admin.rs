@@ -1,16 +1,22 @@
+use std::ops::Deref;
+
 use entity::admin::Model as Admin;
 use log::info;
+use portfolio_core::models::admin::AdminRole;
+use portfolio_core::models::audit::{AuditActor, AuditContext};
 use portfolio_core::models::auth::AuthenticableTrait;
 use portfolio_core::sea_orm::prelude::Uuid;
 use portfolio_core::services::admin_service::AdminService;
 use rocket::http::Status;
 use rocket::outcome::Outcome;
 use rocket::request::{FromRequest, Request};
+use rocket::outcome::try_outcome;
 
+use crate::guards::request::client_ip::client_ip;
 use crate::logging::format_request;
 use crate::pool::Db;
 
-pub struct AdminAuth(Admin, String);
+pub struct AdminAuth(Admin, String, Option<String>, Uuid);
 
 impl Into<Admin> for AdminAuth {
     fn into(self) -> Admin {
@@ -22,6 +28,18 @@ impl AdminAuth {
     pub fn get_private_key(&self) -> String {
         self.1.clone()
     }
+
+    pub fn admin_id(&self) -> i32 {
+        self.0.id
+    }
+
+    pub fn session_id(&self) -> Uuid {
+        self.3
+    }
+
+    pub fn audit_context(&self) -> AuditContext {
+        AuditContext::new(AuditActor::Admin(self.0.id), self.2.clone())
+    }
 }
 
 #[rocket::async_trait]
@@ -54,7 +72,7 @@ impl<'r> FromRequest<'r> for AdminAuth {
         match session {
             Ok(model) => {
                 warn!("{}: ADMIN {} AUTHENTICATED", format_request(req), model.id);
-                Outcome::Success(AdminAuth(model, private_key.to_string()))
+                Outcome::Success(AdminAuth(model, private_key.to_string(), Some(client_ip(req).to_string()), uuid))
             },
             Err(e) => {
                 info!("{}: ADMIN AUTHENTICATION FAILED: {}", format_request(req), e);
@@ -63,4 +81,57 @@ impl<'r> FromRequest<'r> for AdminAuth {
         }
 
     }
-}
\ No newline at end of file
+}
+
+async fn admin_auth_with_role(
+    req: &Request<'_>,
+    required: AdminRole,
+) -> Outcome<AdminAuth, (Status, Option<String>), ()> {
+    let admin = try_outcome!(req.guard::<AdminAuth>().await);
+
+    match AdminRole::try_from(&admin.0) {
+        Ok(role) if role.has_permission(required) => Outcome::Success(admin),
+        _ => {
+            info!("{}: ADMIN {} FORBIDDEN, {:?} ROLE REQUIRED", format_request(req), admin.0.id, required);
+            Outcome::Failure((Status::Forbidden, None))
+        },
+    }
+}
+
+/// Admin allowed to create and delete candidates and reset their passwords
+pub struct RegistrarAuth(AdminAuth);
+
+impl Deref for RegistrarAuth {
+    type Target = AdminAuth;
+
+    fn deref(&self) -> &AdminAuth {
+        &self.0
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for RegistrarAuth {
+    type Error = Option<String>;
+    async fn from_request(req: &'r Request<'_>) -> Outcome<RegistrarAuth, (Status, Self::Error), ()> {
+        admin_auth_with_role(req, AdminRole::Registrar).await.map(RegistrarAuth)
+    }
+}
+
+/// Admin allowed to add and remove other admins
+pub struct SuperadminAuth(AdminAuth);
+
+impl Deref for SuperadminAuth {
+    type Target = AdminAuth;
+
+    fn deref(&self) -> &AdminAuth {
+        &self.0
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for SuperadminAuth {
+    type Error = Option<String>;
+    async fn from_request(req: &'r Request<'_>) -> Outcome<SuperadminAuth, (Status, Self::Error), ()> {
+        admin_auth_with_role(req, AdminRole::Superadmin).await.map(SuperadminAuth)
+    }
+}
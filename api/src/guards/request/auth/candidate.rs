@@ -1,4 +1,5 @@
 use entity::application::Model as Application;
+use portfolio_core::models::audit::{AuditActor, AuditContext};
 use portfolio_core::models::auth::AuthenticableTrait;
 use portfolio_core::sea_orm::prelude::Uuid;
 use portfolio_core::services::application_service::ApplicationService;
@@ -6,10 +7,11 @@ use rocket::http::Status;
 use rocket::outcome::Outcome;
 use rocket::request::{FromRequest, Request};
 
+use crate::guards::request::client_ip::client_ip;
 use crate::logging::format_request;
 use crate::pool::Db;
 
-pub struct ApplicationAuth(Application, String);
+pub struct ApplicationAuth(Application, String, Option<String>);
 
 impl Into<Application> for ApplicationAuth {
     fn into(self) -> Application {
@@ -21,6 +23,10 @@ impl ApplicationAuth {
     pub fn get_private_key(&self) -> String {
         self.1.clone()
     }
+
+    pub fn audit_context(&self) -> AuditContext {
+        AuditContext::new(AuditActor::Application(self.0.id), self.2.clone())
+    }
 }
 
 #[rocket::async_trait]
@@ -55,7 +61,7 @@ impl<'r> FromRequest<'r> for ApplicationAuth {
         match session {
             Ok(model) => {
                 info!("{}: CANDIDATE {} AUTHENTICATED", format_request(req), model.id);
-                Outcome::Success(ApplicationAuth(model, private_key.to_string().to_string()))
+                Outcome::Success(ApplicationAuth(model, private_key.to_string(), Some(client_ip(req).to_string())))
             },
             Err(e) => {
                 info!("{}: CANDIDATE {} AUTHENTICATION FAILED", format_request(req), e);
@@ -0,0 +1,149 @@
+use std::net::{IpAddr, Ipv4Addr};
+
+use rocket::outcome::Outcome;
+use rocket::request::{FromRequest, Request};
+
+/// Proxies allowed to report the client address in `X-Forwarded-For` / `X-Real-IP`,
+/// read from comma separated addresses or CIDR ranges in `PORTFOLIO_TRUSTED_PROXIES`
+#[derive(Debug, Clone, Default)]
+pub struct TrustedProxies(Vec<(IpAddr, u8)>);
+
+impl TrustedProxies {
+    pub fn from_env() -> Self {
+        dotenv::dotenv().ok();
+        Self::parse(&std::env::var("PORTFOLIO_TRUSTED_PROXIES").unwrap_or_default())
+    }
+
+    pub fn parse(s: &str) -> Self {
+        let ranges = s.split(',')
+            .map(str::trim)
+            .filter(|r| !r.is_empty())
+            .filter_map(|range| {
+                let (ip, prefix) = range.split_once('/').unwrap_or((range, ""));
+                let ip: IpAddr = ip.parse().ok()?;
+                let max_prefix = if ip.is_ipv4() { 32 } else { 128 };
+                let prefix = if prefix.is_empty() { max_prefix } else { prefix.parse().ok()? };
+                if prefix > max_prefix {
+                    warn!("INVALID TRUSTED PROXY RANGE {}", range);
+                    return None;
+                }
+                Some((ip, prefix))
+            })
+            .collect();
+
+        Self(ranges)
+    }
+
+    pub fn contains(&self, ip: &IpAddr) -> bool {
+        self.0.iter().any(|(range, prefix)| match (range, ip) {
+            (IpAddr::V4(range), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - *prefix as u32).unwrap_or(0);
+                u32::from(*range) & mask == u32::from(*ip) & mask
+            },
+            (IpAddr::V6(range), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - *prefix as u32).unwrap_or(0);
+                u128::from(*range) & mask == u128::from(*ip) & mask
+            },
+            _ => false,
+        })
+    }
+
+    /// Forwarding headers are only honoured when the peer is a trusted proxy.
+    /// `X-Forwarded-For` is walked from the right, the first untrusted address is the client.
+    /// When the walk hits an invalid entry the client is unknown, `X-Real-IP` or the peer is used,
+    /// not the left-most trusted proxy all such clients would share
+    pub fn resolve(&self, peer: IpAddr, forwarded_for: Option<&str>, real_ip: Option<&str>) -> IpAddr {
+        if !self.contains(&peer) {
+            return peer;
+        }
+
+        if let Some(forwarded_for) = forwarded_for {
+            let mut last = None;
+            for ip in forwarded_for.rsplit(',').map(|ip| ip.trim().parse::<IpAddr>()) {
+                let Ok(ip) = ip else {
+                    last = None;
+                    break;
+                };
+                if !self.contains(&ip) {
+                    return ip;
+                }
+                last = Some(ip);
+            }
+            if let Some(ip) = last {
+                return ip;
+            }
+        }
+
+        real_ip
+            .and_then(|ip| ip.trim().parse().ok())
+            .unwrap_or(peer)
+    }
+}
+
+/// Address of the client, taking trusted proxies in front of the API into account
+pub fn client_ip(req: &Request<'_>) -> IpAddr {
+    let peer = req.remote()
+        .map(|addr| addr.ip())
+        .unwrap_or(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)));
+
+    match req.rocket().state::<TrustedProxies>() {
+        Some(trusted) => trusted.resolve(
+            peer,
+            req.headers().get_one("X-Forwarded-For"),
+            req.headers().get_one("X-Real-IP"),
+        ),
+        None => peer,
+    }
+}
+
+pub struct ClientIp(pub IpAddr);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ClientIp {
+    type Error = ();
+    async fn from_request(req: &'r Request<'_>) -> Outcome<ClientIp, (rocket::http::Status, ()), ()> {
+        Outcome::Success(ClientIp(client_ip(req)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+
+    use super::TrustedProxies;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_trusted_proxy_ranges() {
+        let trusted = TrustedProxies::parse("10.0.0.0/8, 192.168.1.5, fd00::/8, invalid, 1.2.3.4/33");
+
+        assert!(trusted.contains(&ip("10.20.30.40")));
+        assert!(trusted.contains(&ip("192.168.1.5")));
+        assert!(!trusted.contains(&ip("192.168.1.6")));
+        assert!(trusted.contains(&ip("fd12::1")));
+        assert!(!trusted.contains(&ip("1.2.3.4")));
+        assert!(!TrustedProxies::default().contains(&ip("127.0.0.1")));
+    }
+
+    #[test]
+    fn test_resolve_client_ip() {
+        let trusted = TrustedProxies::parse("10.0.0.0/8");
+        let proxy = ip("10.0.0.2");
+
+        // headers from untrusted peers are ignored
+        assert_eq!(trusted.resolve(ip("8.8.8.8"), Some("1.1.1.1"), Some("1.1.1.1")), ip("8.8.8.8"));
+
+        assert_eq!(trusted.resolve(proxy, Some("1.1.1.1"), None), ip("1.1.1.1"));
+        // a spoofed left-most entry is not trusted
+        assert_eq!(trusted.resolve(proxy, Some("6.6.6.6, 1.1.1.1, 10.0.0.3"), None), ip("1.1.1.1"));
+        assert_eq!(trusted.resolve(proxy, Some("10.0.0.4"), None), ip("10.0.0.4"));
+        assert_eq!(trusted.resolve(proxy, None, Some("2.2.2.2")), ip("2.2.2.2"));
+        assert_eq!(trusted.resolve(proxy, Some("garbage"), Some("2.2.2.2")), ip("2.2.2.2"));
+        assert_eq!(trusted.resolve(proxy, Some("garbage, 10.0.0.3"), Some("2.2.2.2")), ip("2.2.2.2"));
+        assert_eq!(trusted.resolve(proxy, Some("garbage, 10.0.0.3"), None), proxy);
+        assert_eq!(trusted.resolve(proxy, None, None), proxy);
+    }
+}
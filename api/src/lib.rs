@@ -8,6 +8,7 @@ use rocket::http::Header;
 use rocket::{Build, Request, Response, Rocket};
 
 use migration::MigratorTrait;
+use portfolio_core::models::admission_cycle::AdmissionCycle;
 use sea_orm_rocket::Database;
 
 mod guards;
@@ -17,6 +18,7 @@ mod routes;
 mod logging;
 pub mod test;
 
+use guards::request::client_ip::TrustedProxies;
 use pool::Db;
 
 pub use entity::candidate;
@@ -91,6 +93,16 @@ fn setup_logger() -> Result<(), fern::InitError> {
     Ok(())
 }
 
+async fn load_admission_cycle(rocket: Rocket<Build>) -> fairing::Result {
+    match AdmissionCycle::init() {
+        Ok(_) => Ok(rocket),
+        Err(e) => {
+            error!("{}", e);
+            Err(rocket)
+        },
+    }
+}
+
 async fn run_migrations(rocket: Rocket<Build>) -> fairing::Result {
     let conn = &Db::fetch(&rocket).unwrap().conn;
     let _ = migration::Migrator::up(conn, None).await;
@@ -99,35 +111,39 @@ async fn run_migrations(rocket: Rocket<Build>) -> fairing::Result {
 
 pub fn rocket() -> Rocket<Build> {
     rocket::build()
+        .manage(TrustedProxies::from_env())
         .attach(Logging)
         .attach(CORS)
         .attach(Db::init())
+        .attach(AdHoc::try_on_ignite("Admission cycle", load_admission_cycle))
         .attach(AdHoc::try_on_ignite("Migrations", run_migrations))
-        .mount("/", routes![hello, all_options])
+        .mount("/", routes![hello, all_options, routes::timeline::get_timeline])
         .mount(
             "/candidate/",
             routes![
                 routes::candidate::login,
                 routes::candidate::logout,
+                routes::candidate::change_password,
                 routes::candidate::whoami,
                 routes::candidate::get_details,
                 routes::candidate::post_details,
             ],
         )
         .mount(
-            "/candidate/add",
+            "/candidate/documents",
             routes![
-                routes::candidate::upload_portfolio_letter,
-                routes::candidate::upload_portfolio_zip,
-                routes::candidate::upload_cover_letter,
+                routes::candidate::list_documents,
+                routes::candidate::upload_document,
+                routes::candidate::delete_document,
             ],
         )
         .mount(
-            "/candidate/remove",
+            "/candidate/upload",
             routes![
-                routes::candidate::delete_portfolio_letter,
-                routes::candidate::delete_portfolio_zip,
-                routes::candidate::delete_cover_letter,
+                routes::candidate::init_document_upload,
+                routes::candidate::document_upload_status,
+                routes::candidate::upload_document_chunk,
+                routes::candidate::finalize_document_upload,
             ],
         )
         .mount(
@@ -136,6 +152,7 @@ pub fn rocket() -> Rocket<Build> {
                 routes::candidate::submit_portfolio,
                 routes::candidate::submission_progress,
                 routes::candidate::download_portfolio,
+                routes::candidate::list_portfolio_versions,
                 routes::candidate::delete_portfolio,
             ],
         )
@@ -147,18 +164,39 @@ pub fn rocket() -> Rocket<Build> {
                 routes::admin::whoami,
                 routes::admin::hello,
                 routes::admin::create_candidate,
+                routes::admin::import_candidates,
+                routes::admin::credential_letters,
                 routes::admin::get_candidate,
                 routes::admin::reset_candidate_password,
+                routes::admin::extend_candidate_deadline,
                 routes::admin::get_candidate_portfolio,
+                routes::admin::list_candidate_portfolio_versions,
+                routes::admin::verify_portfolio_integrity,
+                routes::admin::verify_submission_receipt,
                 routes::admin::delete_candidate,
+                routes::admin::create_admin,
+                routes::admin::remove_admin,
+                routes::admin::list_audit_logs,
+                routes::admin::unlock_login,
+                routes::admin::list_admission_rounds,
+                routes::admin::list_notifications,
+                routes::admin::send_notifications,
+                routes::admin::list_assigned_reviews,
+                routes::admin::list_application_reviews,
+                routes::admin::assign_reviewer,
+                routes::admin::unassign_reviewer,
+                routes::admin::submit_review,
+                routes::admin::list_scores,
             ],
         )
         .mount(
             "/admin/list",
             routes![
                 routes::admin::list_candidates,
+                routes::admin::search_candidates,
                 routes::admin::list_candidates_csv,
-                routes::admin::list_admissions_csv
+                routes::admin::list_admissions_csv,
+                routes::admin::list_scores_csv
             ]
         )
         .register("/", catchers![])
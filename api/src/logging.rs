@@ -1,8 +1,8 @@
-use std::net::Ipv4Addr;
-
 use log::info;
 use rocket::{fairing::{Fairing, Info, Kind}, Request, Data};
 
+use crate::guards::request::client_ip::client_ip;
+
 pub struct Logging;
 
 #[rocket::async_trait]
@@ -22,7 +22,7 @@ impl Fairing for Logging {
 }
 
 pub fn format_request(request: &Request<'_>) -> String {
-    let client_ip = request.client_ip().unwrap_or(std::net::IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1))).to_string();
+    let client_ip = client_ip(request).to_string();
 
         let method = request.method().to_string();
 
@@ -1,3 +1,5 @@
+use chrono::NaiveDateTime;
+use portfolio_core::models::review::ReviewScores;
 use rocket::serde::{Serialize, Deserialize};
 
 
@@ -8,6 +10,13 @@ pub struct LoginRequest {
     pub password: String,
 }
 
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde", rename_all = "camelCase")]
+pub struct ChangePasswordRequest {
+    pub old_password: String,
+    pub new_password: String,
+}
+
 #[derive(Serialize, Deserialize)]
 #[serde(crate = "rocket::serde", rename_all = "camelCase")]
 pub struct RegisterRequest {
@@ -21,4 +30,59 @@ pub struct RegisterRequest {
 pub struct AdminLoginRequest {
     pub admin_id: i32,
     pub password: String,
-}
\ No newline at end of file
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde", rename_all = "camelCase")]
+pub struct CreateAdminRequest {
+    pub name: String,
+    pub password: String,
+    pub role: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde", rename_all = "camelCase")]
+pub struct DeadlineExtensionRequest {
+    /// `None` removes the extension
+    pub until: Option<NaiveDateTime>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde", rename_all = "camelCase")]
+pub struct UploadInitRequest {
+    /// Size of the whole file in bytes
+    pub size: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde", rename_all = "camelCase")]
+pub struct UploadFinalizeRequest {
+    /// SHA-256 of the whole file, hex encoded
+    pub sha256: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde", rename_all = "camelCase")]
+pub struct AssignReviewerRequest {
+    pub admin_id: i32,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde", rename_all = "camelCase")]
+pub struct ReviewRequest {
+    /// Points per criterion of the program rubric
+    pub scores: ReviewScores,
+    pub comment: Option<String>,
+}
+
+/// Audit log filter in the query string, `from` and `to` are parsed as `NaiveDateTime`
+#[derive(FromForm)]
+pub struct AuditLogQuery {
+    pub actor: Option<String>,
+    pub actor_id: Option<i32>,
+    pub action: Option<String>,
+    pub target: Option<i32>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub page: Option<u64>,
+}
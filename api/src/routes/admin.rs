@@ -1,18 +1,19 @@
-use std::net::{SocketAddr, IpAddr, Ipv4Addr};
-
 use portfolio_core::{
     crypto::random_12_char_string,
-    services::{admin_service::AdminService, application_service::ApplicationService, portfolio_service::PortfolioService}, models::{candidate::{CreateCandidateResponse, ApplicationDetails}, auth::AuthenticableTrait, application::ApplicationResponse}, sea_orm::prelude::Uuid, Query, error::ServiceError,
+    services::{admin_service::AdminService, admission_round_service::AdmissionRoundService, import_service::ImportService, letter_service::LetterService, notification_service::NotificationService, search_service::SearchService, audit_service::AuditService, application_service::ApplicationService, login_throttle_service::LoginThrottleService, portfolio_service::PortfolioService, timeline_service::TimelineService, review_service::ReviewService}, models::{admission_cycle::AdmissionCycle, admission_round::AdmissionRoundResponse, import::ImportRowResult, credential_letter::CredentialLetter, notification::NotificationResponse, portfolio_version::{IntegrityReport, PortfolioVersionResponse}, receipt::{ReceiptKey, SubmissionReceipt}, review::{ReviewAssignmentResponse, ReviewResponse, ScoreSummary}, admin::{AdminResponse, AdminRole}, audit::{AuditLogFilter, AuditLogResponse}, login_attempt::LoginScope, candidate::{CreateCandidateResponse, ApplicationDetails}, auth::AuthenticableTrait, application::ApplicationResponse}, sea_orm::prelude::Uuid, Query, error::ServiceError,
 };
-use requests::{AdminLoginRequest, RegisterRequest};
-use rocket::http::{Cookie, Status, CookieJar};
+use chrono::NaiveDateTime;
+use requests::{AdminLoginRequest, AssignReviewerRequest, AuditLogQuery, CreateAdminRequest, DeadlineExtensionRequest, RegisterRequest, ReviewRequest};
+use rocket::http::{ContentType, Cookie, Status, CookieJar};
 use rocket::response::status::Custom;
+use rocket::response::stream::{One, ReaderStream};
+use rocket::tokio::io::AsyncRead;
 use rocket::serde::json::Json;
 
 use sea_orm_rocket::Connection;
-use portfolio_core::utils::csv::{ApplicationCsv, CandidateCsv, CsvExporter};
+use portfolio_core::utils::csv::{ApplicationCsv, CandidateCsv, CsvExporter, ScoreCsv};
 
-use crate::{guards::request::{auth::AdminAuth}, pool::Db, requests};
+use crate::{guards::{data::csv_file::CsvFile, request::{auth::{AdminAuth, RegistrarAuth, SuperadminAuth}, client_ip::ClientIp}}, pool::Db, requests};
 
 use super::to_custom_error;
 
@@ -20,16 +21,15 @@ use super::to_custom_error;
 pub async fn login(
     conn: Connection<'_, Db>,
     login_form: Json<AdminLoginRequest>,
-    // ip_addr: SocketAddr, // TODO uncomment in production
+    ip_addr: ClientIp,
     cookies: &CookieJar<'_>,
 ) -> Result<(), Custom<String>> {
-    let ip_addr: SocketAddr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 0);
     let db = conn.into_inner();
     let session_token_key = AdminService::login(
         db,
         login_form.admin_id,
         login_form.password.to_string(),
-        ip_addr.ip().to_string(),
+        ip_addr.0.to_string(),
     )
     .await;
 
@@ -86,7 +86,7 @@ pub async fn hello(_session: AdminAuth) -> Result<String, Custom<String>> {
 #[post("/create", data = "<request>")]
 pub async fn create_candidate(
     conn: Connection<'_, Db>,
-    session: AdminAuth,
+    session: RegistrarAuth,
     request: Json<RegisterRequest>,
 ) -> Result<Json<CreateCandidateResponse>, Custom<String>> {
     let db = conn.into_inner();
@@ -120,24 +120,194 @@ pub async fn create_candidate(
     )
 }
 
+/// Creates candidates from CSV with `Ev. č. přihlášky` and `Rodné číslo` columns, results contain generated passwords
+#[post("/import?<dry_run>", data = "<csv>")]
+pub async fn import_candidates(
+    conn: Connection<'_, Db>,
+    session: RegistrarAuth,
+    dry_run: Option<bool>,
+    csv: CsvFile,
+) -> Result<Json<Vec<ImportRowResult>>, Custom<String>> {
+    let db = conn.into_inner();
+    let private_key = session.get_private_key();
+    let csv: Vec<u8> = csv.into();
+
+    let results = ImportService::import_csv(db, &private_key, &csv, dry_run.unwrap_or(false))
+        .await
+        .map_err(to_custom_error)?;
+
+    Ok(
+        Json(results)
+    )
+}
+
+/// Printable PDF with one credential letter per application, accepts responses of create, reset password and import
+#[post("/letters", data = "<request>")]
+pub async fn credential_letters(
+    conn: Connection<'_, Db>,
+    _session: RegistrarAuth,
+    request: Json<Vec<CredentialLetter>>,
+) -> Result<(ContentType, Vec<u8>), Custom<String>> {
+    let db = conn.into_inner();
+
+    let pdf = LetterService::credential_letters(db, &request.into_inner())
+        .await
+        .map_err(to_custom_error)?;
+
+    Ok((ContentType::PDF, pdf))
+}
+
+#[post("/admin", data = "<request>")]
+pub async fn create_admin(
+    conn: Connection<'_, Db>,
+    session: SuperadminAuth,
+    request: Json<CreateAdminRequest>,
+) -> Result<Json<AdminResponse>, Custom<String>> {
+    let db = conn.into_inner();
+    let form = request.into_inner();
+    let private_key = session.get_private_key();
+
+    let role = AdminRole::try_from(form.role.as_str()).map_err(to_custom_error)?;
+
+    let admin = AdminService::create_admin(db, private_key, form.name, form.password, role)
+        .await
+        .map_err(to_custom_error)?;
+
+    Ok(
+        Json(admin.into())
+    )
+}
+
+#[delete("/admin/<id>")]
+pub async fn remove_admin(
+    conn: Connection<'_, Db>,
+    session: SuperadminAuth,
+    id: i32,
+) -> Result<(), Custom<String>> {
+    let db = conn.into_inner();
+    let private_key = session.get_private_key();
+
+    AdminService::remove_admin(db, private_key, id)
+        .await
+        .map_err(to_custom_error)
+}
+
+#[delete("/lockout/<scope>/<key>")]
+pub async fn unlock_login(
+    conn: Connection<'_, Db>,
+    _session: SuperadminAuth,
+    scope: String,
+    key: String,
+) -> Result<(), Custom<String>> {
+    let db = conn.into_inner();
+    let scope = LoginScope::try_from(scope.as_str()).map_err(to_custom_error)?;
+
+    LoginThrottleService::unlock(db, scope, &key)
+        .await
+        .map_err(to_custom_error)
+}
+
+/// `from` and `to` are local times, e.g. `2023-05-01T08:00:00`
+#[get("/audit?<query..>")]
+pub async fn list_audit_logs(
+    conn: Connection<'_, Db>,
+    _session: SuperadminAuth,
+    query: AuditLogQuery,
+) -> Result<Json<Vec<AuditLogResponse>>, Custom<String>> {
+    let db = conn.into_inner();
+    let parse_time = |time: Option<String>| time
+        .map(|t| t.parse::<NaiveDateTime>())
+        .transpose()
+        .map_err(|_| to_custom_error(ServiceError::InvalidAuditFilter));
+    let filter = AuditLogFilter {
+        actor_type: query.actor,
+        actor_id: query.actor_id,
+        action: query.action,
+        target_id: query.target,
+        from: parse_time(query.from)?,
+        to: parse_time(query.to)?,
+    };
+
+    let logs = AuditService::list(db, filter, query.page)
+        .await
+        .map_err(to_custom_error)?;
+
+    Ok(
+        Json(logs)
+    )
+}
+
+#[get("/rounds")]
+pub async fn list_admission_rounds(
+    conn: Connection<'_, Db>,
+    _session: AdminAuth,
+) -> Result<Json<Vec<AdmissionRoundResponse>>, Custom<String>> {
+    let db = conn.into_inner();
+
+    let rounds = AdmissionRoundService::list(db)
+        .await
+        .map_err(to_custom_error)?;
+
+    Ok(
+        Json(rounds)
+    )
+}
+
+/// Notification outbox, optionally filtered by status (`pending`, `sent` or `failed`)
+#[get("/notifications?<status>")]
+pub async fn list_notifications(
+    conn: Connection<'_, Db>,
+    _session: AdminAuth,
+    status: Option<String>,
+) -> Result<Json<Vec<NotificationResponse>>, Custom<String>> {
+    let db = conn.into_inner();
+
+    let notifications = NotificationService::list(db, status)
+        .await
+        .map_err(to_custom_error)?;
+
+    Ok(
+        Json(notifications)
+    )
+}
+
+/// Retries pending notifications, recipients are decrypted with the admin key. Returns number of sent emails
+#[post("/notifications/send")]
+pub async fn send_notifications(
+    conn: Connection<'_, Db>,
+    session: RegistrarAuth,
+) -> Result<Json<usize>, Custom<String>> {
+    let db = conn.into_inner();
+    let private_key = session.get_private_key();
+
+    let sent = NotificationService::send_pending(db, &private_key, None)
+        .await
+        .map_err(to_custom_error)?;
+
+    Ok(
+        Json(sent)
+    )
+}
+
 #[allow(unused_variables)]
-#[get("/candidates?<field>&<page>&<sort>")]
+#[get("/candidates?<field>&<round>&<page>&<sort>")]
 pub async fn list_candidates(
     conn: Connection<'_, Db>,
     session: AdminAuth,
     field: Option<String>,
+    round: Option<i32>,
     page: Option<u64>, 
     sort: Option<String>,
 ) -> Result<Json<Vec<ApplicationResponse>>, Custom<String>> {
     let db = conn.into_inner();
     let private_key = session.get_private_key();
     if let Some(field) = field.clone() {
-        if !(field == "KB".to_string() || field == "IT".to_string() || field == "G") {
+        if AdmissionCycle::get().program_by_code(&field).is_none() {
             return Err(Custom(Status::BadRequest, "Invalid field of study".to_string()));
         }
     }
 
-    let candidates = ApplicationService::list_applications(&private_key, db, field, page, sort)
+    let candidates = ApplicationService::list_applications(&private_key, db, field, round, page, sort)
         .await.map_err(to_custom_error)?;
 
     Ok(
@@ -145,6 +315,27 @@ pub async fn list_candidates(
     )
 }
 
+#[get("/search?<q>&<field>&<round>&<page>")]
+pub async fn search_candidates(
+    conn: Connection<'_, Db>,
+    session: AdminAuth,
+    q: String,
+    field: Option<String>,
+    round: Option<i32>,
+    page: Option<u64>,
+) -> Result<Json<Vec<ApplicationResponse>>, Custom<String>> {
+    let db = conn.into_inner();
+    let private_key = session.get_private_key();
+
+    let candidates = SearchService::search(db, session.session_id(), &private_key, &q, field, round, page)
+        .await
+        .map_err(to_custom_error)?;
+
+    Ok(
+        Json(candidates)
+    )
+}
+
 #[get("/candidates_csv")]
 pub async fn list_candidates_csv(
     conn: Connection<'_, Db>,
@@ -179,6 +370,23 @@ pub async fn list_admissions_csv(
     )
 }
 
+#[get("/scores_csv")]
+pub async fn list_scores_csv(
+    conn: Connection<'_, Db>,
+    session: RegistrarAuth,
+) -> Result<Vec<u8>, Custom<String>> {
+    let db = conn.into_inner();
+    let private_key = session.get_private_key();
+
+    let scores = ScoreCsv::export(db, private_key)
+        .await
+        .map_err(to_custom_error)?;
+
+    Ok(
+        scores
+    )
+}
+
 #[get("/candidate/<id>")]
 pub async fn get_candidate(
     conn: Connection<'_, Db>,
@@ -196,7 +404,8 @@ pub async fn get_candidate(
     let details = ApplicationService::decrypt_all_details(
         private_key,
         db,
-        &application
+        &application,
+        &session.audit_context(),
     )
         .await
         .map_err(to_custom_error)?;
@@ -209,7 +418,7 @@ pub async fn get_candidate(
 #[delete("/candidate/<id>")]
 pub async fn delete_candidate(
     conn: Connection<'_, Db>,
-    _session: AdminAuth,
+    session: RegistrarAuth,
     id: i32,
 ) -> Result<(), Custom<String>> {
     let db = conn.into_inner();
@@ -220,7 +429,7 @@ pub async fn delete_candidate(
         .ok_or(to_custom_error(ServiceError::CandidateNotFound))?;
 
 
-    ApplicationService::delete(db, application)
+    ApplicationService::delete(db, application, &session.audit_context())
         .await
         .map_err(to_custom_error)
 
@@ -229,14 +438,14 @@ pub async fn delete_candidate(
 #[post("/candidate/<id>/reset_password")]
 pub async fn reset_candidate_password(
     conn: Connection<'_, Db>,
-    session: AdminAuth,
+    session: RegistrarAuth,
     id: i32,
 ) -> Result<Json<CreateCandidateResponse>, Custom<String>> {
     // TODO
     let db = conn.into_inner();
     let private_key = session.get_private_key();
 
-    let response = ApplicationService::reset_password(private_key, db, id)
+    let response = ApplicationService::reset_password(private_key, db, id, &session.audit_context())
         .await
         .map_err(to_custom_error)?;
     
@@ -245,12 +454,77 @@ pub async fn reset_candidate_password(
     )
 }
 
-#[get("/candidate/<id>/portfolio")]
+#[put("/candidate/<id>/extension", data = "<request>")]
+pub async fn extend_candidate_deadline(
+    conn: Connection<'_, Db>,
+    _session: RegistrarAuth,
+    id: i32,
+    request: Json<DeadlineExtensionRequest>,
+) -> Result<(), Custom<String>> {
+    let db = conn.into_inner();
+
+    TimelineService::set_extension(db, id, request.until)
+        .await
+        .map_err(to_custom_error)
+}
+
+#[get("/candidate/<id>/portfolio/versions")]
+pub async fn list_candidate_portfolio_versions(
+    conn: Connection<'_, Db>,
+    _session: AdminAuth,
+    id: i32,
+) -> Result<Json<Vec<PortfolioVersionResponse>>, Custom<String>> {
+    let db = conn.into_inner();
+
+    let application = Query::find_application_by_id(db, id)
+        .await
+        .map_err(|e| to_custom_error(ServiceError::DbError(e)))?
+        .ok_or(to_custom_error(ServiceError::CandidateNotFound))?;
+
+    let versions = PortfolioService::list_versions(db, application.candidate_id)
+        .await
+        .map_err(to_custom_error)?;
+
+    Ok(Json(versions))
+}
+
+/// Checks every stored portfolio version against its manifest
+#[get("/integrity")]
+pub async fn verify_portfolio_integrity(
+    conn: Connection<'_, Db>,
+    _session: AdminAuth,
+) -> Result<Json<Vec<IntegrityReport>>, Custom<String>> {
+    let db = conn.into_inner();
+    let store = portfolio_core::storage::from_env().map_err(to_custom_error)?;
+
+    let reports = PortfolioService::verify_integrity(db, &*store)
+        .await
+        .map_err(to_custom_error)?;
+
+    Ok(Json(reports))
+}
+
+/// Tells whether a receipt shown by a candidate was issued by this server and not altered
+#[post("/receipt/verify", data = "<receipt>")]
+pub async fn verify_submission_receipt(
+    _session: AdminAuth,
+    receipt: Json<SubmissionReceipt>,
+) -> Result<Json<bool>, Custom<String>> {
+    let key = ReceiptKey::from_env()
+        .map_err(to_custom_error)?
+        .ok_or(to_custom_error(ServiceError::ReceiptConfigError("PORTFOLIO_RECEIPT_KEY is not set".to_string())))?;
+
+    Ok(Json(key.verify(&receipt)))
+}
+
+/// Submitted portfolio, or any stored version with `version`
+#[get("/candidate/<id>/portfolio?<version>")]
 pub async fn get_candidate_portfolio(
     conn: Connection<'_, Db>,
     session: AdminAuth, 
     id: i32,
-) -> Result<Vec<u8>, Custom<String>> {
+    version: Option<i32>,
+) -> Result<(ContentType, ReaderStream<One<impl AsyncRead + Send>>), Custom<String>> {
     let db = conn.into_inner();
     let private_key = session.get_private_key();
 
@@ -259,19 +533,119 @@ pub async fn get_candidate_portfolio(
         .map_err(|e| to_custom_error(ServiceError::DbError(e)))?
         .ok_or(to_custom_error(ServiceError::CandidateNotFound))?;
 
-    let portfolio = PortfolioService::get_portfolio(application.candidate_id, private_key)
+    let portfolio = PortfolioService::download_portfolio(db, &application, version, private_key, &session.audit_context())
+        .await
+        .map_err(to_custom_error)?;
+
+    Ok((ContentType::Binary, ReaderStream::one(portfolio)))
+}
+
+/// Applications the logged in admin was assigned to review
+#[get("/reviews")]
+pub async fn list_assigned_reviews(
+    conn: Connection<'_, Db>,
+    session: AdminAuth,
+) -> Result<Json<Vec<ReviewAssignmentResponse>>, Custom<String>> {
+    let db = conn.into_inner();
+
+    let assignments = ReviewService::list_assigned(db, session.admin_id())
+        .await
+        .map_err(to_custom_error)?;
+
+    Ok(
+        Json(assignments)
+    )
+}
+
+#[get("/reviews/<id>")]
+pub async fn list_application_reviews(
+    conn: Connection<'_, Db>,
+    session: RegistrarAuth,
+    id: i32,
+) -> Result<Json<Vec<ReviewResponse>>, Custom<String>> {
+    let db = conn.into_inner();
+    let private_key = session.get_private_key();
+
+    let reviews = ReviewService::list_reviews(db, &private_key, id)
+        .await
+        .map_err(to_custom_error)?;
+
+    Ok(
+        Json(reviews)
+    )
+}
+
+#[post("/reviews/<id>/reviewers", data = "<request>")]
+pub async fn assign_reviewer(
+    conn: Connection<'_, Db>,
+    session: RegistrarAuth,
+    id: i32,
+    request: Json<AssignReviewerRequest>,
+) -> Result<(), Custom<String>> {
+    let db = conn.into_inner();
+
+    ReviewService::assign(db, id, request.admin_id, &session.audit_context())
         .await
         .map_err(to_custom_error)?;
 
-    Ok(portfolio)
+    Ok(())
+}
+
+#[delete("/reviews/<id>/reviewers/<admin_id>")]
+pub async fn unassign_reviewer(
+    conn: Connection<'_, Db>,
+    session: RegistrarAuth,
+    id: i32,
+    admin_id: i32,
+) -> Result<(), Custom<String>> {
+    let db = conn.into_inner();
+
+    ReviewService::unassign(db, id, admin_id, &session.audit_context())
+        .await
+        .map_err(to_custom_error)
+}
+
+/// Scores the application by the rubric of its program, only assigned admins can review
+#[put("/reviews/<id>", data = "<request>")]
+pub async fn submit_review(
+    conn: Connection<'_, Db>,
+    session: AdminAuth,
+    id: i32,
+    request: Json<ReviewRequest>,
+) -> Result<(), Custom<String>> {
+    let db = conn.into_inner();
+    let request = request.into_inner();
+
+    ReviewService::submit(db, session.admin_id(), id, request.scores, request.comment, &session.audit_context())
+        .await
+        .map_err(to_custom_error)?;
+
+    Ok(())
+}
+
+#[get("/scores")]
+pub async fn list_scores(
+    conn: Connection<'_, Db>,
+    session: RegistrarAuth,
+) -> Result<Json<Vec<ScoreSummary>>, Custom<String>> {
+    let db = conn.into_inner();
+    let private_key = session.get_private_key();
+
+    let scores = ReviewService::summaries(db, &private_key)
+        .await
+        .map_err(to_custom_error)?;
+
+    Ok(
+        Json(scores)
+    )
 }
 
 #[cfg(test)]
 pub mod tests {
-    use portfolio_core::models::candidate::CreateCandidateResponse;
-    use rocket::{local::blocking::Client, http::{Cookie, Status}};
+    use portfolio_core::models::{admin::AdminResponse, admission_round::AdmissionRoundResponse, application::ApplicationResponse, audit::AuditLogResponse, candidate::CreateCandidateResponse, credential_letter::CredentialLetter, import::{ImportRowResult, ImportStatus}, notification::NotificationResponse, portfolio_version::IntegrityReport, review::{ReviewAssignmentResponse, ScoreSummary}};
+    use rocket::{local::blocking::Client, http::{ContentType, Cookie, Status}};
 
-    use crate::test::tests::{test_client, ADMIN_PASSWORD, ADMIN_ID};
+    use crate::test::tests::{test_client, ADMIN_PASSWORD, ADMIN_ID, APPLICATION_ID};
 
     pub fn admin_login(client: &Client) -> (Cookie, Cookie) {
         let response = client
@@ -291,7 +665,7 @@ pub mod tests {
         )
     }
 
-    fn create_candidate(
+    pub fn create_candidate(
         client: &Client,
         cookies: (Cookie, Cookie),
         id: i32,
@@ -315,6 +689,322 @@ pub mod tests {
         response.into_json::<CreateCandidateResponse>().unwrap()
     }
 
+    #[test]
+    fn test_create_and_remove_admin() {
+        let client = test_client().lock().unwrap();
+        let cookies = admin_login(&client);
+
+        let response = client
+            .post("/admin/admin")
+            .body("{\"name\": \"admin franta\", \"password\": \"franta\", \"role\": \"registrar\"}")
+            .cookie(cookies.0.clone())
+            .cookie(cookies.1.clone())
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let admin = response.into_json::<AdminResponse>().unwrap();
+        assert_eq!(admin.name, "admin franta");
+        assert_eq!(admin.role, "registrar");
+
+        let response = client
+            .delete(format!("/admin/admin/{}", admin.id))
+            .cookie(cookies.0)
+            .cookie(cookies.1)
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+    }
+
+    #[test]
+    fn test_login_lockout_and_unlock() {
+        let client = test_client().lock().unwrap();
+        let login = |client: &Client| client
+            .post("/candidate/login")
+            .body("{\"applicationId\": 103999, \"password\": \"wrong\"}")
+            .dispatch()
+            .status();
+
+        for _ in 0..5 {
+            assert_eq!(login(&client), Status::NotFound);
+        }
+        assert_eq!(login(&client), Status::Locked);
+
+        let cookies = admin_login(&client);
+        let response = client
+            .delete("/admin/lockout/application/103999")
+            .cookie(cookies.0.clone())
+            .cookie(cookies.1.clone())
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(login(&client), Status::NotFound);
+
+        let response = client
+            .delete("/admin/lockout/ip/127.0.0.1")
+            .cookie(cookies.0.clone())
+            .cookie(cookies.1.clone())
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+
+        let response = client
+            .delete("/admin/lockout/account/103999")
+            .cookie(cookies.0)
+            .cookie(cookies.1)
+            .dispatch();
+        assert_eq!(response.status(), Status::BadRequest);
+    }
+
+    #[test]
+    fn test_list_audit_logs() {
+        let client = test_client().lock().unwrap();
+        let cookies = admin_login(&client);
+
+        let response = client
+            .get(format!("/admin/audit?actor=admin&actor_id={}&action=login", ADMIN_ID))
+            .cookie(cookies.0.clone())
+            .cookie(cookies.1.clone())
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let logs = response.into_json::<Vec<AuditLogResponse>>().unwrap();
+        assert!(!logs.is_empty());
+        assert!(logs.iter().all(|l| l.actor_id == ADMIN_ID && l.action == "login"));
+
+        let response = client
+            .get("/admin/audit?action=login&from=2099-01-01T00:00:00")
+            .cookie(cookies.0.clone())
+            .cookie(cookies.1.clone())
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        assert!(response.into_json::<Vec<AuditLogResponse>>().unwrap().is_empty());
+
+        let response = client
+            .get("/admin/audit?to=yesterday")
+            .cookie(cookies.0.clone())
+            .cookie(cookies.1.clone())
+            .dispatch();
+        assert_eq!(response.status(), Status::BadRequest);
+
+        let response = client
+            .get("/admin/audit?action=sudo")
+            .cookie(cookies.0)
+            .cookie(cookies.1)
+            .dispatch();
+        assert_eq!(response.status(), Status::BadRequest);
+    }
+
+    #[test]
+    fn test_list_admission_rounds() {
+        let client = test_client().lock().unwrap();
+        let cookies = admin_login(&client);
+
+        let response = client
+            .get("/admin/rounds")
+            .cookie(cookies.0)
+            .cookie(cookies.1)
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let rounds = response.into_json::<Vec<AdmissionRoundResponse>>().unwrap();
+        assert_eq!(rounds.iter().filter(|r| r.closed_at.is_none()).count(), 1);
+    }
+
+    #[test]
+    fn test_list_notifications() {
+        let client = test_client().lock().unwrap();
+        let cookies = admin_login(&client);
+
+        let response = client
+            .get("/admin/notifications?status=pending")
+            .cookie(cookies.0.clone())
+            .cookie(cookies.1.clone())
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        assert!(response.into_json::<Vec<NotificationResponse>>().is_some());
+
+        let response = client
+            .get("/admin/notifications?status=unknown")
+            .cookie(cookies.0)
+            .cookie(cookies.1)
+            .dispatch();
+        assert_eq!(response.status(), Status::BadRequest);
+    }
+
+    #[test]
+    fn test_extend_candidate_deadline() {
+        let client = test_client().lock().unwrap();
+        let cookies = admin_login(&client);
+
+        create_candidate(&client, cookies.clone(), 101154, "0202021234".to_string());
+
+        let response = client
+            .put("/admin/candidate/101154/extension")
+            .cookie(cookies.0.clone())
+            .cookie(cookies.1.clone())
+            .body("{\"until\": \"2099-01-01T12:00:00\"}")
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+
+        let response = client
+            .put("/admin/candidate/101999/extension")
+            .cookie(cookies.0)
+            .cookie(cookies.1)
+            .body("{\"until\": null}")
+            .dispatch();
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    #[test]
+    fn test_verify_portfolio_integrity() {
+        let client = test_client().lock().unwrap();
+        let cookies = admin_login(&client);
+
+        let response = client
+            .get("/admin/integrity")
+            .cookie(cookies.0)
+            .cookie(cookies.1)
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        assert!(response.into_json::<Vec<IntegrityReport>>().is_some());
+    }
+
+    #[test]
+    fn test_search_candidates() {
+        let client = test_client().lock().unwrap();
+        let cookies = admin_login(&client);
+
+        create_candidate(&client, cookies.clone(), 102153, "0101011234".to_string());
+
+        let response = client
+            .get("/admin/list/search?q=0101011234")
+            .cookie(cookies.0.clone())
+            .cookie(cookies.1.clone())
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let candidates = response.into_json::<Vec<ApplicationResponse>>().unwrap();
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].application_id, 102153);
+
+        let response = client
+            .get("/admin/list/search?q=%20")
+            .cookie(cookies.0)
+            .cookie(cookies.1)
+            .dispatch();
+        assert_eq!(response.status(), Status::BadRequest);
+    }
+
+    #[test]
+    fn test_reviewer_permissions() {
+        let client = test_client().lock().unwrap();
+        let cookies = admin_login(&client);
+
+        let response = client
+            .post("/admin/admin")
+            .body("{\"name\": \"admin karel\", \"password\": \"karel\", \"role\": \"reviewer\"}")
+            .cookie(cookies.0.clone())
+            .cookie(cookies.1.clone())
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let reviewer = response.into_json::<AdminResponse>().unwrap();
+
+        let response = client
+            .post("/admin/login")
+            .body(format!("{{\"adminId\": {}, \"password\": \"karel\"}}", reviewer.id))
+            .dispatch();
+        let reviewer_cookies = (
+            response.cookies().get("id").unwrap().to_owned(),
+            response.cookies().get("key").unwrap().to_owned(),
+        );
+
+        let response = client
+            .get("/admin/list/candidates")
+            .cookie(reviewer_cookies.0.clone())
+            .cookie(reviewer_cookies.1.clone())
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+
+        for response in [
+            client.delete(format!("/admin/candidate/{}", APPLICATION_ID)),
+            client.post(format!("/admin/candidate/{}/reset_password", APPLICATION_ID)),
+            client.delete(format!("/admin/admin/{}", ADMIN_ID)),
+            client.post("/admin/notifications/send"),
+        ] {
+            let response = response
+                .cookie(reviewer_cookies.0.clone())
+                .cookie(reviewer_cookies.1.clone())
+                .dispatch();
+            assert_eq!(response.status(), Status::Forbidden);
+        }
+
+        let response = client
+            .delete(format!("/admin/admin/{}", reviewer.id))
+            .cookie(cookies.0)
+            .cookie(cookies.1)
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+    }
+
+    #[test]
+    fn test_portfolio_reviews() {
+        let client = test_client().lock().unwrap();
+        let cookies = admin_login(&client);
+
+        create_candidate(&client, cookies.clone(), 101156, "0303031234".to_string());
+
+        let response = client
+            .post("/admin/reviews/101156/reviewers")
+            .body(format!("{{\"adminId\": {}}}", ADMIN_ID))
+            .cookie(cookies.0.clone())
+            .cookie(cookies.1.clone())
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+
+        let response = client
+            .get("/admin/reviews")
+            .cookie(cookies.0.clone())
+            .cookie(cookies.1.clone())
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let assignments = response.into_json::<Vec<ReviewAssignmentResponse>>().unwrap();
+        assert!(assignments.iter().any(|a| a.application_id == 101156));
+
+        // Portfolio was not submitted yet
+        let response = client
+            .put("/admin/reviews/101156")
+            .body("{\"scores\": {\"overall\": 5}}")
+            .cookie(cookies.0.clone())
+            .cookie(cookies.1.clone())
+            .dispatch();
+        assert_eq!(response.status(), Status::NotAcceptable);
+
+        let response = client
+            .put(format!("/admin/reviews/{}", APPLICATION_ID))
+            .body("{\"scores\": {\"overall\": 5}}")
+            .cookie(cookies.0.clone())
+            .cookie(cookies.1.clone())
+            .dispatch();
+        assert_eq!(response.status(), Status::Forbidden);
+
+        let response = client
+            .get("/admin/scores")
+            .cookie(cookies.0.clone())
+            .cookie(cookies.1.clone())
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let scores = response.into_json::<Vec<ScoreSummary>>().unwrap();
+        assert!(scores.iter().any(|s| s.application_id == 101156 && s.total.is_none()));
+
+        let response = client
+            .get("/admin/list/scores_csv")
+            .cookie(cookies.0.clone())
+            .cookie(cookies.1.clone())
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        assert!(response.into_string().unwrap().starts_with("Číslo uchazeče (přiděleno systémem)"));
+
+        let response = client
+            .delete(format!("/admin/reviews/101156/reviewers/{}", ADMIN_ID))
+            .cookie(cookies.0)
+            .cookie(cookies.1)
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+    }
+
     #[test]
     fn test_create_candidate() {
         let client = test_client().lock().unwrap();
@@ -323,4 +1013,48 @@ pub mod tests {
     
         assert_eq!(response.password.len(), 12);
     }
+
+    #[test]
+    fn test_import_candidates() {
+        let client = test_client().lock().unwrap();
+        let cookies = admin_login(&client);
+        let csv = "applicationId,personalIdNumber\n101201,0505051234\n102201,0505051234\n9,0505051235\n";
+
+        let response = client
+            .post("/admin/import?dry_run=true")
+            .header(ContentType::CSV)
+            .body(csv)
+            .cookie(cookies.0.clone())
+            .cookie(cookies.1.clone())
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let results = response.into_json::<Vec<ImportRowResult>>().unwrap();
+        assert_eq!(results.iter().map(|r| r.status).collect::<Vec<_>>(), vec![ImportStatus::Valid, ImportStatus::Valid, ImportStatus::Error]);
+
+        let response = client
+            .post("/admin/import")
+            .header(ContentType::CSV)
+            .body(csv)
+            .cookie(cookies.0.clone())
+            .cookie(cookies.1.clone())
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let results = response.into_json::<Vec<ImportRowResult>>().unwrap();
+        assert_eq!(results.iter().map(|r| r.status).collect::<Vec<_>>(), vec![ImportStatus::Created, ImportStatus::Linked, ImportStatus::Error]);
+        assert_eq!(results[0].password.as_ref().unwrap().len(), 12);
+
+        let letters: Vec<CredentialLetter> = results.iter()
+            .filter_map(CredentialLetter::from_import_result)
+            .collect();
+        assert_eq!(letters.len(), 2);
+        let response = client
+            .post("/admin/letters")
+            .body(serde_json::to_string(&letters).unwrap())
+            .cookie(cookies.0)
+            .cookie(cookies.1)
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.content_type(), Some(ContentType::PDF));
+        assert!(response.into_bytes().unwrap().starts_with(b"%PDF"));
+    }
 }
\ No newline at end of file
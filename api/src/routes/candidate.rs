@@ -1,23 +1,29 @@
-use std::net::{IpAddr, Ipv4Addr, SocketAddr};
-
 use entity::application;
 use portfolio_core::Query;
 use portfolio_core::error::ServiceError;
 use portfolio_core::models::auth::AuthenticableTrait;
 use portfolio_core::models::candidate::{ApplicationDetails, NewCandidateResponse};
+use portfolio_core::models::document::DocumentStatus;
+use portfolio_core::models::portfolio_version::PortfolioVersionResponse;
+use portfolio_core::models::receipt::SubmissionReceipt;
+use portfolio_core::models::upload::UploadResponse;
 use portfolio_core::sea_orm::prelude::Uuid;
 use portfolio_core::services::application_service::ApplicationService;
-use portfolio_core::services::portfolio_service::{PortfolioService, SubmissionProgress};
-use requests::LoginRequest;
-use rocket::http::{Cookie, CookieJar, Status};
+use portfolio_core::services::notification_service::NotificationService;
+use portfolio_core::services::portfolio_service::{PortfolioService, SubmissionStatus};
+use portfolio_core::services::upload_service::UploadService;
+use requests::{ChangePasswordRequest, LoginRequest, UploadFinalizeRequest, UploadInitRequest};
+use rocket::http::{ContentType, Cookie, CookieJar, Status};
 use rocket::response::status::Custom;
+use rocket::response::stream::{One, ReaderStream};
+use rocket::tokio::io::AsyncRead;
 use rocket::serde::json::Json;
 
 use sea_orm_rocket::Connection;
 
-use crate::guards::data::letter::Letter;
-use crate::guards::data::portfolio::Portfolio;
-use crate::{guards::request::auth::ApplicationAuth, pool::Db, requests};
+use crate::guards::data::chunk::Chunk;
+use crate::guards::data::document::Document;
+use crate::{guards::request::{auth::ApplicationAuth, client_ip::ClientIp}, pool::Db, requests};
 
 use super::to_custom_error;
 
@@ -25,16 +31,15 @@ use super::to_custom_error;
 pub async fn login(
     conn: Connection<'_, Db>,
     login_form: Json<LoginRequest>,
-    // ip_addr: SocketAddr, // TODO uncomment in production
+    ip_addr: ClientIp,
     cookies: &CookieJar<'_>,
 ) -> Result<(), Custom<String>> {
-    let ip_addr: SocketAddr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 0);
     let db = conn.into_inner();
     let (session_token, private_key) = ApplicationService::login(
         db,
         login_form.application_id,
         login_form.password.to_string(),
-        ip_addr.ip().to_string(),
+        ip_addr.0.to_string(),
     )
     .await
     .map_err(to_custom_error)?;
@@ -72,12 +77,38 @@ pub async fn logout(
     Ok(())
 }
 
+#[post("/password", data = "<password_form>")]
+pub async fn change_password(
+    conn: Connection<'_, Db>,
+    session: ApplicationAuth,
+    password_form: Json<ChangePasswordRequest>,
+    cookies: &CookieJar<'_>,
+) -> Result<(), Custom<String>> {
+    let db = conn.into_inner();
+    let audit = session.audit_context();
+    let application: application::Model = session.into();
+
+    let session_token = ApplicationService::change_password(
+        db,
+        application,
+        password_form.old_password.to_string(),
+        password_form.new_password.to_string(),
+        &audit,
+    )
+    .await
+    .map_err(to_custom_error)?;
+
+    cookies.add_private(Cookie::new("id", session_token));
+
+    Ok(())
+}
+
 #[get("/whoami")]
 pub async fn whoami(conn: Connection<'_, Db>, session: ApplicationAuth) -> Result<Json<NewCandidateResponse>, Custom<String>> {
     let db = conn.into_inner();
 
     let private_key = session.get_private_key();
-    let application: entity::application::Model = session.into();
+    let application: application::Model = session.into();
     let candidate = ApplicationService::find_related_candidate(&db, &application)
         .await.map_err(to_custom_error)?; // TODO more compact
     let applications = Query::find_applications_by_candidate_id(&db, candidate.id)
@@ -103,12 +134,14 @@ pub async fn post_details(
     let db = conn.into_inner();
     let form = details.into_inner();
     form.candidate.validate_self().map_err(to_custom_error)?;
+    let private_key = session.get_private_key();
     let application: application::Model = session.into();
     let candidate = ApplicationService::find_related_candidate(&db, &application).await.map_err(to_custom_error)?; // TODO
 
-    let _candidate_parent = ApplicationService::add_all_details(db, &application, candidate, &form)
+    let (candidate, _parents) = ApplicationService::add_all_details(db, &application, candidate, &form)
         .await
         .map_err(to_custom_error)?;
+    NotificationService::flush(db, &private_key, candidate.id).await;
 
     Ok(Json(form))
 }
@@ -120,12 +153,14 @@ pub async fn get_details(
 ) -> Result<Json<ApplicationDetails>, Custom<String>> {
     let db = conn.into_inner();
     let private_key = session.get_private_key();
-    let application: entity::application::Model = session.into();
+    let audit = session.audit_context();
+    let application: application::Model = session.into();
 
     let details = ApplicationService::decrypt_all_details(
         private_key,
         db,
-        &application
+        &application,
+        &audit,
     )
         .await
         .map(|x| Json(x))
@@ -133,75 +168,123 @@ pub async fn get_details(
 
     details
 }
-#[post("/cover_letter", data = "<letter>")]
-pub async fn upload_cover_letter(
+/// Documents required or allowed by the programs the candidate applied to, with their upload state
+#[get("/")]
+pub async fn list_documents(
+    conn: Connection<'_, Db>,
     session: ApplicationAuth,
-    letter: Letter,
-) -> Result<(), Custom<String>> {
-    let application: entity::application::Model = session.into();
+) -> Result<Json<Vec<DocumentStatus>>, Custom<String>> {
+    let db = conn.into_inner();
+    let application: application::Model = session.into();
 
-    PortfolioService::add_cover_letter_to_cache(application.candidate_id, letter.into())
+    let documents = PortfolioService::document_statuses(db, application.candidate_id)
         .await
         .map_err(to_custom_error)?;
 
-    Ok(())
+    Ok(Json(documents))
 }
 
-#[delete("/cover_letter")]
-pub async fn delete_cover_letter(session: ApplicationAuth) -> Result<(), Custom<String>> {
-    let application: entity::application::Model = session.into();
+#[post("/<slug>", data = "<document>")]
+pub async fn upload_document(
+    conn: Connection<'_, Db>,
+    session: ApplicationAuth,
+    slug: &str,
+    document: Document,
+) -> Result<(), Custom<String>> {
+    let db = conn.into_inner();
+    let application: application::Model = session.into();
 
-    PortfolioService::delete_cover_letter_from_cache(application.candidate_id)
+    PortfolioService::add_document_to_cache(db, application.candidate_id, slug, document.into())
         .await
         .map_err(to_custom_error)?;
 
     Ok(())
 }
 
-#[post("/portfolio_letter", data = "<letter>")]
-pub async fn upload_portfolio_letter(
+#[delete("/<slug>")]
+pub async fn delete_document(
+    conn: Connection<'_, Db>,
     session: ApplicationAuth,
-    letter: Letter,
+    slug: &str,
 ) -> Result<(), Custom<String>> {
-    let application: entity::application::Model = session.into();
+    let db = conn.into_inner();
+    let application: application::Model = session.into();
 
-    PortfolioService::add_portfolio_letter_to_cache(application.candidate_id, letter.into())
+    PortfolioService::delete_document_from_cache(db, application.candidate_id, slug)
         .await
         .map_err(to_custom_error)?;
 
     Ok(())
 }
 
-#[delete("/portfolio_letter")]
-pub async fn delete_portfolio_letter(session: ApplicationAuth) -> Result<(), Custom<String>> {
-    let candidate: entity::application::Model = session.into();
+fn parse_upload_id(upload_id: &str) -> Result<Uuid, Custom<String>> {
+    Uuid::try_parse(upload_id).map_err(|_| to_custom_error(ServiceError::UploadNotFound))
+}
+
+/// Starts resumable upload of a document, continued with `upload_document_chunk`
+#[post("/<slug>", data = "<request>")]
+pub async fn init_document_upload(
+    conn: Connection<'_, Db>,
+    session: ApplicationAuth,
+    slug: &str,
+    request: Json<UploadInitRequest>,
+) -> Result<Json<UploadResponse>, Custom<String>> {
+    let db = conn.into_inner();
+    let application: application::Model = session.into();
 
-    PortfolioService::delete_portfolio_letter_from_cache(candidate.candidate_id)
+    let upload = UploadService::init(db, application.candidate_id, slug, request.size)
         .await
         .map_err(to_custom_error)?;
 
-    Ok(())
+    Ok(Json(upload))
 }
 
-#[post("/portfolio_zip", data = "<portfolio>")]
-pub async fn upload_portfolio_zip(
+#[get("/<slug>/<upload_id>")]
+pub async fn document_upload_status(
     session: ApplicationAuth,
-    portfolio: Portfolio,
-) -> Result<(), Custom<String>> {
-    let application: entity::application::Model = session.into();
+    slug: &str,
+    upload_id: &str,
+) -> Result<Json<UploadResponse>, Custom<String>> {
+    let application: application::Model = session.into();
 
-    PortfolioService::add_portfolio_zip_to_cache(application.candidate_id, portfolio.into())
+    let upload = UploadService::status(application.candidate_id, slug, parse_upload_id(upload_id)?)
         .await
         .map_err(to_custom_error)?;
 
-    Ok(())
+    Ok(Json(upload))
 }
 
-#[delete("/portfolio_zip")]
-pub async fn delete_portfolio_zip(session: ApplicationAuth) -> Result<(), Custom<String>> {
-    let application: entity::application::Model = session.into();
+#[patch("/<slug>/<upload_id>?<offset>", data = "<chunk>")]
+pub async fn upload_document_chunk(
+    conn: Connection<'_, Db>,
+    session: ApplicationAuth,
+    slug: &str,
+    upload_id: &str,
+    offset: u64,
+    chunk: Chunk,
+) -> Result<Json<UploadResponse>, Custom<String>> {
+    let db = conn.into_inner();
+    let application: application::Model = session.into();
+
+    let upload = UploadService::append(db, application.candidate_id, slug, parse_upload_id(upload_id)?, offset, chunk.into())
+        .await
+        .map_err(to_custom_error)?;
+
+    Ok(Json(upload))
+}
+
+#[post("/<slug>/<upload_id>/finalize", data = "<request>")]
+pub async fn finalize_document_upload(
+    conn: Connection<'_, Db>,
+    session: ApplicationAuth,
+    slug: &str,
+    upload_id: &str,
+    request: Json<UploadFinalizeRequest>,
+) -> Result<(), Custom<String>> {
+    let db = conn.into_inner();
+    let application: application::Model = session.into();
 
-    PortfolioService::delete_portfolio_zip_from_cache(application.candidate_id)
+    UploadService::finalize(db, application.candidate_id, slug, parse_upload_id(upload_id)?, &request.sha256)
         .await
         .map_err(to_custom_error)?;
 
@@ -210,11 +293,13 @@ pub async fn delete_portfolio_zip(session: ApplicationAuth) -> Result<(), Custom
 
 #[get("/submission_progress")]
 pub async fn submission_progress(
+    conn: Connection<'_, Db>,
     session: ApplicationAuth,
-) -> Result<Json<SubmissionProgress>, Custom<String>> {
-    let application: entity::application::Model = session.into();
+) -> Result<Json<SubmissionStatus>, Custom<String>> {
+    let db = conn.into_inner();
+    let application: application::Model = session.into();
 
-    let progress = PortfolioService::get_submission_progress(application.candidate_id)
+    let progress = PortfolioService::get_submission_status(db, application.candidate_id)
         .await
         .map(|x| Json(x))
         .map_err(to_custom_error);
@@ -226,64 +311,81 @@ pub async fn submission_progress(
 pub async fn submit_portfolio(
     conn: Connection<'_, Db>,
     session: ApplicationAuth,
-) -> Result<(), Custom<String>> {
+) -> Result<Json<SubmissionReceipt>, Custom<String>> {
     let db = conn.into_inner();
 
-    let application: entity::application::Model = session.into();
+    let audit = session.audit_context();
+    let private_key = session.get_private_key();
+    let application: application::Model = session.into();
     let candidate = ApplicationService::find_related_candidate(&db, &application).await.map_err(to_custom_error)?; // TODO
 
-    let submit = PortfolioService::submit(&candidate, &db).await;
-
-    if submit.is_err() {
-        let e = submit.err().unwrap();
-        // Delete on critical error
-        if e.code() == 500 {
-            // Cleanup
-            PortfolioService::delete_portfolio(application.id)
-                .await
-                .unwrap();
-        }
-        return Err(to_custom_error(e));
-    }
+    // Submit cleans up its own partial writes on failure
+    let receipt = PortfolioService::submit(&candidate, &db, &audit)
+        .await
+        .map_err(to_custom_error)?;
+    NotificationService::flush(db, &private_key, candidate.id).await;
 
-    Ok(())
+    Ok(Json(receipt))
 }
 
 #[post("/delete")]
 pub async fn delete_portfolio(
+    conn: Connection<'_, Db>,
     session: ApplicationAuth,
 ) -> Result<(), Custom<String>> {
-    let application: entity::application::Model = session.into();
+    let db = conn.into_inner();
+    let private_key = session.get_private_key();
+    let application: application::Model = session.into();
 
-    PortfolioService::delete_portfolio(application.candidate_id)
+    PortfolioService::delete_portfolio(db, application.candidate_id, private_key)
         .await
         .map_err(to_custom_error)?;
 
     Ok(())
 }
 
+#[get("/versions")]
+pub async fn list_portfolio_versions(
+    conn: Connection<'_, Db>,
+    session: ApplicationAuth,
+) -> Result<Json<Vec<PortfolioVersionResponse>>, Custom<String>> {
+    let db = conn.into_inner();
+    let application: application::Model = session.into();
+
+    let versions = PortfolioService::list_versions(db, application.candidate_id)
+        .await
+        .map_err(to_custom_error)?;
+
+    Ok(Json(versions))
+}
+
 #[get("/download")]
-pub async fn download_portfolio(session: ApplicationAuth) -> Result<Vec<u8>, Custom<String>> {
+pub async fn download_portfolio(
+    conn: Connection<'_, Db>,
+    session: ApplicationAuth,
+) -> Result<(ContentType, ReaderStream<One<impl AsyncRead + Send>>), Custom<String>> {
+    let db = conn.into_inner();
     let private_key = session.get_private_key();
-    let application: entity::application::Model = session.into();
+    let audit = session.audit_context();
+    let application: application::Model = session.into();
 
-    let file = PortfolioService::get_portfolio(application.candidate_id, private_key)
+    let file = PortfolioService::download_portfolio(db, &application, None, private_key, &audit)
         .await
-        .map_err(to_custom_error);
+        .map_err(to_custom_error)?;
 
-    file
+    Ok((ContentType::Binary, ReaderStream::one(file)))
 }
 
 #[cfg(test)]
 mod tests {
-    use portfolio_core::{crypto, models::candidate::{ApplicationDetails, NewCandidateResponse}, sea_orm::prelude::Uuid};
+    use portfolio_core::{crypto, models::{candidate::{ApplicationDetails, NewCandidateResponse}, portfolio_version::PortfolioVersionResponse}, sea_orm::prelude::Uuid};
     use rocket::{
         http::{Cookie, Status},
         local::blocking::Client,
     };
 
     use crate::{
-        routes::admin::tests::admin_login,
+        routes::admin::tests::{admin_login, create_candidate},
         test::tests::{test_client, APPLICATION_ID, CANDIDATE_PASSWORD, PERSONAL_ID_NUMBER},
     };
 
@@ -387,6 +489,47 @@ mod tests {
         assert_eq!(details_orig, details_resp);
     }
 
+    #[test]
+    fn test_change_password() {
+        let client = test_client().lock().unwrap();
+        let admin_cookies = admin_login(&client);
+        let candidate = create_candidate(&client, admin_cookies, 101152, "0000001111".to_string());
+
+        let login = |password: &str| client
+            .post("/candidate/login")
+            .body(format!("{{\"applicationId\": 101152, \"password\": \"{}\"}}", password))
+            .dispatch();
+        let response = login(&candidate.password);
+        let id = response.cookies().get("id").unwrap().to_owned();
+        let key = response.cookies().get("key").unwrap().to_owned();
+
+        let response = client
+            .post("/candidate/password")
+            .cookie(id.clone())
+            .cookie(key.clone())
+            .body(format!("{{\"oldPassword\": \"{}\", \"newPassword\": \"short\"}}", candidate.password))
+            .dispatch();
+        assert_eq!(response.status(), Status::BadRequest);
+
+        let response = client
+            .post("/candidate/password")
+            .cookie(id.clone())
+            .cookie(key.clone())
+            .body(format!("{{\"oldPassword\": \"{}\", \"newPassword\": \"newpassword123\"}}", candidate.password))
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let new_id = response.cookies().get("id").unwrap().to_owned();
+
+        // the old session is logged out
+        let response = client.get("/candidate/whoami").cookie(id).cookie(key.clone()).dispatch();
+        assert_eq!(response.status(), Status::Unauthorized);
+        let response = client.get("/candidate/whoami").cookie(new_id).cookie(key).dispatch();
+        assert_eq!(response.status(), Status::Ok);
+
+        assert_eq!(login(&candidate.password).status(), Status::Unauthorized);
+        assert_eq!(login("newpassword123").status(), Status::Ok);
+    }
+
     #[test]
     fn test_invalid_token_every_secured_endpoint() {
         let client = test_client().lock().unwrap();
@@ -445,4 +588,93 @@ mod tests {
             .dispatch();
         assert_eq!(response.status(), Status::Unauthorized);
     }
+
+    #[test]
+    fn test_portfolio_zip_upload_rejected() {
+        let client = test_client().lock().unwrap();
+        let cookies = candidate_login(&client);
+
+        let response = client
+            .post("/candidate/upload/portfolio_zip")
+            .cookie(cookies.0.clone())
+            .cookie(cookies.1.clone())
+            .body("{\"size\": 1000000000}")
+            .dispatch();
+        assert_eq!(response.status(), Status::PayloadTooLarge);
+
+        let response = client
+            .get(format!("/candidate/upload/portfolio_zip/{}", Uuid::new_v4()))
+            .cookie(cookies.0.clone())
+            .cookie(cookies.1.clone())
+            .dispatch();
+        assert_eq!(response.status(), Status::NotFound);
+
+        let response = client
+            .patch("/candidate/upload/portfolio_zip/invalid?offset=0")
+            .cookie(cookies.0)
+            .cookie(cookies.1)
+            .body([1, 2, 3])
+            .dispatch();
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    #[test]
+    fn test_upload_invalid_content() {
+        let client = test_client().lock().unwrap();
+        let cookies = candidate_login(&client);
+
+        let response = client
+            .post("/candidate/documents/cover_letter")
+            .cookie(cookies.0.clone())
+            .cookie(cookies.1.clone())
+            .body("%PDF-1.7\n1 0 obj")
+            .dispatch();
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+        assert_eq!(response.into_string().unwrap(), r#"[{"reason":"malformedPdf"}]"#);
+
+        let response = client
+            .post("/candidate/documents/portfolio_zip")
+            .cookie(cookies.0.clone())
+            .cookie(cookies.1.clone())
+            .body(b"PK\x03\x04truncated")
+            .dispatch();
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+
+        let response = client
+            .post("/candidate/documents/photo")
+            .cookie(cookies.0)
+            .cookie(cookies.1)
+            .body(b"PK\x03\x04truncated")
+            .dispatch();
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    #[test]
+    fn test_list_portfolio_versions() {
+        let client = test_client().lock().unwrap();
+        let cookies = candidate_login(&client);
+
+        let response = client
+            .get("/candidate/portfolio/versions")
+            .cookie(cookies.0)
+            .cookie(cookies.1)
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        assert!(response.into_json::<Vec<PortfolioVersionResponse>>().is_some());
+
+        let cookies = admin_login(&client);
+        let response = client
+            .get(format!("/admin/candidate/{}/portfolio/versions", APPLICATION_ID))
+            .cookie(cookies.0.clone())
+            .cookie(cookies.1.clone())
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+
+        let response = client
+            .get(format!("/admin/candidate/{}/portfolio?version=999", APPLICATION_ID))
+            .cookie(cookies.0)
+            .cookie(cookies.1)
+            .dispatch();
+        assert_eq!(response.status(), Status::NotFound);
+    }
 }
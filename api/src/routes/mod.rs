@@ -3,14 +3,40 @@ use rocket::{response::status::Custom, http::Status};
 
 pub mod admin;
 pub mod candidate;
+pub mod timeline;
 
 pub fn to_custom_error(e: ServiceError) -> Custom<String> {
     if e.code() == 500 {
         warn!("Internal server error: {} ({})", e, e.inner_trace().unwrap_or("".to_string()));
     }
 
+    let body = match &e {
+        // List of `{"reason": ..., ...}` objects, so that the frontend can explain each issue
+        ServiceError::InvalidContent(issues) => serde_json::to_string(issues).unwrap_or_else(|_| e.to_string()),
+        _ => e.to_string(),
+    };
+
     Custom(
         Status::from_code(e.code()).unwrap_or_default(),
-        e.to_string()
+        body
     )
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use portfolio_core::{error::ServiceError, utils::content::ContentIssue};
+    use rocket::http::Status;
+
+    use super::to_custom_error;
+
+    #[test]
+    fn test_invalid_content_as_json() {
+        let error = to_custom_error(ServiceError::InvalidContent(vec![
+            ContentIssue::PdfJavaScript,
+            ContentIssue::UnsafePath { name: "../passwd".to_string() },
+        ]));
+
+        assert_eq!(error.0, Status::UnprocessableEntity);
+        assert_eq!(error.1, r#"[{"reason":"pdfJavaScript"},{"reason":"unsafePath","name":"../passwd"}]"#);
+    }
+}
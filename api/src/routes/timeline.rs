@@ -0,0 +1,28 @@
+use portfolio_core::{models::timeline::TimelineResponse, services::timeline_service::TimelineService};
+use rocket::serde::json::Json;
+
+/// Current admission phase, public so that the frontend can show it before login
+#[get("/timeline")]
+pub async fn get_timeline() -> Json<TimelineResponse> {
+    Json(TimelineService::current())
+}
+
+#[cfg(test)]
+mod tests {
+    use portfolio_core::models::timeline::{AdmissionPhase, TimelineResponse};
+    use rocket::http::Status;
+
+    use crate::test::tests::test_client;
+
+    #[test]
+    fn test_get_timeline() {
+        let client = test_client().lock().unwrap();
+
+        let response = client.get("/timeline").dispatch();
+        assert_eq!(response.status(), Status::Ok);
+
+        let timeline = response.into_json::<TimelineResponse>().unwrap();
+        assert_eq!(timeline.phase, AdmissionPhase::RegistrationOpen);
+        assert!(timeline.timeline.is_none());
+    }
+}
@@ -35,6 +35,7 @@ pub mod tests {
             public_key: Set(pubkey),
             private_key: Set(priv_key),
             password: Set(password_hash),
+            role: Set("superadmin".to_string()),
             created_at: Set(chrono::Utc::now().naive_utc()),
             updated_at: Set(chrono::Utc::now().naive_utc()),
         }
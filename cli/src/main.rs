@@ -1,11 +1,25 @@
 use std::path::PathBuf;
 
+use chrono::NaiveDate;
 use clap::{arg, ArgAction, ArgMatches, command, Command, value_parser};
 use sea_orm::{Database, DatabaseConnection, DbConn};
 use url::Url;
 
 use portfolio_core::{crypto, Query};
-use portfolio_core::services::portfolio_service::{FileType};
+use portfolio_core::models::admin::AdminRole;
+use portfolio_core::models::audit::AuditLogFilter;
+use portfolio_core::models::credential_letter::CredentialLetter;
+use portfolio_core::models::portfolio_version::IntegrityStatus;
+use portfolio_core::services::admission_round_service::AdmissionRoundService;
+use portfolio_core::services::audit_service::AuditService;
+use portfolio_core::services::candidate_service::CandidateService;
+use portfolio_core::services::import_service::ImportService;
+use portfolio_core::services::letter_service::LetterService;
+use portfolio_core::services::notification_service::NotificationService;
+use portfolio_core::services::admin_service::AdminService;
+use portfolio_core::services::key_rotation_service::KeyRotationService;
+use portfolio_core::services::portfolio_service::PortfolioService;
+use portfolio_core::storage::LocalStore;
 use portfolio_core::utils::csv::{ApplicationCsv, CsvExporter};
 
 async fn get_admin_private_key(db: &DbConn, sub_matches: &ArgMatches) -> Result<String, Box<dyn std::error::Error>> {
@@ -180,6 +194,400 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                         .required(false),
                 )
         )
+        .subcommand(
+            Command::new("create-admin")
+                .about("Create admin and re-encrypt all data for them")
+                .arg(
+                    arg!(
+                        -d --database <URL> "URL to the database or sql file with postgres:// or sqlite://"
+                    )
+                        .alias("url")
+                        .required(true)
+                        .value_parser(value_parser!(Url)),
+                )
+                .arg(
+                    arg!(
+                        -k --key <KEY> "AGE private key of an existing admin"
+                    )
+                        .required(false),
+                )
+                .arg(
+                    arg!(
+                        -p --password <PASSWORD> "Password of an existing admin"
+                    )
+                        .required(false),
+                )
+                .arg(
+                    arg!(
+                        -a --admin_id <ADMIN_ID> "ID of an existing admin"
+                    )
+                        .required(false),
+                )
+                .arg(
+                    arg!(
+                        -r --root_dir <PATH> "Portfolio root directory"
+                    )
+                        .required(false)
+                        .value_parser(value_parser!(PathBuf)),
+                )
+                .arg(
+                    arg!(
+                        -n --name <NAME> "Name of the new admin"
+                    )
+                        .required(true),
+                )
+                .arg(
+                    arg!(
+                        --new_password <PASSWORD> "Password of the new admin"
+                    )
+                        .required(true),
+                )
+                .arg(
+                    arg!(
+                        --role <ROLE> "Role of the new admin"
+                    )
+                        .required(true)
+                        .value_parser(["reviewer", "registrar", "superadmin"]),
+                )
+        )
+        .subcommand(
+            Command::new("remove-admin")
+                .about("Remove admin and re-encrypt all data without their key")
+                .arg(
+                    arg!(
+                        -d --database <URL> "URL to the database or sql file with postgres:// or sqlite://"
+                    )
+                        .alias("url")
+                        .required(true)
+                        .value_parser(value_parser!(Url)),
+                )
+                .arg(
+                    arg!(
+                        -k --key <KEY> "AGE private key of an existing admin"
+                    )
+                        .required(false),
+                )
+                .arg(
+                    arg!(
+                        -p --password <PASSWORD> "Password of an existing admin"
+                    )
+                        .required(false),
+                )
+                .arg(
+                    arg!(
+                        -a --admin_id <ADMIN_ID> "ID of an existing admin"
+                    )
+                        .required(false),
+                )
+                .arg(
+                    arg!(
+                        -r --root_dir <PATH> "Portfolio root directory"
+                    )
+                        .required(false)
+                        .value_parser(value_parser!(PathBuf)),
+                )
+                .arg(
+                    arg!(
+                        --remove_id <ADMIN_ID> "ID of the admin to remove"
+                    )
+                        .required(true)
+                        .value_parser(value_parser!(i32)),
+                )
+        )
+        .subcommand(
+            Command::new("rotate-admin-key")
+                .about("Generate a new admin key and re-encrypt all data to it")
+                .arg(
+                    arg!(
+                        -d --database <URL> "URL to the database or sql file with postgres:// or sqlite://"
+                    )
+                        .alias("url")
+                        .required(true)
+                        .value_parser(value_parser!(Url)),
+                )
+                .arg(
+                    arg!(
+                        -a --admin_id <ADMIN_ID> "Admin ID"
+                    )
+                        .required(true)
+                        .value_parser(value_parser!(i32)),
+                )
+                .arg(
+                    arg!(
+                        -p --password <PASSWORD> "Admin password"
+                    )
+                        .required(true),
+                )
+                .arg(
+                    arg!(
+                        -r --root_dir <PATH> "Portfolio root directory"
+                    )
+                        .required(false)
+                        .value_parser(value_parser!(PathBuf)),
+                )
+        )
+        .subcommand(
+            Command::new("audit")
+                .about("List audit log entries, newest first")
+                .arg(
+                    arg!(
+                        -d --database <URL> "URL to the database or sql file with postgres:// or sqlite://"
+                    )
+                        .alias("url")
+                        .required(true)
+                        .value_parser(value_parser!(Url)),
+                )
+                .arg(
+                    arg!(
+                        --actor <ACTOR> "Actor type"
+                    )
+                        .required(false)
+                        .value_parser(["admin", "application"]),
+                )
+                .arg(
+                    arg!(
+                        --actor_id <ID> "Admin or application ID of the actor"
+                    )
+                        .required(false)
+                        .value_parser(value_parser!(i32)),
+                )
+                .arg(
+                    arg!(
+                        --action <ACTION> "Action"
+                    )
+                        .required(false)
+                        .value_parser(["login", "view_details", "download_portfolio", "reset_password", "change_password", "delete", "submit"]),
+                )
+                .arg(
+                    arg!(
+                        --target <APPLICATION_ID> "Application the action was performed on"
+                    )
+                        .required(false)
+                        .value_parser(value_parser!(i32)),
+                )
+                .arg(
+                    arg!(
+                        --from <DATE> "Entries from this day (YYYY-MM-DD)"
+                    )
+                        .required(false)
+                        .value_parser(value_parser!(NaiveDate)),
+                )
+                .arg(
+                    arg!(
+                        --to <DATE> "Entries before this day (YYYY-MM-DD)"
+                    )
+                        .required(false)
+                        .value_parser(value_parser!(NaiveDate)),
+                )
+        )
+        .subcommand(
+            Command::new("new-round")
+                .about("Close the current admission round and open a new one")
+                .arg(
+                    arg!(
+                        -d --database <URL> "URL to the database or sql file with postgres:// or sqlite://"
+                    )
+                        .alias("url")
+                        .required(true)
+                        .value_parser(value_parser!(Url)),
+                )
+                .arg(
+                    arg!(
+                        -n --name <NAME> "Name of the new round"
+                    )
+                        .required(true),
+                )
+        )
+        .subcommand(
+            Command::new("archive-round")
+                .about("Write encrypted archive of a closed admission round and optionally purge it")
+                .arg(
+                    arg!(
+                        -d --database <URL> "URL to the database or sql file with postgres:// or sqlite://"
+                    )
+                        .alias("url")
+                        .required(true)
+                        .value_parser(value_parser!(Url)),
+                )
+                .arg(
+                    arg!(
+                        --round <ROUND_ID> "ID of the admission round"
+                    )
+                        .required(true)
+                        .value_parser(value_parser!(i32)),
+                )
+                .arg(
+                    arg!(
+                        -o --output <FILE> "Output archive file"
+                    )
+                        .required(true)
+                        .value_parser(value_parser!(PathBuf)),
+                )
+                .arg(
+                    arg!(
+                        -r --root_dir <PATH> "Portfolio root directory"
+                    )
+                        .required(false)
+                        .value_parser(value_parser!(PathBuf)),
+                )
+                .arg(
+                    arg!(
+                        --purge "Delete the round from the database and store after archiving"
+                    )
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    arg!(
+                        --retention_days <DAYS> "Days after closing the round before it can be purged"
+                    )
+                        .required(false)
+                        .default_value("180")
+                        .value_parser(value_parser!(i64)),
+                )
+        )
+        .subcommand(
+            Command::new("import")
+                .about("Create candidates from a CSV file and write their generated passwords")
+                .arg(
+                    arg!(
+                        -d --database <URL> "URL to the database or sql file with postgres:// or sqlite://"
+                    )
+                        .alias("url")
+                        .required(true)
+                        .value_parser(value_parser!(Url)),
+                )
+                .arg(
+                    arg!(
+                        -i --input <PATH> "CSV file with application and personal id numbers"
+                    )
+                        .required(true)
+                        .value_parser(value_parser!(PathBuf)),
+                )
+                .arg(
+                    arg!(
+                        -o --output <PATH> "Output CSV file with results and generated passwords"
+                    )
+                        .required(true)
+                        .value_parser(value_parser!(PathBuf)),
+                )
+                .arg(
+                    arg!(
+                        -l --letters <PATH> "Output PDF file with credential letters of created applications"
+                    )
+                        .required(false)
+                        .value_parser(value_parser!(PathBuf)),
+                )
+                .arg(
+                    arg!(
+                        --dry_run "Only validate the rows, nothing is created"
+                    )
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    arg!(
+                        -r --root_dir <PATH> "Portfolio root directory"
+                    )
+                        .required(false)
+                        .value_parser(value_parser!(PathBuf)),
+                )
+                .arg(
+                    arg!(
+                        -k --key <KEY> "AGE private key for decryption"
+                    )
+                        .required(false),
+                )
+                .arg(
+                    arg!(
+                        -p --password <PASSWORD> "Password for decryption"
+                    )
+                        .required(false),
+                )
+                .arg(
+                    arg!(
+                        -a --admin_id <ADMIN_ID> "Admin ID"
+                    )
+                        .required(false),
+                )
+        )
+        .subcommand(
+            Command::new("backfill-blind-index")
+                .about("Fill blind indexes of candidates created before they were introduced")
+                .arg(
+                    arg!(
+                        -d --database <URL> "URL to the database or sql file with postgres:// or sqlite://"
+                    )
+                        .alias("url")
+                        .required(true)
+                        .value_parser(value_parser!(Url)),
+                )
+                .arg(
+                    arg!(
+                        -k --key <KEY> "AGE private key for decryption"
+                    )
+                        .required(false),
+                )
+                .arg(
+                    arg!(
+                        -p --password <PASSWORD> "Password for decryption"
+                    )
+                        .required(false),
+                )
+                .arg(
+                    arg!(
+                        -a --admin_id <ADMIN_ID> "Admin ID"
+                    )
+                        .required(false),
+                )
+        )
+        .subcommand(
+            Command::new("send-notifications")
+                .about("Retry sending pending notifications, recipients are decrypted with the admin key")
+                .arg(
+                    arg!(
+                        -d --database <URL> "URL to the database or sql file with postgres:// or sqlite://"
+                    )
+                        .alias("url")
+                        .required(true)
+                        .value_parser(value_parser!(Url)),
+                )
+                .arg(
+                    arg!(
+                        -k --key <KEY> "AGE private key for decryption"
+                    )
+                        .required(false),
+                )
+                .arg(
+                    arg!(
+                        -p --password <PASSWORD> "Password for decryption"
+                    )
+                        .required(false),
+                )
+                .arg(
+                    arg!(
+                        -a --admin_id <ADMIN_ID> "Admin ID"
+                    )
+                        .required(false),
+                )
+        )
+        .subcommand(
+            Command::new("verify-portfolios")
+                .about("Check all stored portfolio versions against their manifests")
+                .arg(
+                    arg!(
+                        -d --database <URL> "URL to the database or sql file with postgres:// or sqlite://"
+                    )
+                        .alias("url")
+                        .required(true)
+                        .value_parser(value_parser!(Url)),
+                )
+                .arg(
+                    arg!(
+                        -r --root_dir <PATH> "Portfolio root directory"
+                    )
+                        .required(true)
+                        .value_parser(value_parser!(PathBuf)),
+                )
+        )
         .subcommand(
             Command::new("hash")
                 .about("Hash operations")
@@ -263,10 +671,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
             let age_file_path = sub_matches.get_one::<PathBuf>("file").unwrap();
 
-            let decrypted = crypto::decrypt_file_with_private_key_as_buffer(age_file_path, &key).await?;
-
             let output = sub_matches.get_one::<PathBuf>("output").unwrap();
-            tokio::fs::write(output, decrypted).await?;
+
+            crypto::decrypt_file_with_private_key(age_file_path, output, &key).await?;
         },
         Some(("package", sub_matches)) => {
             let db_url = sub_matches.get_one::<Url>("database").unwrap();
@@ -286,13 +693,18 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 .iter()
                 .map(|application_id| application_id.to_i32())
                 .collect();
+            let store = LocalStore::new(portfolio_root_dir);
             for id in ids {
-                let file_path = portfolio_root_dir.join(&id.to_string()).join(FileType::Age.as_str());
+                let Some(portfolio_key) = PortfolioService::current_key(&store, id).await? else {
+                    continue;
+                };
+                let file_path = portfolio_root_dir.join(portfolio_key);
                 println!("{}", file_path.display());
                 let output_path = output.join(&id.to_string());
-                if let Ok(portfolio) = crypto::decrypt_file_with_private_key_as_buffer(file_path, &key).await {
+                if let Ok(mut portfolio) = crypto::decrypt_file_with_private_key_as_stream(file_path, &key).await {
                     tokio::fs::create_dir_all(&output_path).await?;
-                    tokio::fs::write(&output_path.join(FileType::PortfolioZip.as_str()), portfolio).await?;
+                    let mut portfolio_file = tokio::fs::File::create(output_path.join("PORTFOLIO.zip")).await?;
+                    tokio::io::copy(&mut portfolio, &mut portfolio_file).await?;
                 };
             }
             println!("Exported all portfolios");
@@ -308,6 +720,166 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             println!("Exported database");
 
         }
+        Some(("create-admin", sub_matches)) => {
+            let db = get_db_conn(sub_matches).await?;
+            let key = get_admin_private_key(&db, sub_matches).await?;
+            let name = sub_matches.get_one::<String>("name").unwrap();
+            let password = sub_matches.get_one::<String>("new_password").unwrap();
+            let role = AdminRole::try_from(sub_matches.get_one::<String>("role").unwrap().as_str())?;
+
+            if let Some(root_dir) = sub_matches.get_one::<PathBuf>("root_dir") {
+                std::env::set_var("PORTFOLIO_STORE_PATH", root_dir);
+            }
+
+            let admin = AdminService::create_admin(&db, key, name.to_string(), password.to_string(), role).await?;
+
+            println!("{}", admin.id);
+            println!("{}", admin.public_key);
+        }
+        Some(("remove-admin", sub_matches)) => {
+            let db = get_db_conn(sub_matches).await?;
+            let key = get_admin_private_key(&db, sub_matches).await?;
+            let admin_id = *sub_matches.get_one::<i32>("remove_id").unwrap();
+
+            if let Some(root_dir) = sub_matches.get_one::<PathBuf>("root_dir") {
+                std::env::set_var("PORTFOLIO_STORE_PATH", root_dir);
+            }
+
+            AdminService::remove_admin(&db, key, admin_id).await?;
+
+            println!("Removed admin {}", admin_id);
+        }
+        Some(("rotate-admin-key", sub_matches)) => {
+            let db = get_db_conn(sub_matches).await?;
+            let admin_id = *sub_matches.get_one::<i32>("admin_id").unwrap();
+            let password = sub_matches.get_one::<String>("password").unwrap();
+
+            if let Some(root_dir) = sub_matches.get_one::<PathBuf>("root_dir") {
+                std::env::set_var("PORTFOLIO_STORE_PATH", root_dir);
+            }
+
+            let pubkey = KeyRotationService::rotate_admin_key(&db, admin_id, password.to_string()).await?;
+
+            println!("{}", pubkey);
+        }
+        Some(("audit", sub_matches)) => {
+            let db = get_db_conn(sub_matches).await?;
+            let filter = AuditLogFilter {
+                actor_type: sub_matches.get_one::<String>("actor").cloned(),
+                actor_id: sub_matches.get_one::<i32>("actor_id").copied(),
+                action: sub_matches.get_one::<String>("action").cloned(),
+                target_id: sub_matches.get_one::<i32>("target").copied(),
+                from: sub_matches.get_one::<NaiveDate>("from").and_then(|d| d.and_hms_opt(0, 0, 0)),
+                to: sub_matches.get_one::<NaiveDate>("to").and_then(|d| d.and_hms_opt(0, 0, 0)),
+            };
+
+            for log in AuditService::list(&db, filter, None).await? {
+                println!("{}\t{}\t{}\t{}\t{}\t{}",
+                    log.created_at,
+                    log.actor_type,
+                    log.actor_id,
+                    log.action,
+                    log.target_id.map(|id| id.to_string()).unwrap_or_default(),
+                    log.ip_address.unwrap_or_default(),
+                );
+            }
+        }
+        Some(("new-round", sub_matches)) => {
+            let db = get_db_conn(sub_matches).await?;
+            let name = sub_matches.get_one::<String>("name").unwrap();
+
+            let round = AdmissionRoundService::open(&db, name.to_string()).await?;
+
+            println!("{}", round.id);
+        }
+        Some(("archive-round", sub_matches)) => {
+            let db = get_db_conn(sub_matches).await?;
+            let round_id = *sub_matches.get_one::<i32>("round").unwrap();
+            let output = sub_matches.get_one::<PathBuf>("output").unwrap();
+
+            if let Some(root_dir) = sub_matches.get_one::<PathBuf>("root_dir") {
+                std::env::set_var("PORTFOLIO_STORE_PATH", root_dir);
+            }
+
+            let mut archive = tokio::fs::File::create(output).await?;
+            AdmissionRoundService::archive(&db, round_id, &mut archive).await?;
+            archive.sync_all().await?;
+            println!("Archived round {} to {}", round_id, output.display());
+
+            if *sub_matches.get_one::<bool>("purge").unwrap_or(&false) {
+                let retention_days = *sub_matches.get_one::<i64>("retention_days").unwrap();
+                AdmissionRoundService::purge(&db, round_id, chrono::Duration::days(retention_days)).await?;
+                println!("Purged round {}", round_id);
+            }
+        }
+        Some(("import", sub_matches)) => {
+            let db = get_db_conn(sub_matches).await?;
+            let key = get_admin_private_key(&db, sub_matches).await?;
+            let input = sub_matches.get_one::<PathBuf>("input").unwrap();
+            let output = sub_matches.get_one::<PathBuf>("output").unwrap();
+            let dry_run = *sub_matches.get_one::<bool>("dry_run").unwrap_or(&false);
+
+            if let Some(root_dir) = sub_matches.get_one::<PathBuf>("root_dir") {
+                std::env::set_var("PORTFOLIO_STORE_PATH", root_dir);
+            }
+
+            let csv = tokio::fs::read(input).await?;
+            let results = ImportService::import_csv(&db, &key, &csv, dry_run).await?;
+            tokio::fs::write(output, ImportService::results_to_csv(&results)?).await?;
+
+            if let Some(letters_output) = sub_matches.get_one::<PathBuf>("letters") {
+                let letters: Vec<CredentialLetter> = results.iter()
+                    .filter_map(CredentialLetter::from_import_result)
+                    .collect();
+                tokio::fs::write(letters_output, LetterService::render(&letters)).await?;
+            }
+
+            for result in results.iter().filter(|r| r.error.is_some()) {
+                println!("{}: {}", result.line, result.error.as_deref().unwrap_or_default());
+            }
+            println!("{} rows, {} failed", results.len(), results.iter().filter(|r| r.error.is_some()).count());
+        }
+        Some(("backfill-blind-index", sub_matches)) => {
+            let db = get_db_conn(sub_matches).await?;
+            let key = get_admin_private_key(&db, sub_matches).await?;
+
+            let count = CandidateService::backfill_blind_indexes(&db, &key).await?;
+
+            println!("Backfilled {} candidates", count);
+        }
+        Some(("send-notifications", sub_matches)) => {
+            let db = get_db_conn(sub_matches).await?;
+            let key = get_admin_private_key(&db, sub_matches).await?;
+
+            let sent = NotificationService::send_pending(&db, &key, None).await?;
+
+            println!("Sent {} notifications", sent);
+        }
+        Some(("verify-portfolios", sub_matches)) => {
+            let db = get_db_conn(sub_matches).await?;
+            let portfolio_root_dir = sub_matches.get_one::<PathBuf>("root_dir").unwrap();
+            let store = LocalStore::new(portfolio_root_dir);
+
+            let reports = PortfolioService::verify_integrity(&db, &store).await?;
+            let failed: Vec<_> = reports.iter()
+                .filter(|r| matches!(r.status, IntegrityStatus::Mismatch | IntegrityStatus::Missing))
+                .collect();
+            for report in reports.iter().filter(|r| r.status != IntegrityStatus::Ok) {
+                println!(
+                    "{}\t{}\t{:?}\texpected {}\tactual {}",
+                    report.candidate_id,
+                    report.version,
+                    report.status,
+                    report.expected_hash.as_deref().unwrap_or("-"),
+                    report.actual_hash.as_deref().unwrap_or("-"),
+                );
+            }
+
+            println!("Checked {} versions, {} failed", reports.len(), failed.len());
+            if !failed.is_empty() {
+                return Err("Portfolio integrity check failed".into());
+            }
+        }
         Some(("hash", sub_matches)) => {
             let input = sub_matches.get_one::<String>("input").unwrap();
 
@@ -3,18 +3,31 @@ use aes_gcm_siv::KeyInit;
 use argon2::{
     Argon2, PasswordHasher as ArgonPasswordHasher, PasswordVerifier as ArgonPasswordVerifier,
 };
-use async_compat::CompatExt;
+use async_compat::{Compat, CompatExt};
 use base64::Engine;
 use base64::engine::general_purpose::STANDARD as base64;
+use hmac::{Hmac, Mac};
 use futures::io::{AsyncReadExt, AsyncWriteExt};
 use rand::Rng;
 use secrecy::ExposeSecret;
 use std::iter;
 use std::path::Path;
+use std::pin::Pin;
 use std::str::FromStr;
+use std::task::{ready, Context, Poll};
 
 use crate::error::ServiceError;
 
+/// Keyed HMAC-SHA256 of value, base64 encoded. Equal values give equal digests,
+/// but the digest can't be reversed or brute forced without the key
+pub fn hmac_sha256(key: &[u8], value: &str) -> String {
+    let mut mac = <Hmac<sha2::Sha256> as Mac>::new_from_slice(key)
+        .expect("HMAC accepts keys of any length");
+    mac.update(value.as_bytes());
+
+    base64.encode(mac.finalize().into_bytes())
+}
+
 /// Foolproof random 12 char string
 /// Only uppercase letters (except for O) and numbers (except for 0)
 pub fn random_12_char_string() -> String {
@@ -107,28 +120,130 @@ fn convert_key_aes256(key: &str) -> Vec<u8> {
     key
 }
 
+const ENVELOPE_V2: &str = "v2";
+const ENVELOPE_V2_M_COST: u32 = 19456;
+const ENVELOPE_V2_T_COST: u32 = 2;
+const ENVELOPE_V2_P_COST: u32 = 1;
+
+/// Derives the AES-256 key from a password with Argon2id
+fn derive_key_argon2id(key: &str, salt: &[u8], params: argon2::Params) -> Result<[u8; 32], ServiceError> {
+    let argon = Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params);
+
+    let mut derived_key = [0u8; 32];
+    argon.hash_password_into(key.as_bytes(), salt, &mut derived_key)?;
+
+    Ok(derived_key)
+}
+
+/// Encrypts `password_plain_text` into a versioned envelope
+/// `v2$m=<m_cost>,t=<t_cost>,p=<p_cost>$<salt>$<nonce>$<ciphertext>`
+/// with a key derived from `key` by Argon2id and a random nonce
 pub async fn encrypt_password(
     password_plain_text: String,
     key: String,
+) -> Result<String, ServiceError> {
+    tokio::task::spawn_blocking(move || {
+        let salt: [u8; 16] = rand::thread_rng().gen();
+        let nonce: [u8; 12] = rand::thread_rng().gen();
+
+        let params = argon2::Params::new(ENVELOPE_V2_M_COST, ENVELOPE_V2_T_COST, ENVELOPE_V2_P_COST, Some(32))?;
+        let derived_key = derive_key_argon2id(&key, &salt, params)?;
+
+        let cipher = aes_gcm_siv::Aes256GcmSiv::new_from_slice(&derived_key).unwrap();
+        let cipher_text = cipher.encrypt(aes_gcm_siv::Nonce::from_slice(&nonce), password_plain_text.as_bytes())?;
+
+        Ok(format!("{}$m={},t={},p={}${}${}${}",
+            ENVELOPE_V2,
+            ENVELOPE_V2_M_COST,
+            ENVELOPE_V2_T_COST,
+            ENVELOPE_V2_P_COST,
+            base64.encode(salt),
+            base64.encode(nonce),
+            base64.encode(cipher_text),
+        ))
+    })
+    .await?
+}
+
+/// Legacy values are plain base64 without a version prefix
+pub fn is_legacy_encrypted_password(password_cipher_text: &str) -> bool {
+    !password_cipher_text.starts_with(&format!("{}$", ENVELOPE_V2))
+}
+
+/// Decrypts both versioned envelopes and legacy values
+pub async fn decrypt_password(
+    password_cipher_text: String,
+    key: String,
+) -> Result<String, ServiceError> {
+    if is_legacy_encrypted_password(&password_cipher_text) {
+        return decrypt_password_legacy(password_cipher_text, key).await;
+    }
+
+    let parts: Vec<&str> = password_cipher_text.split('$').collect();
+    let [_, params, salt, nonce, cipher_text] = parts[..] else {
+        return Err(ServiceError::CryptoDecryptFailed);
+    };
+
+    let params = parse_argon2_params(params)?;
+    let salt = base64.decode(salt)?;
+    let nonce = base64.decode(nonce)?;
+    let cipher_text = base64.decode(cipher_text)?;
+    if nonce.len() != 12 {
+        return Err(ServiceError::CryptoDecryptFailed);
+    }
+
+    let plain = tokio::task::spawn_blocking(move || {
+        let derived_key = derive_key_argon2id(&key, &salt, params)?;
+
+        let cipher = aes_gcm_siv::Aes256GcmSiv::new_from_slice(&derived_key).unwrap();
+        Ok::<_, ServiceError>(cipher.decrypt(aes_gcm_siv::Nonce::from_slice(&nonce), &*cipher_text)?)
+    })
+    .await??;
+
+    Ok(String::from_utf8(plain)?)
+}
+
+fn parse_argon2_params(params: &str) -> Result<argon2::Params, ServiceError> {
+    let mut m_cost = None;
+    let mut t_cost = None;
+    let mut p_cost = None;
+    for param in params.split(',') {
+        let (name, value) = param.split_once('=').ok_or(ServiceError::CryptoDecryptFailed)?;
+        let value = value.parse::<u32>().map_err(|_| ServiceError::CryptoDecryptFailed)?;
+        match name {
+            "m" => m_cost = Some(value),
+            "t" => t_cost = Some(value),
+            "p" => p_cost = Some(value),
+            _ => return Err(ServiceError::CryptoDecryptFailed),
+        }
+    }
+
+    match (m_cost, t_cost, p_cost) {
+        (Some(m), Some(t), Some(p)) => Ok(argon2::Params::new(m, t, p, Some(32))?),
+        _ => Err(ServiceError::CryptoDecryptFailed),
+    }
+}
+
+#[cfg(test)]
+async fn encrypt_password_legacy(
+    password_plain_text: String,
+    key: String,
 ) -> Result<String, ServiceError> {
     let hash = tokio::task::spawn_blocking(move || {
         let aes_key_nonce = convert_key_aes256(&key);
 
-        // Nonce should be always unique, but for our use case it's fine
-        // Also aes-gcm-siv is not vulnerable to nonce reuse
         let nonce = aes_gcm_siv::Nonce::from_slice(&aes_key_nonce[..12]);
-
         let cipher = aes_gcm_siv::Aes256GcmSiv::new_from_slice(&aes_key_nonce[..32]).unwrap();
 
-        let res = cipher.encrypt(nonce, password_plain_text.as_bytes());
-        res
+        cipher.encrypt(nonce, password_plain_text.as_bytes())
     })
     .await??;
 
     Ok(base64.encode(hash))
 }
 
-pub async fn decrypt_password(
+/// Legacy format, the key is the repeated password and the nonce is taken from the key
+async fn decrypt_password_legacy(
     password_cipher_text: String,
     key: String,
 ) -> Result<String, ServiceError> {
@@ -200,6 +315,14 @@ pub fn create_identity() -> (String, String) {
     )
 }
 
+/// Returns the public key belonging to an age private key
+pub fn public_key_from_private_key(key: &str) -> Result<String, ServiceError> {
+    let identity = age::x25519::Identity::from_str(key)
+        .map_err(|e| ServiceError::AgeKeyError(e.to_string()))?;
+
+    Ok(identity.to_public().to_string())
+}
+
 pub async fn encrypt_buffer_with_recipients(
     input_buffer: &[u8],
     recipients: &Vec<String>,
@@ -218,49 +341,140 @@ async fn age_encrypt_with_recipients<W: tokio::io::AsyncWrite + Unpin>(
     output_buffer: &mut W,
     recipients: &Vec<&str>,
 ) -> Result<(), ServiceError> {
+    let mut encrypt_writer = age_encrypt_writer(output_buffer, recipients).await?;
+
+    encrypt_writer.write_all(input_buffer).await?;
+
+    encrypt_writer.flush().await?;
+
+    encrypt_writer.close().await?;
+
+    Ok(())
+}
+
+/// Wraps output in age encryptor, everything written to the returned writer is encrypted on the fly.
+/// Writer must be closed, otherwise the last chunk is never written
+pub async fn age_encrypt_writer<W: tokio::io::AsyncWrite + Unpin>(
+    output: W,
+    recipients: &Vec<&str>,
+) -> Result<EncryptWriter<W>, ServiceError> {
     let public_keys = recipients
-        .into_iter()
+        .iter()
         .map(|recipient| {
-            //TODO: No unwrap
-            Box::new(age::x25519::Recipient::from_str(recipient).unwrap()) as _
+            age::x25519::Recipient::from_str(recipient)
+                .map(|r| Box::new(r) as Box<dyn age::Recipient + Send>)
+                .map_err(|e| ServiceError::AgeKeyError(e.to_string()))
         })
-        .collect();
+        .collect::<Result<Vec<_>, ServiceError>>()?;
 
-    let encryptor_option = age::Encryptor::with_recipients(public_keys);
+    let Some(encryptor) = age::Encryptor::with_recipients(public_keys) else {
+        return Err(ServiceError::AgeNoRecipientsError);
+    };
 
-    if let Some(encryptor) = encryptor_option {
-        let mut encrypt_writer = encryptor
-            .wrap_async_output(output_buffer.compat_mut())
-            .await?;
+    Ok(EncryptWriter(encryptor.wrap_async_output(output.compat()).await?))
+}
 
-        encrypt_writer.write_all(input_buffer).await?;
+/// Age stream writer which never reports a zero length write for non-empty buffer.
+/// age 0.9 returns `Ok(0)` when the internal chunk is exactly full,
+/// which makes `write_all` and `copy` fail with `WriteZero`
+pub struct EncryptWriter<W: tokio::io::AsyncWrite + Unpin>(age::stream::StreamWriter<Compat<W>>);
+
+impl<W: tokio::io::AsyncWrite + Unpin> futures::io::AsyncWrite for EncryptWriter<W> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        loop {
+            let written = ready!(Pin::new(&mut self.0).poll_write(cx, buf))?;
+            if written > 0 || buf.is_empty() {
+                return Poll::Ready(Ok(written));
+            }
+        }
+    }
 
-        encrypt_writer.flush().await?;
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.0).poll_flush(cx)
+    }
 
-        encrypt_writer.close().await?;
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.0).poll_close(cx)
+    }
+}
 
-        return Ok(());
-    } else {
-        return Err(ServiceError::AgeNoRecipientsError);
+/// Passes writes through to the inner writer while computing SHA-256 and size of everything written
+pub struct HashWriter<W: futures::io::AsyncWrite + Unpin> {
+    inner: W,
+    hasher: sha2::Sha256,
+    size: u64,
+}
+
+impl<W: futures::io::AsyncWrite + Unpin> HashWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            hasher: <sha2::Sha256 as sha2::Digest>::new(),
+            size: 0,
+        }
+    }
+
+    /// Returns the inner writer, hex encoded hash and size of the written data
+    pub fn finish(self) -> (W, String, u64) {
+        let hash = sha2::Digest::finalize(self.hasher)
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        (self.inner, hash, self.size)
     }
 }
 
-async fn age_decrypt_with_private_key<R: tokio::io::AsyncRead + Unpin>(
-    input_buffer: R,
-    output_buffer: &mut Vec<u8>,
+impl<W: futures::io::AsyncWrite + Unpin> futures::io::AsyncWrite for HashWriter<W> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        let written = ready!(Pin::new(&mut self.inner).poll_write(cx, buf))?;
+        sha2::Digest::update(&mut self.hasher, &buf[..written]);
+        self.size += written as u64;
+        Poll::Ready(Ok(written))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_close(cx)
+    }
+}
+
+/// Wraps input in age decryptor, reading from the returned reader yields plaintext
+pub async fn age_decrypt_reader<R: tokio::io::AsyncRead + Unpin>(
+    input: R,
     key: &str,
-) -> Result<(), ServiceError> {
-    let decryptor = match age::Decryptor::new_async(input_buffer.compat()).await? {
+) -> Result<Compat<age::stream::StreamReader<Compat<R>>>, ServiceError> {
+    let decryptor = match age::Decryptor::new_async(input.compat()).await? {
         age::Decryptor::Recipients(d) => d,
         _ => unreachable!(),
     };
 
-    let mut decrypt_writer = decryptor.decrypt_async(iter::once(
+    let decrypt_reader = decryptor.decrypt_async(iter::once(
         &age::x25519::Identity::from_str(key)
             .map_err(|e| ServiceError::AgeKeyError(e.to_string()))? as &dyn age::Identity,
     ))?;
 
-    decrypt_writer.read_to_end(output_buffer).await?;
+    Ok(decrypt_reader.compat())
+}
+
+async fn age_decrypt_with_private_key<R: tokio::io::AsyncRead + Unpin>(
+    input_buffer: R,
+    output_buffer: &mut Vec<u8>,
+    key: &str,
+) -> Result<(), ServiceError> {
+    let mut decrypt_reader = age_decrypt_reader(input_buffer, key).await?;
+
+    tokio::io::AsyncReadExt::read_to_end(&mut decrypt_reader, output_buffer).await?;
 
     Ok(())
 }
@@ -295,45 +509,56 @@ pub async fn decrypt_password_with_private_key(
     Ok(string)
 }
 
-pub async fn encrypt_file_with_recipients<P: AsRef<Path>>(
-    plain_file_path: P,
-    cipher_file_path: P,
-    recipients: Vec<&str>,
-) -> Result<(), ServiceError> {
-    let mut cipher_file = tokio::fs::File::create(cipher_file_path).await?;
-    let mut plain_file = tokio::fs::File::open(plain_file_path).await?;
-
-    let mut plain_file_contents = Vec::new();
+/// Hex encoded SHA-256 and size of everything read from input, without buffering the whole input
+pub async fn sha256_stream<R: tokio::io::AsyncRead + Unpin>(input: R) -> Result<(String, u64), ServiceError> {
+    let mut writer = HashWriter::new(futures::io::sink());
+    futures::io::copy(input.compat(), &mut writer).await?;
 
-    tokio::io::AsyncReadExt::read_to_end(&mut plain_file, &mut plain_file_contents).await?;
+    let (_, hash, size) = writer.finish();
+    Ok((hash, size))
+}
 
-    drop(plain_file);
+/// Encrypts everything read from input and writes it to output, without buffering the whole input
+pub async fn encrypt_stream_with_recipients<R, W>(
+    mut input: R,
+    output: W,
+    recipients: &Vec<&str>,
+) -> Result<(), ServiceError>
+where
+    R: tokio::io::AsyncRead + Unpin,
+    W: tokio::io::AsyncWrite + Unpin,
+{
+    let mut encrypt_writer = age_encrypt_writer(output, recipients).await?;
 
-    age_encrypt_with_recipients(
-        plain_file_contents.as_slice(),
-        &mut cipher_file,
-        &recipients,
-    )
-    .await?;
+    futures::io::copy((&mut input).compat(), &mut encrypt_writer).await?;
 
-    tokio::io::AsyncWriteExt::shutdown(&mut cipher_file).await?;
+    // Writes the final chunk and shuts the output down
+    encrypt_writer.close().await?;
 
     Ok(())
 }
 
+pub async fn encrypt_file_with_recipients<P: AsRef<Path>>(
+    plain_file_path: P,
+    cipher_file_path: P,
+    recipients: Vec<&str>,
+) -> Result<(), ServiceError> {
+    let plain_file = tokio::fs::File::open(plain_file_path).await?;
+    let cipher_file = tokio::fs::File::create(cipher_file_path).await?;
+
+    encrypt_stream_with_recipients(plain_file, cipher_file, &recipients).await
+}
+
 pub async fn decrypt_file_with_private_key<P: AsRef<Path>>(
     cipher_file_path: P,
     plain_file_path: P,
     key: &str,
 ) -> Result<(), ServiceError> {
-    let cipher_file = tokio::fs::File::open(cipher_file_path).await?;
+    let mut decrypt_reader = decrypt_file_with_private_key_as_stream(cipher_file_path, key).await?;
     let mut plain_file = tokio::fs::File::create(plain_file_path).await?;
 
-    let mut plain_file_contents = Vec::new();
-
-    age_decrypt_with_private_key(cipher_file, &mut plain_file_contents, key).await?;
-
-    tokio::io::AsyncWriteExt::write_all(&mut plain_file, plain_file_contents.as_slice()).await?;
+    tokio::io::copy(&mut decrypt_reader, &mut plain_file).await?;
+    tokio::io::AsyncWriteExt::shutdown(&mut plain_file).await?;
 
     Ok(())
 }
@@ -351,6 +576,16 @@ pub async fn decrypt_file_with_private_key_as_buffer<P: AsRef<Path>>(
     Ok(plain_file)
 }
 
+/// Returns reader over decrypted file contents, file is decrypted chunk by chunk as it is read
+pub async fn decrypt_file_with_private_key_as_stream<P: AsRef<Path>>(
+    cipher_file_path: P,
+    key: &str,
+) -> Result<impl tokio::io::AsyncRead + Send + Unpin, ServiceError> {
+    let cipher_file = tokio::fs::File::open(cipher_file_path).await?;
+
+    age_decrypt_reader(cipher_file, key).await
+}
+
 #[cfg(test)]
 mod tests {
     use base64::Engine;
@@ -424,7 +659,37 @@ mod tests {
             .await
             .unwrap();
 
-        assert!(base64.decode(encrypted).is_ok());
+        let parts: Vec<&str> = encrypted.split('$').collect();
+        assert_eq!(parts.len(), 5);
+        assert_eq!(parts[0], "v2");
+        assert!(parts[2..].iter().all(|p| base64.decode(p).is_ok()));
+        assert!(!super::is_legacy_encrypted_password(&encrypted));
+    }
+
+    #[tokio::test]
+    async fn test_encrypt_password_random_nonce() {
+        let encrypted_1 = super::encrypt_password("test".to_string(), "key".to_string()).await.unwrap();
+        let encrypted_2 = super::encrypt_password("test".to_string(), "key".to_string()).await.unwrap();
+
+        assert_ne!(encrypted_1, encrypted_2);
+        assert!(super::decrypt_password(encrypted_1, "wrong".to_string()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_decrypt_legacy_password() {
+        const PASSWORD: &str = "test";
+        const KEY: &str = "test";
+
+        let encrypted = super::encrypt_password_legacy(PASSWORD.to_string(), KEY.to_string())
+            .await
+            .unwrap();
+        assert!(super::is_legacy_encrypted_password(&encrypted));
+
+        let decrypted = super::decrypt_password(encrypted, KEY.to_string())
+            .await
+            .unwrap();
+
+        assert_eq!(PASSWORD, decrypted);
     }
 
     #[tokio::test]
@@ -639,4 +904,57 @@ mod tests {
             PASSWORD
         );
     }
+
+    #[tokio::test]
+    async fn test_decrypt_file_with_private_key_as_stream() {
+        const PUBLIC_KEY: &str = "age1t220v5c8ye0pjx99kw8nr57y7a5qlw4ke0wchjuxnr2gcvfzt3hq7fufz0";
+        const PRIVATE_KEY: &str =
+            "AGE-SECRET-KEY-1WPDHL2FLJ23T6RK5KCX8KS8DNLX0CGXMNZG0XNUAH4QP5C8ZZ46QGD3STV";
+
+        // Spans multiple 64 KiB age chunks
+        let plain_buffer: Vec<u8> = (0..200_000).map(|i| (i % 251) as u8).collect();
+
+        let plain_file = async_tempfile::TempFile::new().await.unwrap();
+        let encrypted_file = async_tempfile::TempFile::new().await.unwrap();
+
+        tokio::fs::write(plain_file.file_path(), &plain_buffer).await.unwrap();
+
+        super::encrypt_file_with_recipients(
+            &plain_file.file_path(),
+            &encrypted_file.file_path(),
+            vec![PUBLIC_KEY],
+        )
+        .await
+        .unwrap();
+
+        let mut decrypt_reader =
+            super::decrypt_file_with_private_key_as_stream(encrypted_file.file_path(), PRIVATE_KEY)
+                .await
+                .unwrap();
+
+        let mut decrypted_buffer = Vec::new();
+        tokio::io::AsyncReadExt::read_to_end(&mut decrypt_reader, &mut decrypted_buffer)
+            .await
+            .unwrap();
+
+        assert_eq!(plain_buffer, decrypted_buffer);
+    }
+
+    #[tokio::test]
+    async fn test_encrypt_invalid_recipient() {
+        let res = super::encrypt_password_with_recipients("test", &vec!["not-a-key"]).await;
+
+        assert!(matches!(res, Err(crate::error::ServiceError::AgeKeyError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_hash_writer() {
+        let mut writer = super::HashWriter::new(Vec::new());
+        futures::io::AsyncWriteExt::write_all(&mut writer, b"abc").await.unwrap();
+
+        let (written, hash, size) = writer.finish();
+        assert_eq!(written, b"abc".to_vec());
+        assert_eq!(hash, "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        assert_eq!(size, 3);
+    }
 }
@@ -0,0 +1,71 @@
+use ::entity::{admin, admin_session};
+use log::{info, warn};
+use sea_orm::*;
+
+use crate::Mutation;
+
+impl Mutation {
+    pub async fn create_admin(
+        db: &DbConn,
+        name: String,
+        pubkey: String,
+        encrypted_priv_key: String,
+        password_hash: String,
+        role: String,
+    ) -> Result<admin::Model, DbErr> {
+        let admin = admin::ActiveModel {
+            name: Set(name),
+            role: Set(role),
+            public_key: Set(pubkey),
+            private_key: Set(encrypted_priv_key),
+            password: Set(password_hash),
+            created_at: Set(chrono::offset::Local::now().naive_local()),
+            updated_at: Set(chrono::offset::Local::now().naive_local()),
+            ..Default::default()
+        }
+            .insert(db)
+            .await?;
+
+        info!("ADMIN {} CREATED", admin.id);
+        Ok(admin)
+    }
+
+    pub async fn delete_admin<C: ConnectionTrait>(
+        db: &C,
+        admin: admin::Model,
+    ) -> Result<DeleteResult, DbErr> {
+        let admin_id = admin.id;
+        let delete = admin.delete(db).await?;
+
+        warn!("ADMIN {} DELETED", admin_id);
+        Ok(delete)
+    }
+
+    pub async fn update_admin_keys<C: ConnectionTrait>(
+        db: &C,
+        admin: admin::Model,
+        pubkey: String,
+        encrypted_priv_key: String,
+    ) -> Result<admin::Model, DbErr> {
+        let admin_id = admin.id;
+        let mut admin = admin.into_active_model();
+        admin.public_key = Set(pubkey);
+        admin.private_key = Set(encrypted_priv_key);
+        admin.updated_at = Set(chrono::offset::Local::now().naive_local());
+
+        let update = admin.update(db).await?;
+
+        info!("ADMIN {} KEYS UPDATED", admin_id);
+        Ok(update)
+    }
+
+    pub async fn delete_admin_sessions<C: ConnectionTrait>(
+        db: &C,
+        admin_id: i32,
+    ) -> Result<DeleteResult, DbErr> {
+        admin_session::Entity::delete_many()
+            .filter(admin_session::Column::AdminId.eq(admin_id))
+            .exec(db)
+            .await
+    }
+}
@@ -0,0 +1,47 @@
+use ::entity::admin_key_rotation;
+use log::info;
+use sea_orm::*;
+
+use crate::Mutation;
+
+impl Mutation {
+    pub async fn create_admin_key_rotation(
+        db: &DbConn,
+        admin_id: i32,
+        pubkey: String,
+        encrypted_priv_key: String,
+    ) -> Result<admin_key_rotation::Model, DbErr> {
+        let rotation = admin_key_rotation::ActiveModel {
+            admin_id: Set(admin_id),
+            public_key: Set(pubkey),
+            private_key: Set(encrypted_priv_key),
+            last_candidate_id: Set(0),
+            created_at: Set(chrono::offset::Local::now().naive_local()),
+            updated_at: Set(chrono::offset::Local::now().naive_local()),
+        }
+            .insert(db)
+            .await?;
+
+        info!("ADMIN {} KEY ROTATION CREATED", admin_id);
+        Ok(rotation)
+    }
+
+    pub async fn update_admin_key_rotation_progress<C: ConnectionTrait>(
+        db: &C,
+        rotation: admin_key_rotation::Model,
+        last_candidate_id: i32,
+    ) -> Result<admin_key_rotation::Model, DbErr> {
+        let mut rotation = rotation.into_active_model();
+        rotation.last_candidate_id = Set(last_candidate_id);
+        rotation.updated_at = Set(chrono::offset::Local::now().naive_local());
+
+        rotation.update(db).await
+    }
+
+    pub async fn delete_admin_key_rotation<C: ConnectionTrait>(
+        db: &C,
+        rotation: admin_key_rotation::Model,
+    ) -> Result<DeleteResult, DbErr> {
+        rotation.delete(db).await
+    }
+}
@@ -0,0 +1,59 @@
+use ::entity::admission_round;
+use log::{info, warn};
+use sea_orm::*;
+
+use crate::Mutation;
+
+impl Mutation {
+    pub async fn create_admission_round(
+        db: &DbConn,
+        name: String,
+    ) -> Result<admission_round::Model, DbErr> {
+        let round = admission_round::ActiveModel {
+            name: Set(name),
+            created_at: Set(chrono::offset::Local::now().naive_local()),
+            ..Default::default()
+        }
+            .insert(db)
+            .await?;
+
+        info!("ADMISSION ROUND {} CREATED", round.id);
+        Ok(round)
+    }
+
+    pub async fn close_admission_round(
+        db: &DbConn,
+        round: admission_round::Model,
+    ) -> Result<admission_round::Model, DbErr> {
+        let mut round = round.into_active_model();
+        round.closed_at = Set(Some(chrono::offset::Local::now().naive_local()));
+
+        let round = round.update(db).await?;
+        info!("ADMISSION ROUND {} CLOSED", round.id);
+        Ok(round)
+    }
+
+    pub async fn set_admission_round_archived(
+        db: &DbConn,
+        round: admission_round::Model,
+    ) -> Result<admission_round::Model, DbErr> {
+        let mut round = round.into_active_model();
+        round.archived_at = Set(Some(chrono::offset::Local::now().naive_local()));
+
+        let round = round.update(db).await?;
+        info!("ADMISSION ROUND {} ARCHIVED", round.id);
+        Ok(round)
+    }
+
+    pub async fn set_admission_round_purged<C: ConnectionTrait>(
+        db: &C,
+        round: admission_round::Model,
+    ) -> Result<admission_round::Model, DbErr> {
+        let mut round = round.into_active_model();
+        round.purged_at = Set(Some(chrono::offset::Local::now().naive_local()));
+
+        let round = round.update(db).await?;
+        warn!("ADMISSION ROUND {} PURGED", round.id);
+        Ok(round)
+    }
+}
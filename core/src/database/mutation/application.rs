@@ -1,6 +1,6 @@
-use ::entity::application;
+use ::entity::{application, candidate};
 use log::{info, warn};
-use sea_orm::{DbConn, DbErr, Set, ActiveModelTrait, IntoActiveModel, DeleteResult, ModelTrait};
+use sea_orm::{DbConn, DbErr, Set, ActiveModelTrait, IntoActiveModel, DeleteResult, ModelTrait, ConnectionTrait};
 
 use crate::{Mutation, models::candidate::FieldOfStudy};
 
@@ -8,19 +8,21 @@ impl Mutation {
     pub async fn create_application(
         db: &DbConn,
         application_id: i32,
-        candidate_id: i32,
+        candidate: &candidate::Model,
         hashed_password: String,
         enc_personal_id_number: String,
         pubkey: String,
         encrypted_priv_key: String,
     ) -> Result<application::Model, DbErr> {
-        let field_of_study = FieldOfStudy::from(application_id);
+        let field_of_study = FieldOfStudy::try_from(application_id)
+            .map_err(|e| DbErr::Custom(e.to_string()))?;
         let insert = application::ActiveModel {
             id: Set(application_id),
             field_of_study: Set(field_of_study.into()),
             personal_id_number: Set(enc_personal_id_number),
             password: Set(hashed_password),
-            candidate_id: Set(candidate_id),
+            candidate_id: Set(candidate.id),
+            admission_round_id: Set(candidate.admission_round_id),
             public_key: Set(pubkey),
             private_key: Set(encrypted_priv_key),
             created_at: Set(chrono::offset::Local::now().naive_local()),
@@ -33,8 +35,8 @@ impl Mutation {
         Ok(insert)
     }
 
-    pub async fn delete_application(
-        db: &DbConn,
+    pub async fn delete_application<C: ConnectionTrait>(
+        db: &C,
         application: application::Model,
     ) -> Result<DeleteResult, DbErr> {
         let application_id = application.id;
@@ -62,4 +64,47 @@ impl Mutation {
         warn!("CANDIDATE {} PASSWORD CHANGED", application_id);
         Ok(update)
     }
-}
\ No newline at end of file
+
+    /// Keeps the keypair, only the private key is encrypted with the new password
+    pub async fn update_application_password(
+        db: &DbConn,
+        application: application::Model,
+        new_password_hash: String,
+        priv_key_enc: String,
+    ) -> Result<application::Model, DbErr> {
+        let application_id = application.id;
+        let mut application = application.into_active_model();
+        application.password = Set(new_password_hash);
+        application.private_key = Set(priv_key_enc);
+        application.updated_at = Set(chrono::offset::Local::now().naive_local());
+
+        let update = application.update(db).await?;
+
+        warn!("CANDIDATE {} CHANGED PASSWORD", application_id);
+        Ok(update)
+    }
+
+    pub async fn update_application_private_key(
+        db: &DbConn,
+        application: application::Model,
+        priv_key_enc: String,
+    ) -> Result<application::Model, DbErr> {
+        let mut application = application.into_active_model();
+        application.private_key = Set(priv_key_enc);
+        application.updated_at = Set(chrono::offset::Local::now().naive_local());
+
+        application.update(db).await
+    }
+
+    pub async fn update_application_personal_id_number<C: ConnectionTrait>(
+        db: &C,
+        application: application::Model,
+        enc_personal_id_number: String,
+    ) -> Result<application::Model, DbErr> {
+        let mut application = application.into_active_model();
+        application.personal_id_number = Set(enc_personal_id_number);
+        application.updated_at = Set(chrono::offset::Local::now().naive_local());
+
+        application.update(db).await
+    }
+}
@@ -0,0 +1,27 @@
+use ::entity::audit_log;
+use sea_orm::*;
+
+use crate::Mutation;
+
+impl Mutation {
+    pub async fn insert_audit_log(
+        db: &DbConn,
+        actor_type: String,
+        actor_id: i32,
+        action: String,
+        target_id: Option<i32>,
+        ip_address: Option<String>,
+    ) -> Result<audit_log::Model, DbErr> {
+        audit_log::ActiveModel {
+            actor_type: Set(actor_type),
+            actor_id: Set(actor_id),
+            action: Set(action),
+            target_id: Set(target_id),
+            ip_address: Set(ip_address),
+            created_at: Set(chrono::offset::Local::now().naive_local()),
+            ..Default::default()
+        }
+            .insert(db)
+            .await
+    }
+}
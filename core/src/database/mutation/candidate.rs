@@ -8,9 +8,13 @@ impl Mutation {
     pub async fn create_candidate(
         db: &DbConn,
         enc_personal_id_number: String,
+        personal_id_blind_index: Option<String>,
+        admission_round_id: i32,
     ) -> Result<candidate::Model, DbErr> {
         let candidate = candidate::ActiveModel {
             personal_identification_number: Set(enc_personal_id_number),
+            personal_id_blind_index: Set(personal_id_blind_index),
+            admission_round_id: Set(admission_round_id),
             created_at: Set(chrono::offset::Local::now().naive_local()),
             updated_at: Set(chrono::offset::Local::now().naive_local()),
             ..Default::default()
@@ -22,8 +26,8 @@ impl Mutation {
         Ok(candidate)
     }
 
-    pub async fn delete_candidate(
-        db: &DbConn,
+    pub async fn delete_candidate<C: ConnectionTrait>(
+        db: &C,
         candidate: candidate::Model,
     ) -> Result<DeleteResult, DbErr> {
         let application = candidate.id;
@@ -70,6 +74,65 @@ impl Mutation {
         Ok(update)
     }
 
+    /// Replaces all encrypted columns, used when data is re-encrypted to a new set of recipients
+    pub async fn update_candidate_encrypted_details<C: ConnectionTrait>(
+        db: &C,
+        candidate: candidate::Model,
+        enc_candidate: EncryptedCandidateDetails,
+    ) -> Result<candidate::Model, DbErr> {
+        let mut candidate: candidate::ActiveModel = candidate.into();
+
+        candidate.name = Set(enc_candidate.name.map(|e| e.into()));
+        candidate.surname = Set(enc_candidate.surname.map(|e| e.into()));
+        candidate.birth_surname = Set(enc_candidate.birth_surname.map(|e| e.into()));
+        candidate.birthplace = Set(enc_candidate.birthplace.map(|e| e.into()));
+        candidate.birthdate = Set(enc_candidate.birthdate.map(|e| e.into()));
+        candidate.address = Set(enc_candidate.address.map(|e| e.into()));
+        candidate.letter_address = Set(enc_candidate.letter_address.map(|e| e.into()));
+        candidate.telephone = Set(enc_candidate.telephone.map(|e| e.into()));
+        candidate.citizenship = Set(enc_candidate.citizenship.map(|e| e.into()));
+        candidate.email = Set(enc_candidate.email.map(|e| e.into()));
+        candidate.sex = Set(enc_candidate.sex.map(|e| e.into()));
+        if let Some(personal_id_number) = enc_candidate.personal_id_number {
+            candidate.personal_identification_number = Set(personal_id_number.into());
+        }
+        candidate.school_name = Set(enc_candidate.school_name.map(|e| e.into()));
+        candidate.health_insurance = Set(enc_candidate.health_insurance.map(|e| e.into()));
+        candidate.grades_json = Set(enc_candidate.grades_json.map(|e| e.into()));
+        candidate.first_school = Set(enc_candidate.first_school.map(|e| e.into()));
+        candidate.second_school = Set(enc_candidate.second_school.map(|e| e.into()));
+
+        candidate.updated_at = Set(chrono::offset::Local::now().naive_local());
+
+        candidate.update(db).await
+    }
+
+    pub async fn update_candidate_blind_index(
+        db: &DbConn,
+        candidate: candidate::Model,
+        personal_id_blind_index: Option<String>,
+    ) -> Result<candidate::Model, DbErr> {
+        let mut candidate = candidate.into_active_model();
+        candidate.personal_id_blind_index = Set(personal_id_blind_index);
+
+        candidate
+            .update(db)
+            .await
+    }
+
+    pub async fn update_candidate_deadline_extension(
+        db: &DbConn,
+        candidate: candidate::Model,
+        deadline_extension: Option<chrono::NaiveDateTime>,
+    ) -> Result<candidate::Model, DbErr> {
+        let mut candidate = candidate.into_active_model();
+        candidate.deadline_extension = Set(deadline_extension);
+
+        candidate
+            .update(db)
+            .await
+    }
+
     pub async fn update_personal_id(
         db: &DbConn,
         candidate: candidate::Model,
@@ -96,9 +159,12 @@ mod tests {
     async fn test_create_candidate() {
         let db = get_memory_sqlite_connection().await;
 
+        let round = Mutation::create_admission_round(&db, "2023".to_string()).await.unwrap();
         let candidate = Mutation::create_candidate(
             &db,
             "".to_string(),
+            None,
+            round.id,
         )
         .await
         .unwrap();
@@ -113,9 +179,12 @@ mod tests {
     async fn test_add_candidate_details() {
         let db = get_memory_sqlite_connection().await;
 
+        let round = Mutation::create_admission_round(&db, "2023".to_string()).await.unwrap();
         let candidate = Mutation::create_candidate(
             &db,
             "".to_string(),
+            None,
+            round.id,
         )
         .await
         .unwrap();
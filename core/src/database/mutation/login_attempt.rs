@@ -0,0 +1,51 @@
+use ::entity::{login_attempt, login_attempt::Entity as LoginAttempt};
+use chrono::NaiveDateTime;
+use sea_orm::*;
+
+use crate::Mutation;
+
+impl Mutation {
+    pub async fn create_login_attempt(
+        db: &DbConn,
+        scope: String,
+        key: String,
+        locked_until: Option<NaiveDateTime>,
+    ) -> Result<login_attempt::Model, DbErr> {
+        login_attempt::ActiveModel {
+            scope: Set(scope),
+            key: Set(key),
+            failed_count: Set(1),
+            locked_until: Set(locked_until),
+            last_failed_at: Set(chrono::offset::Local::now().naive_local()),
+            ..Default::default()
+        }
+            .insert(db)
+            .await
+    }
+
+    pub async fn update_login_attempt(
+        db: &DbConn,
+        attempt: login_attempt::Model,
+        failed_count: i32,
+        locked_until: Option<NaiveDateTime>,
+    ) -> Result<login_attempt::Model, DbErr> {
+        let mut attempt = attempt.into_active_model();
+        attempt.failed_count = Set(failed_count);
+        attempt.locked_until = Set(locked_until);
+        attempt.last_failed_at = Set(chrono::offset::Local::now().naive_local());
+
+        attempt.update(db).await
+    }
+
+    pub async fn delete_login_attempt(
+        db: &DbConn,
+        scope: &str,
+        key: &str,
+    ) -> Result<DeleteResult, DbErr> {
+        LoginAttempt::delete_many()
+            .filter(login_attempt::Column::Scope.eq(scope))
+            .filter(login_attempt::Column::Key.eq(key))
+            .exec(db)
+            .await
+    }
+}
@@ -1,7 +1,15 @@
-pub(crate) struct Mutation;
+pub struct Mutation;
 
 pub mod application;
 pub mod session;
 pub mod candidate;
 pub mod parent;
-pub mod admin_session;
\ No newline at end of file
+pub mod admin_session;
+pub mod admin;
+pub mod admin_key_rotation;
+pub mod audit_log;
+pub mod login_attempt;
+pub mod admission_round;
+pub mod notification;
+pub mod portfolio_version;
+pub mod review;
\ No newline at end of file
@@ -0,0 +1,56 @@
+use ::entity::{notification, notification::Entity as Notification};
+use sea_orm::*;
+
+use crate::Mutation;
+
+impl Mutation {
+    pub async fn create_notification(
+        db: &DbConn,
+        candidate_id: i32,
+        kind: String,
+        locale: String,
+        status: String,
+    ) -> Result<notification::Model, DbErr> {
+        notification::ActiveModel {
+            candidate_id: Set(candidate_id),
+            kind: Set(kind),
+            locale: Set(locale),
+            status: Set(status),
+            attempts: Set(0),
+            created_at: Set(chrono::offset::Local::now().naive_local()),
+            ..Default::default()
+        }
+            .insert(db)
+            .await
+    }
+
+    /// Records a delivery attempt, `sent` is true when the attempt succeeded
+    pub async fn update_notification_attempt(
+        db: &DbConn,
+        notification: notification::Model,
+        status: String,
+        error: Option<String>,
+        sent: bool,
+    ) -> Result<notification::Model, DbErr> {
+        let attempts = notification.attempts + 1;
+        let mut notification = notification.into_active_model();
+        notification.status = Set(status);
+        notification.attempts = Set(attempts);
+        notification.last_error = Set(error);
+        if sent {
+            notification.sent_at = Set(Some(chrono::offset::Local::now().naive_local()));
+        }
+
+        notification.update(db).await
+    }
+
+    pub async fn delete_candidate_notifications<C: ConnectionTrait>(
+        db: &C,
+        candidate_id: i32,
+    ) -> Result<DeleteResult, DbErr> {
+        Notification::delete_many()
+            .filter(notification::Column::CandidateId.eq(candidate_id))
+            .exec(db)
+            .await
+    }
+}
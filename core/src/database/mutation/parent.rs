@@ -15,14 +15,14 @@ impl Mutation {
         .await
     }
 
-    pub async fn delete_parent(db: &DbConn, parent: Model) -> Result<DeleteResult, DbErr> {
+    pub async fn delete_parent<C: ConnectionTrait>(db: &C, parent: Model) -> Result<DeleteResult, DbErr> {
         parent
             .delete(db)
             .await
     }
 
-    pub async fn add_parent_details(
-        db: &DbConn,
+    pub async fn add_parent_details<C: ConnectionTrait>(
+        db: &C,
         parent: Model,
         enc_parent: EncryptedParentDetails,
     ) -> Result<Model, sea_orm::DbErr> {
@@ -49,9 +49,12 @@ mod tests {
     async fn test_create_parent() {
         let db = get_memory_sqlite_connection().await;
 
+        let round = Mutation::create_admission_round(&db, "2023".to_string()).await.unwrap();
         let candidate = Mutation::create_candidate(
             &db,
             "".to_string(),
+            None,
+            round.id,
         )
         .await
         .unwrap();
@@ -66,9 +69,12 @@ mod tests {
     async fn test_add_candidate_details() {
         let db = get_memory_sqlite_connection().await;
 
+        let round = Mutation::create_admission_round(&db, "2023".to_string()).await.unwrap();
         let candidate = Mutation::create_candidate(
             &db,
             "".to_string(),
+            None,
+            round.id,
         )
         .await
         .unwrap();
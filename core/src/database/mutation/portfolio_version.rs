@@ -0,0 +1,63 @@
+use ::entity::{portfolio_version, portfolio_version::Entity as PortfolioVersion};
+use sea_orm::*;
+
+use crate::{Mutation, models::portfolio_version::ArchiveManifest};
+
+impl Mutation {
+    pub async fn create_portfolio_version(
+        db: &DbConn,
+        candidate_id: i32,
+        version: i32,
+        manifest: ArchiveManifest,
+        ciphertext_hash: String,
+        ciphertext_size: i64,
+    ) -> Result<portfolio_version::Model, DbErr> {
+        let files = serde_json::to_string(&manifest.files)
+            .map_err(|e| DbErr::Custom(e.to_string()))?;
+
+        portfolio_version::ActiveModel {
+            candidate_id: Set(candidate_id),
+            version: Set(version),
+            hash: Set(manifest.hash),
+            size: Set(manifest.size),
+            files: Set(Some(files)),
+            ciphertext_hash: Set(Some(ciphertext_hash)),
+            ciphertext_size: Set(Some(ciphertext_size)),
+            created_at: Set(chrono::offset::Local::now().naive_local()),
+            ..Default::default()
+        }
+            .insert(db)
+            .await
+    }
+
+    /// Records new ciphertext after the version was re-encrypted
+    pub async fn update_portfolio_version_ciphertext<C: ConnectionTrait>(
+        db: &C,
+        version: portfolio_version::Model,
+        ciphertext_hash: String,
+        ciphertext_size: i64,
+    ) -> Result<portfolio_version::Model, DbErr> {
+        let mut version = version.into_active_model();
+        version.ciphertext_hash = Set(Some(ciphertext_hash));
+        version.ciphertext_size = Set(Some(ciphertext_size));
+
+        version.update(db).await
+    }
+
+    pub async fn delete_portfolio_version(
+        db: &DbConn,
+        version: portfolio_version::Model,
+    ) -> Result<DeleteResult, DbErr> {
+        version.delete(db).await
+    }
+
+    pub async fn delete_candidate_portfolio_versions<C: ConnectionTrait>(
+        db: &C,
+        candidate_id: i32,
+    ) -> Result<DeleteResult, DbErr> {
+        PortfolioVersion::delete_many()
+            .filter(portfolio_version::Column::CandidateId.eq(candidate_id))
+            .exec(db)
+            .await
+    }
+}
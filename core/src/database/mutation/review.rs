@@ -0,0 +1,77 @@
+use ::entity::{review, review::Entity as Review};
+use sea_orm::*;
+
+use crate::Mutation;
+
+impl Mutation {
+    pub async fn create_review_assignment(
+        db: &DbConn,
+        application_id: i32,
+        admin_id: i32,
+    ) -> Result<review::Model, DbErr> {
+        review::ActiveModel {
+            application_id: Set(application_id),
+            admin_id: Set(admin_id),
+            assigned_at: Set(chrono::offset::Local::now().naive_local()),
+            ..Default::default()
+        }
+            .insert(db)
+            .await
+    }
+
+    /// Stores submitted scores and comment, both already encrypted
+    pub async fn update_review(
+        db: &DbConn,
+        review: review::Model,
+        scores: String,
+        comment: Option<String>,
+    ) -> Result<review::Model, DbErr> {
+        let mut review = review.into_active_model();
+        review.scores = Set(Some(scores));
+        review.comment = Set(comment);
+        review.submitted_at = Set(Some(chrono::offset::Local::now().naive_local()));
+
+        review.update(db).await
+    }
+
+    /// Replaces ciphertext after the review was re-encrypted, submission time is kept
+    pub async fn update_review_encrypted<C: ConnectionTrait>(
+        db: &C,
+        review: review::Model,
+        scores: Option<String>,
+        comment: Option<String>,
+    ) -> Result<review::Model, DbErr> {
+        let mut review = review.into_active_model();
+        review.scores = Set(scores);
+        review.comment = Set(comment);
+
+        review.update(db).await
+    }
+
+    pub async fn delete_review(
+        db: &DbConn,
+        review: review::Model,
+    ) -> Result<DeleteResult, DbErr> {
+        review.delete(db).await
+    }
+
+    pub async fn delete_admin_reviews<C: ConnectionTrait>(
+        db: &C,
+        admin_id: i32,
+    ) -> Result<DeleteResult, DbErr> {
+        Review::delete_many()
+            .filter(review::Column::AdminId.eq(admin_id))
+            .exec(db)
+            .await
+    }
+
+    pub async fn delete_application_reviews<C: ConnectionTrait>(
+        db: &C,
+        application_id: i32,
+    ) -> Result<DeleteResult, DbErr> {
+        Review::delete_many()
+            .filter(review::Column::ApplicationId.eq(application_id))
+            .exec(db)
+            .await
+    }
+}
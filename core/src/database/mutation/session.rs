@@ -39,7 +39,7 @@ impl Mutation {
         session.update(db).await
     }
 
-    pub async fn delete_session<T>(db: &DbConn, session: T) -> Result<DeleteResult, DbErr> 
+    pub async fn delete_session<C: ConnectionTrait, T>(db: &C, session: T) -> Result<DeleteResult, DbErr> 
     where T: ActiveModelTrait + std::marker::Send + ActiveModelBehavior
     {
         session
@@ -1,6 +1,6 @@
 use crate::Query;
 
-use ::entity::{admin, admin::Entity as Admin};
+use ::entity::{admin, admin::Entity as Admin, admin_key_rotation::Entity as AdminKeyRotation};
 use sea_orm::*;
 
 impl Query {
@@ -8,12 +8,23 @@ impl Query {
         Admin::find_by_id(id).one(db).await
     }
 
+    pub async fn find_admin_by_public_key(db: &DbConn, public_key: &str) -> Result<Option<admin::Model>, DbErr> {
+        Admin::find()
+            .filter(admin::Column::PublicKey.eq(public_key))
+            .one(db)
+            .await
+    }
+
+    /// Public keys of all admins, including keys of unfinished key rotations,
+    /// so that data written during a rotation is readable with the new key as well
     pub async fn get_all_admin_public_keys(db: &DbConn) -> Result<Vec<String>, DbErr> {
         let admins = Admin::find().all(db).await?;
+        let rotations = AdminKeyRotation::find().all(db).await?;
 
         let public_keys = admins
             .iter()
             .map(|admin| admin.public_key.to_owned())
+            .chain(rotations.iter().map(|rotation| rotation.public_key.to_owned()))
             .collect();
 
         Ok(public_keys)
@@ -26,7 +37,7 @@ mod tests {
     use sea_orm::{ActiveModelTrait, Set};
 
     use crate::utils::db::get_memory_sqlite_connection;
-    use crate::Query;
+    use crate::{Mutation, Query};
 
     #[tokio::test]
     async fn test_find_admin_by_id() {
@@ -77,4 +88,30 @@ mod tests {
             assert!(public_keys.contains(&format!("valid_public_key_{}", index)));
         }
     }
+
+    #[tokio::test]
+    async fn test_get_all_admin_public_keys_with_pending_rotation() {
+        let db = get_memory_sqlite_connection().await;
+        let admin = admin::ActiveModel {
+            id: Set(1),
+            name: Set("admin_1".to_string()),
+            public_key: Set("old_public_key".to_string()),
+            private_key: Set("test".to_string()),
+            password: Set("test".to_string()),
+            created_at: Set(chrono::offset::Local::now().naive_local()),
+            updated_at: Set(chrono::offset::Local::now().naive_local()),
+            ..Default::default()
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+
+        Mutation::create_admin_key_rotation(&db, admin.id, "new_public_key".to_string(), "test".to_string())
+            .await
+            .unwrap();
+
+        let public_keys = Query::get_all_admin_public_keys(&db).await.unwrap();
+
+        assert_eq!(public_keys, vec!["old_public_key".to_string(), "new_public_key".to_string()]);
+    }
 }
@@ -0,0 +1,36 @@
+use crate::Query;
+
+use ::entity::{admin_key_rotation, admin_key_rotation::Entity as AdminKeyRotation};
+use sea_orm::*;
+
+impl Query {
+    pub async fn find_admin_key_rotation(
+        db: &DbConn,
+        admin_id: i32,
+    ) -> Result<Option<admin_key_rotation::Model>, DbErr> {
+        AdminKeyRotation::find_by_id(admin_id).one(db).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::services::admin_service::admin_tests::create_admin;
+    use crate::utils::db::get_memory_sqlite_connection;
+    use crate::{Mutation, Query};
+
+    #[tokio::test]
+    async fn test_find_admin_key_rotation() {
+        let db = get_memory_sqlite_connection().await;
+        let admin = create_admin(&db).await;
+
+        assert!(Query::find_admin_key_rotation(&db, admin.id).await.unwrap().is_none());
+
+        Mutation::create_admin_key_rotation(&db, admin.id, "pubkey".to_string(), "privkey".to_string())
+            .await
+            .unwrap();
+
+        let rotation = Query::find_admin_key_rotation(&db, admin.id).await.unwrap().unwrap();
+        assert_eq!(rotation.public_key, "pubkey");
+        assert_eq!(rotation.last_candidate_id, 0);
+    }
+}
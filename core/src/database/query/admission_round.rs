@@ -0,0 +1,33 @@
+use crate::Query;
+
+use ::entity::{admission_round, admission_round::Entity as AdmissionRound};
+use sea_orm::*;
+
+impl Query {
+    pub async fn find_admission_round_by_id(
+        db: &DbConn,
+        id: i32,
+    ) -> Result<Option<admission_round::Model>, DbErr> {
+        AdmissionRound::find_by_id(id).one(db).await
+    }
+
+    /// Newest round which is not closed yet
+    pub async fn find_open_admission_round(
+        db: &DbConn,
+    ) -> Result<Option<admission_round::Model>, DbErr> {
+        AdmissionRound::find()
+            .filter(admission_round::Column::ClosedAt.is_null())
+            .order_by(admission_round::Column::Id, Order::Desc)
+            .one(db)
+            .await
+    }
+
+    pub async fn list_admission_rounds(
+        db: &DbConn,
+    ) -> Result<Vec<admission_round::Model>, DbErr> {
+        AdmissionRound::find()
+            .order_by(admission_round::Column::Id, Order::Asc)
+            .all(db)
+            .await
+    }
+}
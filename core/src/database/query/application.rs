@@ -14,6 +14,7 @@ pub struct ApplicationCandidateJoin {
     pub email: Option<String>,
     pub telephone: Option<String>,
     pub field_of_study: Option<String>,
+    pub admission_round_id: i32,
     pub created_at: NaiveDateTime,
 }
 
@@ -63,10 +64,15 @@ impl Query {
     pub async fn list_applications(
         db: &DbConn,
         field_of_study: Option<String>,
+        admission_round_id: Option<i32>,
         page: Option<u64>,
         sort: Option<String>,
     ) -> Result<Vec<ApplicationCandidateJoin>, DbErr> {
-        let select = application::Entity::find();
+        let select = if let Some(round) = admission_round_id {
+            application::Entity::find().filter(application::Column::AdmissionRoundId.eq(round))
+        } else {
+            application::Entity::find()
+        };
         let (column, order) = if let Some(sort) = sort {
             get_ordering(sort)
         } else {
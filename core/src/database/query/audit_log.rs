@@ -0,0 +1,49 @@
+use crate::{Query, models::audit::AuditLogFilter};
+
+use ::entity::{audit_log, audit_log::Entity as AuditLog};
+use sea_orm::*;
+
+const PAGE_SIZE: u64 = 50;
+
+impl Query {
+    /// Newest entries first
+    pub async fn list_audit_logs(
+        db: &DbConn,
+        filter: &AuditLogFilter,
+        page: Option<u64>,
+    ) -> Result<Vec<audit_log::Model>, DbErr> {
+        let mut condition = Condition::all();
+        if let Some(actor_type) = &filter.actor_type {
+            condition = condition.add(audit_log::Column::ActorType.eq(actor_type.to_owned()));
+        }
+        if let Some(actor_id) = filter.actor_id {
+            condition = condition.add(audit_log::Column::ActorId.eq(actor_id));
+        }
+        if let Some(action) = &filter.action {
+            condition = condition.add(audit_log::Column::Action.eq(action.to_owned()));
+        }
+        if let Some(target_id) = filter.target_id {
+            condition = condition.add(audit_log::Column::TargetId.eq(target_id));
+        }
+        if let Some(from) = filter.from {
+            condition = condition.add(audit_log::Column::CreatedAt.gte(from));
+        }
+        if let Some(to) = filter.to {
+            condition = condition.add(audit_log::Column::CreatedAt.lt(to));
+        }
+
+        let query = AuditLog::find()
+            .filter(condition)
+            .order_by(audit_log::Column::CreatedAt, Order::Desc)
+            .order_by(audit_log::Column::Id, Order::Desc);
+
+        if let Some(page) = page {
+            query
+                .paginate(db, PAGE_SIZE)
+                .fetch_page(page).await
+        } else {
+            query
+                .all(db).await
+        }
+    }
+}
@@ -53,6 +53,63 @@ impl Query {
             .await
     }
 
+    pub async fn list_candidates_in_round(
+        db: &DbConn,
+        admission_round_id: i32,
+    ) -> Result<Vec<candidate::Model>, DbErr> {
+        Candidate::find()
+            .filter(candidate::Column::AdmissionRoundId.eq(admission_round_id))
+            .order_by(candidate::Column::Id, Order::Asc)
+            .all(db)
+            .await
+    }
+
+    pub async fn find_candidate_by_personal_id_blind_index(
+        db: &DbConn,
+        admission_round_id: i32,
+        blind_index: &str,
+    ) -> Result<Option<candidate::Model>, DbErr> {
+        Candidate::find()
+            .filter(candidate::Column::AdmissionRoundId.eq(admission_round_id))
+            .filter(candidate::Column::PersonalIdBlindIndex.eq(blind_index))
+            .one(db)
+            .await
+    }
+
+    pub async fn list_candidates_without_personal_id_blind_index(
+        db: &DbConn,
+        admission_round_id: i32,
+    ) -> Result<Vec<candidate::Model>, DbErr> {
+        Candidate::find()
+            .filter(candidate::Column::AdmissionRoundId.eq(admission_round_id))
+            .filter(candidate::Column::PersonalIdBlindIndex.is_null())
+            .order_by(candidate::Column::Id, Order::Asc)
+            .all(db)
+            .await
+    }
+
+    /// Candidates created before blind indexes were introduced
+    pub async fn list_candidates_without_blind_index(
+        db: &DbConn,
+    ) -> Result<Vec<candidate::Model>, DbErr> {
+        Candidate::find()
+            .filter(candidate::Column::PersonalIdBlindIndex.is_null())
+            .order_by(candidate::Column::Id, Order::Asc)
+            .all(db)
+            .await
+    }
+
+    pub async fn list_candidates_after_id(
+        db: &DbConn,
+        id: i32,
+    ) -> Result<Vec<candidate::Model>, DbErr> {
+        Candidate::find()
+            .filter(candidate::Column::Id.gt(id))
+            .order_by(candidate::Column::Id, Order::Asc)
+            .all(db)
+            .await
+    }
+
     pub async fn list_all_candidate_ids(
         db: &DbConn,
     ) -> Result<Vec<ApplicationId>, DbErr> {
@@ -81,13 +138,15 @@ mod tests {
 
     use entity::candidate;
 
-    use crate::Query;
+    use crate::{Mutation, Query};
     use crate::utils::db::get_memory_sqlite_connection;
 
     #[tokio::test]
     async fn test_find_candidate_by_id() {
         let db = get_memory_sqlite_connection().await;
+        let round = Mutation::create_admission_round(&db, "2023".to_string()).await.unwrap();
         let candidate = candidate::ActiveModel {
+            admission_round_id: Set(round.id),
             id: Set(103158),
             personal_identification_number: Set("test".to_string()),
             created_at: Set(chrono::offset::Local::now().naive_local()),
@@ -0,0 +1,18 @@
+use crate::Query;
+
+use ::entity::{login_attempt, login_attempt::Entity as LoginAttempt};
+use sea_orm::*;
+
+impl Query {
+    pub async fn find_login_attempt(
+        db: &DbConn,
+        scope: &str,
+        key: &str,
+    ) -> Result<Option<login_attempt::Model>, DbErr> {
+        LoginAttempt::find()
+            .filter(login_attempt::Column::Scope.eq(scope))
+            .filter(login_attempt::Column::Key.eq(key))
+            .one(db)
+            .await
+    }
+}
@@ -3,5 +3,12 @@ pub struct Query;
 pub mod application;
 pub mod candidate;
 pub mod admin;
+pub mod admin_key_rotation;
 pub mod session;
-pub mod parent;
\ No newline at end of file
+pub mod parent;
+pub mod audit_log;
+pub mod login_attempt;
+pub mod admission_round;
+pub mod notification;
+pub mod portfolio_version;
+pub mod review;
\ No newline at end of file
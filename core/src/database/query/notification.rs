@@ -0,0 +1,27 @@
+use crate::Query;
+
+use ::entity::{notification, notification::Entity as Notification};
+use sea_orm::*;
+
+impl Query {
+    /// Oldest first, so that notifications are sent in the order they were created
+    pub async fn list_notifications(
+        db: &DbConn,
+        status: Option<&str>,
+        candidate_id: Option<i32>,
+    ) -> Result<Vec<notification::Model>, DbErr> {
+        let mut condition = Condition::all();
+        if let Some(status) = status {
+            condition = condition.add(notification::Column::Status.eq(status));
+        }
+        if let Some(candidate_id) = candidate_id {
+            condition = condition.add(notification::Column::CandidateId.eq(candidate_id));
+        }
+
+        Notification::find()
+            .filter(condition)
+            .order_by(notification::Column::Id, Order::Asc)
+            .all(db)
+            .await
+    }
+}
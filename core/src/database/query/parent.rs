@@ -32,7 +32,7 @@ mod tests {
     use entity::{candidate, parent};
     use sea_orm::{ActiveModelTrait, Set};
 
-    use crate::Query;
+    use crate::{Mutation, Query};
     use crate::utils::db::get_memory_sqlite_connection;
 
     #[tokio::test]
@@ -41,8 +41,10 @@ mod tests {
 
         const CANDIDATE_ID: i32 = 103158;
 
+        let round = Mutation::create_admission_round(&db, "2023".to_string()).await.unwrap();
         candidate::ActiveModel {
             id: Set(CANDIDATE_ID),
+            admission_round_id: Set(round.id),
             personal_identification_number: Set("test".to_string()),
             created_at: Set(chrono::offset::Local::now().naive_local()),
             updated_at: Set(chrono::offset::Local::now().naive_local()),
@@ -0,0 +1,38 @@
+use crate::Query;
+
+use ::entity::{portfolio_version, portfolio_version::Entity as PortfolioVersion};
+use sea_orm::*;
+
+impl Query {
+    /// Oldest version first
+    pub async fn list_portfolio_versions<C: ConnectionTrait>(
+        db: &C,
+        candidate_id: i32,
+    ) -> Result<Vec<portfolio_version::Model>, DbErr> {
+        PortfolioVersion::find()
+            .filter(portfolio_version::Column::CandidateId.eq(candidate_id))
+            .order_by(portfolio_version::Column::Version, Order::Asc)
+            .all(db)
+            .await
+    }
+
+    pub async fn find_portfolio_version(
+        db: &DbConn,
+        candidate_id: i32,
+        version: i32,
+    ) -> Result<Option<portfolio_version::Model>, DbErr> {
+        PortfolioVersion::find()
+            .filter(portfolio_version::Column::CandidateId.eq(candidate_id))
+            .filter(portfolio_version::Column::Version.eq(version))
+            .one(db)
+            .await
+    }
+
+    pub async fn list_all_portfolio_versions(db: &DbConn) -> Result<Vec<portfolio_version::Model>, DbErr> {
+        PortfolioVersion::find()
+            .order_by(portfolio_version::Column::CandidateId, Order::Asc)
+            .order_by(portfolio_version::Column::Version, Order::Asc)
+            .all(db)
+            .await
+    }
+}
@@ -0,0 +1,50 @@
+use crate::Query;
+
+use ::entity::{review, review::Entity as Review};
+use sea_orm::*;
+
+impl Query {
+    pub async fn find_review(
+        db: &DbConn,
+        application_id: i32,
+        admin_id: i32,
+    ) -> Result<Option<review::Model>, DbErr> {
+        Review::find()
+            .filter(review::Column::ApplicationId.eq(application_id))
+            .filter(review::Column::AdminId.eq(admin_id))
+            .one(db)
+            .await
+    }
+
+    pub async fn list_reviews_by_application_id<C: ConnectionTrait>(
+        db: &C,
+        application_id: i32,
+    ) -> Result<Vec<review::Model>, DbErr> {
+        Review::find()
+            .filter(review::Column::ApplicationId.eq(application_id))
+            .order_by(review::Column::AdminId, Order::Asc)
+            .all(db)
+            .await
+    }
+
+    /// Oldest assignment first
+    pub async fn list_reviews_by_admin_id(
+        db: &DbConn,
+        admin_id: i32,
+    ) -> Result<Vec<review::Model>, DbErr> {
+        Review::find()
+            .filter(review::Column::AdminId.eq(admin_id))
+            .order_by(review::Column::AssignedAt, Order::Asc)
+            .order_by(review::Column::ApplicationId, Order::Asc)
+            .all(db)
+            .await
+    }
+
+    pub async fn list_all_reviews(db: &DbConn) -> Result<Vec<review::Model>, DbErr> {
+        Review::find()
+            .order_by(review::Column::ApplicationId, Order::Asc)
+            .order_by(review::Column::AdminId, Order::Asc)
+            .all(db)
+            .await
+    }
+}
@@ -23,6 +23,30 @@ pub enum ServiceError {
     UserAlreadyExists,
     #[error("Candidate not found")]
     CandidateNotFound,
+    #[error("Admin not found")]
+    AdminNotFound,
+    #[error("Last admin can't be removed")]
+    LastAdmin,
+    #[error("Invalid admin role")]
+    InvalidAdminRole,
+    #[error("Invalid audit log filter")]
+    InvalidAuditFilter,
+    #[error("Invalid login scope")]
+    InvalidLoginScope,
+    #[error("Invalid notification status")]
+    InvalidNotificationStatus,
+    #[error("Invalid search query")]
+    InvalidSearchQuery,
+    #[error("Password must be 12 to 128 characters long and contain letters and numbers")]
+    WeakPassword,
+    #[error("Admission round not found")]
+    AdmissionRoundNotFound,
+    #[error("Admission round is still open")]
+    AdmissionRoundOpen,
+    #[error("Admission round can't be purged yet")]
+    AdmissionRoundNotPurgeable,
+    #[error("Action is not allowed in the current admission phase")]
+    PhaseClosed,
     #[error("Resource is locked")]
     Locked,
     #[error("Too many applications")]
@@ -73,6 +97,28 @@ pub enum ServiceError {
     IncompletePortfolio,
     #[error("Portfolio write error")]
     PortfolioWriteError,
+    #[error("Portfolio version not found")]
+    PortfolioVersionNotFound,
+    #[error("Document not found")]
+    DocumentNotFound,
+    #[error("Upload not found")]
+    UploadNotFound,
+    #[error("Review not found")]
+    ReviewNotFound,
+    #[error("Invalid review: {0}")]
+    InvalidReview(String),
+    #[error("Upload offset doesn't match the received data")]
+    UploadOffsetMismatch,
+    #[error("Upload is larger than allowed")]
+    UploadTooLarge,
+    #[error("Upload is not complete")]
+    IncompleteUpload,
+    #[error("Upload checksum doesn't match the received data")]
+    UploadChecksumMismatch,
+    #[error("File is infected with {0}")]
+    MalwareDetected(String),
+    #[error("Invalid file content: {}", crate::utils::content::format_issues(.0))]
+    InvalidContent(Vec<crate::utils::content::ContentIssue>),
     #[error("Zip error")]
     ZipError(#[from] async_zip::error::ZipError),
     #[error("Csv error")]
@@ -83,6 +129,26 @@ pub enum ServiceError {
     FormatError,
     #[error("Invalid field of study")]
     InvalidFieldOfStudy,
+    #[error("S3 error")]
+    S3Error(#[from] s3::error::S3Error),
+    #[error("Invalid storage configuration: {0}")]
+    StorageConfigError(String),
+    #[error("Invalid admission cycle configuration: {0}")]
+    AdmissionCycleConfigError(String),
+    #[error("Invalid blind index configuration: {0}")]
+    BlindIndexConfigError(String),
+    #[error("Invalid receipt configuration: {0}")]
+    ReceiptConfigError(String),
+    #[error("Invalid scanner configuration: {0}")]
+    ScannerConfigError(String),
+    #[error("Malware scan failed: {0}")]
+    ScannerError(String),
+    #[error("Invalid mail configuration: {0}")]
+    MailConfigError(String),
+    #[error("Invalid notification templates: {0}")]
+    NotificationTemplateError(String),
+    #[error("Mail error: {0}")]
+    MailError(String),
 }
 
 impl ServiceError {
@@ -93,13 +159,36 @@ impl ServiceError {
             ServiceError::ParentOverflow => 400,
             ServiceError::MissingDetails => 400,
             ServiceError::ValidationError(_) => 400,
+            ServiceError::InvalidAdminRole => 400,
+            ServiceError::InvalidAuditFilter => 400,
+            ServiceError::InvalidLoginScope => 400,
+            ServiceError::InvalidNotificationStatus => 400,
+            ServiceError::InvalidSearchQuery => 400,
+            ServiceError::WeakPassword => 400,
+            ServiceError::IncompleteUpload => 400,
+            ServiceError::UploadChecksumMismatch => 400,
+            ServiceError::InvalidReview(_) => 400,
             ServiceError::Unauthorized => 401,
             ServiceError::InvalidCredentials => 401,
             ServiceError::ExpiredSession => 401,
             ServiceError::Forbidden => 403,
+            ServiceError::PhaseClosed => 403,
             ServiceError::CandidateNotFound => 404,
+            ServiceError::AdminNotFound => 404,
+            ServiceError::AdmissionRoundNotFound => 404,
+            ServiceError::PortfolioVersionNotFound => 404,
+            ServiceError::DocumentNotFound => 404,
+            ServiceError::UploadNotFound => 404,
+            ServiceError::ReviewNotFound => 404,
             ServiceError::IncompletePortfolio => 406,
             ServiceError::UserAlreadyExists => 409,
+            ServiceError::LastAdmin => 409,
+            ServiceError::AdmissionRoundOpen => 409,
+            ServiceError::AdmissionRoundNotPurgeable => 409,
+            ServiceError::UploadOffsetMismatch => 409,
+            ServiceError::UploadTooLarge => 413,
+            ServiceError::InvalidContent(_) => 422,
+            ServiceError::MalwareDetected(_) => 422,
             ServiceError::Locked => 423,
             ServiceError::TooManyFieldsForOnePerson => 409,
             ServiceError::TooManyApplications => 409,
@@ -129,6 +218,16 @@ impl ServiceError {
             ServiceError::CsvIntoInnerError => 500,
             ServiceError::FormatError => 500,
             ServiceError::InvalidFieldOfStudy => 500,
+            ServiceError::S3Error(_) => 500,
+            ServiceError::StorageConfigError(_) => 500,
+            ServiceError::AdmissionCycleConfigError(_) => 500,
+            ServiceError::BlindIndexConfigError(_) => 500,
+            ServiceError::ReceiptConfigError(_) => 500,
+            ServiceError::ScannerConfigError(_) => 500,
+            ServiceError::ScannerError(_) => 500,
+            ServiceError::MailConfigError(_) => 500,
+            ServiceError::NotificationTemplateError(_) => 500,
+            ServiceError::MailError(_) => 500,
         }
     }
 
@@ -147,6 +246,7 @@ impl ServiceError {
             ServiceError::ArgonConfigError(e) => Some(e.to_string()),
             ServiceError::ZipError(e) => Some(e.to_string()),
             ServiceError::CsvError(e) => Some(e.to_string()),
+            ServiceError::S3Error(e) => Some(e.to_string()),
             _ => None,
         }
     }
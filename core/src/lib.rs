@@ -1,7 +1,7 @@
 pub use sea_orm;
 
-pub use database::mutation::*;
-pub use database::query::*;
+pub use database::mutation::Mutation;
+pub use database::query::Query;
 
 pub mod database;
 pub mod crypto;
@@ -9,3 +9,6 @@ pub mod services;
 pub mod error;
 pub mod utils;
 pub mod models;
+pub mod storage;
+pub mod mail;
+pub mod scanner;
\ No newline at end of file
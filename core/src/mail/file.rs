@@ -0,0 +1,52 @@
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use sea_orm::prelude::Uuid;
+
+use crate::error::ServiceError;
+
+use super::{Email, MailTransport};
+
+/// Writes every email as a JSON file into a mailbox directory, used for development and tests
+pub struct FileTransport {
+    dir: PathBuf,
+}
+
+impl FileTransport {
+    pub fn new<P: AsRef<Path>>(dir: P) -> Self {
+        Self { dir: dir.as_ref().to_path_buf() }
+    }
+
+    /// Mailbox directory is taken from `PORTFOLIO_MAIL_DIR`
+    pub fn from_env() -> Self {
+        dotenv::dotenv().ok();
+        Self::new(std::env::var("PORTFOLIO_MAIL_DIR").unwrap_or_else(|_| "mail".to_string()))
+    }
+
+    /// All emails in the mailbox, in no particular order
+    pub async fn read_all(&self) -> Result<Vec<Email>, ServiceError> {
+        let mut emails = vec![];
+        let mut entries = match tokio::fs::read_dir(&self.dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(emails),
+            Err(e) => return Err(e.into()),
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            let json = tokio::fs::read(entry.path()).await?;
+            emails.push(serde_json::from_slice(&json).map_err(|_| ServiceError::FormatError)?);
+        }
+
+        Ok(emails)
+    }
+}
+
+#[async_trait]
+impl MailTransport for FileTransport {
+    async fn send(&self, email: &Email) -> Result<(), ServiceError> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let json = serde_json::to_vec_pretty(email).map_err(|_| ServiceError::FormatError)?;
+        tokio::fs::write(self.dir.join(format!("{}.json", Uuid::new_v4())), json).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,38 @@
+use async_trait::async_trait;
+use serde::{Serialize, Deserialize};
+
+use crate::error::ServiceError;
+
+pub mod file;
+pub mod smtp;
+
+pub use self::file::FileTransport;
+pub use self::smtp::SmtpTransport;
+
+/// Plain text email
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Email {
+    pub to: Vec<String>,
+    pub subject: String,
+    pub body: String,
+}
+
+/// Delivery of notification emails
+#[async_trait]
+pub trait MailTransport: Send + Sync {
+    async fn send(&self, email: &Email) -> Result<(), ServiceError>;
+}
+
+/// Returns transport selected by `PORTFOLIO_MAIL_TRANSPORT` (`smtp` or `file`).
+/// Notifications are disabled when it is not set
+pub fn from_env() -> Result<Option<Box<dyn MailTransport>>, ServiceError> {
+    dotenv::dotenv().ok();
+    match std::env::var("PORTFOLIO_MAIL_TRANSPORT").as_deref() {
+        Err(_) => Ok(None),
+        Ok("smtp") => Ok(Some(Box::new(SmtpTransport::from_env()?))),
+        Ok("file") => Ok(Some(Box::new(FileTransport::from_env()))),
+        Ok(transport) => Err(ServiceError::MailConfigError(
+            format!("Unknown mail transport {}", transport)
+        )),
+    }
+}
@@ -0,0 +1,81 @@
+use async_trait::async_trait;
+use lettre::{
+    message::{header::ContentType, Mailbox},
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+
+use crate::error::ServiceError;
+
+use super::{Email, MailTransport};
+
+/// Sends emails through an SMTP relay
+pub struct SmtpTransport {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpTransport {
+    pub fn new(transport: AsyncSmtpTransport<Tokio1Executor>, from: Mailbox) -> Self {
+        Self { transport, from }
+    }
+
+    /// Relay is configured by `PORTFOLIO_SMTP_HOST`, `PORTFOLIO_SMTP_PORT`, `PORTFOLIO_SMTP_TLS`
+    /// (`starttls`, `tls` or `none`), `PORTFOLIO_SMTP_USERNAME`, `PORTFOLIO_SMTP_PASSWORD`
+    /// and the sender address `PORTFOLIO_MAIL_FROM`
+    pub fn from_env() -> Result<Self, ServiceError> {
+        dotenv::dotenv().ok();
+        let var = |name: &str| std::env::var(name)
+            .map_err(|_| ServiceError::MailConfigError(format!("{} is not set", name)));
+        let config_error = |e: &dyn std::fmt::Display| ServiceError::MailConfigError(e.to_string());
+
+        let host = var("PORTFOLIO_SMTP_HOST")?;
+        let mut builder = match std::env::var("PORTFOLIO_SMTP_TLS").as_deref() {
+            Ok("starttls") | Err(_) => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&host)
+                .map_err(|e| config_error(&e))?,
+            Ok("tls") => AsyncSmtpTransport::<Tokio1Executor>::relay(&host)
+                .map_err(|e| config_error(&e))?,
+            Ok("none") => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&host),
+            Ok(tls) => return Err(ServiceError::MailConfigError(format!("Unknown SMTP TLS mode {}", tls))),
+        };
+        if let Ok(port) = std::env::var("PORTFOLIO_SMTP_PORT") {
+            builder = builder.port(port.parse().map_err(|e| config_error(&e))?);
+        }
+        if let (Ok(username), Ok(password)) = (
+            std::env::var("PORTFOLIO_SMTP_USERNAME"),
+            std::env::var("PORTFOLIO_SMTP_PASSWORD"),
+        ) {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+
+        let from = var("PORTFOLIO_MAIL_FROM")?
+            .parse()
+            .map_err(|e| config_error(&e))?;
+
+        Ok(Self::new(builder.build(), from))
+    }
+}
+
+#[async_trait]
+impl MailTransport for SmtpTransport {
+    async fn send(&self, email: &Email) -> Result<(), ServiceError> {
+        let mail_error = |e: &dyn std::fmt::Display| ServiceError::MailError(e.to_string());
+
+        let mut builder = Message::builder()
+            .from(self.from.clone())
+            .subject(&email.subject)
+            .header(ContentType::TEXT_PLAIN);
+        for to in email.to.iter() {
+            builder = builder.to(to.parse().map_err(|e| mail_error(&e))?);
+        }
+        let message = builder
+            .body(email.body.to_owned())
+            .map_err(|e| mail_error(&e))?;
+
+        self.transport.send(message)
+            .await
+            .map_err(|e| mail_error(&e))?;
+
+        Ok(())
+    }
+}
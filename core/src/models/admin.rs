@@ -0,0 +1,96 @@
+use chrono::NaiveDateTime;
+use entity::admin;
+use serde::{Serialize, Deserialize};
+
+use crate::error::ServiceError;
+
+/// Admin roles ordered by permissions, every role has all permissions of the roles before it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AdminRole {
+    /// Can read candidate details and download portfolios
+    Reviewer,
+    /// Can also create and delete candidates and reset their passwords
+    Registrar,
+    /// Can also add and remove admins
+    Superadmin,
+}
+
+impl AdminRole {
+    pub fn has_permission(&self, required: AdminRole) -> bool {
+        *self >= required
+    }
+}
+
+impl From<AdminRole> for String {
+    fn from(role: AdminRole) -> Self {
+        match role {
+            AdminRole::Reviewer => "reviewer".to_string(),
+            AdminRole::Registrar => "registrar".to_string(),
+            AdminRole::Superadmin => "superadmin".to_string(),
+        }
+    }
+}
+
+impl TryFrom<&str> for AdminRole {
+    type Error = ServiceError;
+    fn try_from(s: &str) -> Result<Self, ServiceError> {
+        match s {
+            "reviewer" => Ok(AdminRole::Reviewer),
+            "registrar" => Ok(AdminRole::Registrar),
+            "superadmin" => Ok(AdminRole::Superadmin),
+            _ => Err(ServiceError::InvalidAdminRole),
+        }
+    }
+}
+
+impl TryFrom<&admin::Model> for AdminRole {
+    type Error = ServiceError;
+    fn try_from(admin: &admin::Model) -> Result<Self, ServiceError> {
+        AdminRole::try_from(admin.role.as_str())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminResponse {
+    pub id: i32,
+    pub name: String,
+    pub role: String,
+    pub public_key: String,
+    pub created_at: NaiveDateTime,
+}
+
+impl From<admin::Model> for AdminResponse {
+    fn from(admin: admin::Model) -> Self {
+        Self {
+            id: admin.id,
+            name: admin.name,
+            role: admin.role,
+            public_key: admin.public_key,
+            created_at: admin.created_at,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AdminRole;
+
+    #[test]
+    fn test_admin_role_permissions() {
+        assert!(AdminRole::Superadmin.has_permission(AdminRole::Registrar));
+        assert!(AdminRole::Registrar.has_permission(AdminRole::Registrar));
+        assert!(!AdminRole::Reviewer.has_permission(AdminRole::Registrar));
+        assert!(!AdminRole::Registrar.has_permission(AdminRole::Superadmin));
+    }
+
+    #[test]
+    fn test_admin_role_from_str() {
+        for role in [AdminRole::Reviewer, AdminRole::Registrar, AdminRole::Superadmin] {
+            let s: String = role.into();
+            assert_eq!(AdminRole::try_from(s.as_str()).unwrap(), role);
+        }
+        assert!(AdminRole::try_from("teacher").is_err());
+    }
+}
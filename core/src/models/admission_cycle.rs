@@ -0,0 +1,384 @@
+use std::{collections::{HashMap, HashSet}, sync::OnceLock};
+
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::error::ServiceError;
+
+use super::{
+    document::{default_documents, DocumentType},
+    review::{default_rubric, Rubric},
+    timeline::{AdmissionPhase, Timeline},
+};
+
+/// Keys stored next to the cache: portfolio submitted before versioning and pointer to the current version
+const RESERVED_FILE_NAMES: [&str; 2] = ["PORTFOLIO.age", "CURRENT_VERSION"];
+
+/// Used when `PORTFOLIO_ADMISSION_CONFIG` is not set
+const DEFAULT_ADMISSION_CYCLE: &str = include_str!("../../admission_cycle.json");
+
+static ADMISSION_CYCLE: OnceLock<AdmissionCycle> = OnceLock::new();
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Program {
+    /// Short code stored with applications, e.g. `IT`
+    pub code: String,
+    /// First three digits of application ids for this program
+    pub application_prefix: String,
+    /// Official program code, e.g. `1820M01`
+    pub official_code: String,
+    pub name: String,
+    /// Attachments of the portfolio, the original cover letter, portfolio letter and zip when not set
+    #[serde(default = "default_documents")]
+    pub documents: Vec<DocumentType>,
+    /// How reviewers score portfolios of the program, a single overall score when not set
+    #[serde(default = "default_rubric")]
+    pub rubric: Rubric,
+}
+
+impl Program {
+    /// Program as the candidates pick it in the school form, e.g. `1820M01-Informační technologie`
+    pub fn official_name(&self) -> String {
+        format!("{}-{}", self.official_code, self.name)
+    }
+}
+
+/// School, programs and exam dates of one admission cycle
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct AdmissionCycle {
+    pub school: String,
+    pub school_short_name: String,
+    /// Candidate portal address printed in credential letters
+    #[serde(default)]
+    pub login_url: Option<String>,
+    /// Entrance exam days, candidates with two applications attend the first two
+    pub exam_dates: Vec<NaiveDate>,
+    pub programs: Vec<Program>,
+    /// Without a timeline candidates can change their applications at any time
+    #[serde(default)]
+    pub timeline: Option<Timeline>,
+}
+
+impl AdmissionCycle {
+    pub fn from_json(json: &str) -> Result<Self, ServiceError> {
+        let cycle: Self = serde_json::from_str(json)
+            .map_err(|e| ServiceError::AdmissionCycleConfigError(e.to_string()))?;
+        cycle.validate()?;
+        Ok(cycle)
+    }
+
+    /// Reads the file in `PORTFOLIO_ADMISSION_CONFIG`, falls back to the bundled configuration
+    pub fn from_env() -> Result<Self, ServiceError> {
+        dotenv::dotenv().ok();
+        match std::env::var("PORTFOLIO_ADMISSION_CONFIG") {
+            Ok(path) => {
+                let json = std::fs::read_to_string(&path)
+                    .map_err(|e| ServiceError::AdmissionCycleConfigError(format!("{}: {}", path, e)))?;
+                Self::from_json(&json)
+            },
+            Err(_) => Self::from_json(DEFAULT_ADMISSION_CYCLE),
+        }
+    }
+
+    /// Loads the configuration, should be called at startup so that an invalid configuration is caught early
+    pub fn init() -> Result<&'static Self, ServiceError> {
+        if let Some(cycle) = ADMISSION_CYCLE.get() {
+            return Ok(cycle);
+        }
+        let cycle = Self::from_env()?;
+        Ok(ADMISSION_CYCLE.get_or_init(|| cycle))
+    }
+
+    pub fn get() -> &'static Self {
+        Self::init().expect("Invalid admission cycle configuration")
+    }
+
+    fn validate(&self) -> Result<(), ServiceError> {
+        let error = |msg: &str| Err(ServiceError::AdmissionCycleConfigError(msg.to_string()));
+
+        if self.programs.is_empty() {
+            return error("no programs");
+        }
+        if self.timeline.as_ref().is_some_and(|t| !t.is_ordered()) {
+            return error("timeline phases are not in order");
+        }
+        let mut prefixes = HashSet::new();
+        let mut codes = HashSet::new();
+        let mut documents: HashMap<&str, &DocumentType> = HashMap::new();
+        let mut file_names: HashMap<&str, &str> = HashMap::new();
+        for program in self.programs.iter() {
+            if program.application_prefix.len() != 3 || !program.application_prefix.chars().all(|c| c.is_ascii_digit()) {
+                return error("application prefix has to be three digits");
+            }
+            if !prefixes.insert(&program.application_prefix) || !codes.insert(&program.code) {
+                return error("duplicate program code or application prefix");
+            }
+            Self::validate_documents(&program.documents)?;
+            Self::validate_rubric(&program.rubric)?;
+            // Candidates applying to two programs upload one file for the document both of them require
+            for document in program.documents.iter() {
+                if let Some(other) = documents.insert(&document.slug, document) {
+                    let other = DocumentType { required: document.required, ..other.clone() };
+                    if other != *document {
+                        return error("document with the same slug is defined differently in programs");
+                    }
+                }
+                if file_names.insert(&document.file_name, &document.slug).is_some_and(|slug| slug != document.slug) {
+                    return error("documents with different slugs share a file name");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_documents(documents: &[DocumentType]) -> Result<(), ServiceError> {
+        let error = |msg: &str| Err(ServiceError::AdmissionCycleConfigError(msg.to_string()));
+
+        if documents.is_empty() {
+            return error("program has no documents");
+        }
+        let mut slugs = HashSet::new();
+        let mut file_names = HashSet::new();
+        for document in documents.iter() {
+            if !DocumentType::is_valid_slug(&document.slug) {
+                return error("document slug may contain only lowercase letters, digits and underscores");
+            }
+            // File name becomes a part of the store key, it must not point anywhere else
+            let file_name = document.file_name.as_str();
+            if file_name.is_empty()
+                || file_name == "."
+                || file_name == ".."
+                || file_name.contains(['/', '\\'])
+                || RESERVED_FILE_NAMES.contains(&file_name)
+            {
+                return error("invalid document file name");
+            }
+            if document.mime_types.is_empty() || document.max_size == 0 {
+                return error("document needs allowed types and maximum size");
+            }
+            if !slugs.insert(&document.slug) || !file_names.insert(&document.file_name) {
+                return error("duplicate document slug or file name");
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_rubric(rubric: &Rubric) -> Result<(), ServiceError> {
+        let error = |msg: &str| Err(ServiceError::AdmissionCycleConfigError(msg.to_string()));
+
+        if rubric.criteria.is_empty() || rubric.required_reviews == 0 {
+            return error("rubric needs criteria and at least one review");
+        }
+        let mut keys = HashSet::new();
+        for criterion in rubric.criteria.iter() {
+            if !Rubric::is_valid_key(&criterion.key) {
+                return error("criterion key may contain only lowercase letters, digits and underscores");
+            }
+            if criterion.max_score == 0 || criterion.weight == 0 {
+                return error("criterion needs positive maximum score and weight");
+            }
+            if !keys.insert(&criterion.key) {
+                return error("duplicate criterion key");
+            }
+        }
+        Ok(())
+    }
+
+    /// Documents of all programs the candidate applied to, required when any of the programs requires them.
+    /// Applications outside of the configured programs fall back to the default documents
+    pub fn documents_for(&self, application_ids: &[i32]) -> Vec<DocumentType> {
+        let mut documents: Vec<DocumentType> = vec![];
+        let programs: Vec<&Program> = application_ids.iter()
+            .filter_map(|id| self.program_by_application_id(*id))
+            .collect();
+        if programs.is_empty() {
+            return default_documents();
+        }
+
+        for document in programs.iter().flat_map(|p| p.documents.iter()) {
+            match documents.iter_mut().find(|d| d.slug == document.slug) {
+                Some(existing) => existing.required |= document.required,
+                None => documents.push(document.to_owned()),
+            }
+        }
+        documents
+    }
+
+    /// Largest document of any program, uploads are read up to this size before the document is known
+    pub fn max_document_size(&self) -> u64 {
+        self.programs.iter()
+            .flat_map(|p| p.documents.iter())
+            .map(|d| d.max_size)
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Rubric of the program the application belongs to, applications outside of the configured programs use the default one
+    pub fn rubric_for(&self, application_id: i32) -> Rubric {
+        self.program_by_application_id(application_id)
+            .map(|p| p.rubric.to_owned())
+            .unwrap_or_else(default_rubric)
+    }
+
+    pub fn program_by_application_id(&self, application_id: i32) -> Option<&Program> {
+        let id = application_id.to_string();
+        if id.len() <= 3 {
+            return None;
+        }
+        self.programs.iter().find(|p| id.starts_with(&p.application_prefix))
+    }
+
+    pub fn program_by_code(&self, code: &str) -> Option<&Program> {
+        self.programs.iter().find(|p| p.code == code)
+    }
+
+    pub fn program_by_official_name(&self, official_name: &str) -> Option<&Program> {
+        self.programs.iter().find(|p| p.official_name() == official_name)
+    }
+
+    /// Position of the program in the configuration, used to order program combinations
+    pub fn program_index(&self, code: &str) -> Option<usize> {
+        self.programs.iter().position(|p| p.code == code)
+    }
+
+    pub fn phase_at(&self, time: NaiveDateTime) -> AdmissionPhase {
+        self.timeline
+            .as_ref()
+            .map(|t| t.phase_at(time))
+            .unwrap_or(AdmissionPhase::RegistrationOpen)
+    }
+
+    /// Exam date formatted for exports, e.g. `13. 4.`
+    pub fn exam_day(&self, index: usize) -> String {
+        self.exam_dates
+            .get(index)
+            .map(|d| d.format("%-d. %-m.").to_string())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AdmissionCycle, DEFAULT_ADMISSION_CYCLE};
+
+    #[test]
+    fn test_default_admission_cycle() {
+        let cycle = AdmissionCycle::from_json(DEFAULT_ADMISSION_CYCLE).unwrap();
+
+        assert_eq!(cycle.program_by_application_id(101_101).unwrap().code, "G");
+        assert_eq!(cycle.program_by_application_id(103_151).unwrap().code, "KB");
+        assert!(cycle.program_by_application_id(104_101).is_none());
+        assert!(cycle.program_by_application_id(101).is_none());
+
+        let it = cycle.program_by_official_name("1820M01-Informační technologie").unwrap();
+        assert_eq!(it.code, "IT");
+        assert_eq!(cycle.program_index("KB"), Some(2));
+        assert_eq!(cycle.exam_day(0), "13. 4.");
+        assert_eq!(cycle.exam_day(5), "");
+    }
+
+    #[test]
+    fn test_fields_combination() {
+        use crate::models::candidate::{FieldOfStudy, FieldsCombination};
+
+        let g = FieldOfStudy::try_from(101_101).ok();
+        let kb = FieldOfStudy::try_from("1820M01-Informační technologie - Kybernetická bezpečnost".to_string()).ok();
+
+        assert_eq!(FieldsCombination::from_fields(&kb, &g), FieldsCombination::from_fields(&g, &kb));
+        assert_eq!(serde_json::to_string(&FieldsCombination::from_fields(&kb, &g)).unwrap(), "\"G a KB\"");
+        assert_eq!(serde_json::to_string(&FieldsCombination::from_fields(&g, &g)).unwrap(), "\"G\"");
+        assert_eq!(serde_json::to_string(&FieldsCombination::from_fields(&None, &None)).unwrap(), "\"Žádný obor na SSPŠ\"");
+        assert!(FieldOfStudy::try_from(104_101).is_err());
+    }
+
+    #[test]
+    fn test_invalid_admission_cycle() {
+        let json = r#"{
+            "school": "Škola",
+            "schoolShortName": "Š",
+            "examDates": [],
+            "programs": [
+                {"code": "A", "applicationPrefix": "201", "officialCode": "1", "name": "A"},
+                {"code": "B", "applicationPrefix": "201", "officialCode": "2", "name": "B"}
+            ]
+        }"#;
+        assert!(AdmissionCycle::from_json(json).is_err());
+        assert!(AdmissionCycle::from_json(&json.replace("\"201\"", "\"2a1\"")).is_err());
+        assert!(AdmissionCycle::from_json("{}").is_err());
+
+        let cycle = AdmissionCycle::from_json(&json.replacen("\"201\"", "\"202\"", 1)).unwrap();
+        assert_eq!(cycle.program_by_application_id(201_001).unwrap().code, "B");
+    }
+
+    #[test]
+    fn test_program_documents() {
+        let json = r#"{
+            "school": "Škola",
+            "schoolShortName": "Š",
+            "examDates": [],
+            "programs": [
+                {"code": "A", "applicationPrefix": "201", "officialCode": "1", "name": "A", "documents": [
+                    {"slug": "certificate", "name": "Vysvědčení", "fileName": "VYSVEDCENI.pdf", "mimeTypes": ["application/pdf"], "maxSize": 1000, "required": false},
+                    {"slug": "photo", "name": "Fotografie", "fileName": "FOTO.jpg", "mimeTypes": ["image/jpeg", "image/png"], "maxSize": 5000}
+                ]},
+                {"code": "B", "applicationPrefix": "202", "officialCode": "2", "name": "B", "documents": [
+                    {"slug": "certificate", "name": "Vysvědčení", "fileName": "VYSVEDCENI.pdf", "mimeTypes": ["application/pdf"], "maxSize": 1000}
+                ]},
+                {"code": "C", "applicationPrefix": "203", "officialCode": "3", "name": "C"}
+            ]
+        }"#;
+        let cycle = AdmissionCycle::from_json(json).unwrap();
+
+        let slugs = |ids: &[i32]| cycle.documents_for(ids)
+            .into_iter()
+            .map(|d| (d.slug, d.required))
+            .collect::<Vec<_>>();
+        assert_eq!(slugs(&[201_001]), vec![("certificate".to_string(), false), ("photo".to_string(), true)]);
+        assert_eq!(slugs(&[201_001, 202_001]), vec![("certificate".to_string(), true), ("photo".to_string(), true)]);
+        assert_eq!(slugs(&[203_001]).len(), 3);
+        assert_eq!(slugs(&[]).len(), 3);
+        assert_eq!(cycle.max_document_size(), 101_000_000);
+
+        assert!(AdmissionCycle::from_json(&json.replace("\"certificate\"", "\"Certificate\"")).is_err());
+        assert!(AdmissionCycle::from_json(&json.replacen("\"VYSVEDCENI.pdf\"", "\"CERT.pdf\"", 1)).is_err());
+        assert!(AdmissionCycle::from_json(&json.replace("\"FOTO.jpg\"", "\"VYSVEDCENI.pdf\"")).is_err());
+        assert!(AdmissionCycle::from_json(&json.replace("\"maxSize\": 1000}", "\"maxSize\": 2000}")).is_err());
+        for file_name in ["PORTFOLIO.age", "CURRENT_VERSION", ".", "..", "..\\\\FOTO.jpg"] {
+            assert!(AdmissionCycle::from_json(&json.replace("\"FOTO.jpg\"", &format!("\"{}\"", file_name))).is_err());
+        }
+    }
+
+    #[test]
+    fn test_program_rubric() {
+        let json = r#"{
+            "school": "Škola",
+            "schoolShortName": "Š",
+            "examDates": [],
+            "programs": [
+                {"code": "A", "applicationPrefix": "201", "officialCode": "1", "name": "A", "rubric": {
+                    "criteria": [
+                        {"key": "creativity", "name": "Kreativita", "maxScore": 5, "weight": 2},
+                        {"key": "technique", "name": "Technika", "maxScore": 10}
+                    ],
+                    "requiredReviews": 2
+                }},
+                {"code": "B", "applicationPrefix": "202", "officialCode": "2", "name": "B"}
+            ]
+        }"#;
+        let cycle = AdmissionCycle::from_json(json).unwrap();
+
+        let rubric = cycle.rubric_for(201_001);
+        assert_eq!(rubric.required_reviews, 2);
+        assert_eq!(rubric.criteria[1].weight, 1);
+        assert_eq!(rubric.max_total(), 20);
+        assert_eq!(cycle.rubric_for(202_001).criteria[0].key, "overall");
+        assert_eq!(cycle.rubric_for(104_001).required_reviews, 1);
+
+        assert!(AdmissionCycle::from_json(&json.replace("\"technique\"", "\"creativity\"")).is_err());
+        assert!(AdmissionCycle::from_json(&json.replace("\"maxScore\": 5", "\"maxScore\": 0")).is_err());
+        assert!(AdmissionCycle::from_json(&json.replace("\"requiredReviews\": 2", "\"requiredReviews\": 0")).is_err());
+        assert!(AdmissionCycle::from_json(&json.replace("\"technique\"", "\"Technique\"")).is_err());
+    }
+}
@@ -0,0 +1,27 @@
+use chrono::NaiveDateTime;
+use entity::admission_round;
+use serde::{Serialize, Deserialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdmissionRoundResponse {
+    pub id: i32,
+    pub name: String,
+    pub created_at: NaiveDateTime,
+    pub closed_at: Option<NaiveDateTime>,
+    pub archived_at: Option<NaiveDateTime>,
+    pub purged_at: Option<NaiveDateTime>,
+}
+
+impl From<admission_round::Model> for AdmissionRoundResponse {
+    fn from(round: admission_round::Model) -> Self {
+        Self {
+            id: round.id,
+            name: round.name,
+            created_at: round.created_at,
+            closed_at: round.closed_at,
+            archived_at: round.archived_at,
+            purged_at: round.purged_at,
+        }
+    }
+}
@@ -5,7 +5,7 @@ use crate::{database::query::application::ApplicationCandidateJoin, error::Servi
 
 use super::candidate_details::EncryptedString;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ApplicationResponse {
     pub application_id: i32,
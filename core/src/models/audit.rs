@@ -0,0 +1,142 @@
+use chrono::NaiveDateTime;
+use entity::audit_log;
+use serde::{Serialize, Deserialize};
+
+use crate::error::ServiceError;
+
+/// Who performed an audited action
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditActor {
+    Admin(i32),
+    Application(i32),
+}
+
+impl AuditActor {
+    pub fn actor_type(&self) -> &'static str {
+        match self {
+            AuditActor::Admin(_) => "admin",
+            AuditActor::Application(_) => "application",
+        }
+    }
+
+    pub fn id(&self) -> i32 {
+        match self {
+            AuditActor::Admin(id) => *id,
+            AuditActor::Application(id) => *id,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    Login,
+    ViewDetails,
+    DownloadPortfolio,
+    ResetPassword,
+    ChangePassword,
+    Delete,
+    Submit,
+    AssignReviewer,
+    UnassignReviewer,
+    Review,
+}
+
+impl From<AuditAction> for String {
+    fn from(action: AuditAction) -> Self {
+        match action {
+            AuditAction::Login => "login".to_string(),
+            AuditAction::ViewDetails => "view_details".to_string(),
+            AuditAction::DownloadPortfolio => "download_portfolio".to_string(),
+            AuditAction::ResetPassword => "reset_password".to_string(),
+            AuditAction::ChangePassword => "change_password".to_string(),
+            AuditAction::Delete => "delete".to_string(),
+            AuditAction::Submit => "submit".to_string(),
+            AuditAction::AssignReviewer => "assign_reviewer".to_string(),
+            AuditAction::UnassignReviewer => "unassign_reviewer".to_string(),
+            AuditAction::Review => "review".to_string(),
+        }
+    }
+}
+
+impl TryFrom<&str> for AuditAction {
+    type Error = ServiceError;
+    fn try_from(s: &str) -> Result<Self, ServiceError> {
+        match s {
+            "login" => Ok(AuditAction::Login),
+            "view_details" => Ok(AuditAction::ViewDetails),
+            "download_portfolio" => Ok(AuditAction::DownloadPortfolio),
+            "reset_password" => Ok(AuditAction::ResetPassword),
+            "change_password" => Ok(AuditAction::ChangePassword),
+            "delete" => Ok(AuditAction::Delete),
+            "submit" => Ok(AuditAction::Submit),
+            "assign_reviewer" => Ok(AuditAction::AssignReviewer),
+            "unassign_reviewer" => Ok(AuditAction::UnassignReviewer),
+            "review" => Ok(AuditAction::Review),
+            _ => Err(ServiceError::InvalidAuditFilter),
+        }
+    }
+}
+
+/// Actor and IP address of the request an action is performed in
+#[derive(Debug, Clone)]
+pub struct AuditContext {
+    pub actor: AuditActor,
+    pub ip_address: Option<String>,
+}
+
+impl AuditContext {
+    pub fn new(actor: AuditActor, ip_address: Option<String>) -> Self {
+        Self { actor, ip_address }
+    }
+}
+
+/// Audit log filter, all set fields have to match
+#[derive(Debug, Clone, Default)]
+pub struct AuditLogFilter {
+    pub actor_type: Option<String>,
+    pub actor_id: Option<i32>,
+    pub action: Option<String>,
+    pub target_id: Option<i32>,
+    pub from: Option<NaiveDateTime>,
+    pub to: Option<NaiveDateTime>,
+}
+
+impl AuditLogFilter {
+    pub fn validate(&self) -> Result<(), ServiceError> {
+        if let Some(actor_type) = &self.actor_type {
+            if actor_type != "admin" && actor_type != "application" {
+                return Err(ServiceError::InvalidAuditFilter);
+            }
+        }
+        if let Some(action) = &self.action {
+            AuditAction::try_from(action.as_str())?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditLogResponse {
+    pub id: i32,
+    pub actor_type: String,
+    pub actor_id: i32,
+    pub action: String,
+    pub target_id: Option<i32>,
+    pub ip_address: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+impl From<audit_log::Model> for AuditLogResponse {
+    fn from(log: audit_log::Model) -> Self {
+        Self {
+            id: log.id,
+            actor_type: log.actor_type,
+            actor_id: log.actor_id,
+            action: log.action,
+            target_id: log.target_id,
+            ip_address: log.ip_address,
+            created_at: log.created_at,
+        }
+    }
+}
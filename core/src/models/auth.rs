@@ -13,4 +13,36 @@ pub trait AuthenticableTrait {
     async fn logout(db: &DbConn, session: Self::Session) -> Result<(), ServiceError>;
     async fn new_session(db: &DbConn, user: &Self::User, ip_addr: String, password: String) -> Result<String, ServiceError>;
     async fn delete_old_sessions(db: &DbConn, user: &Self::User, keep_n_recent: usize) -> Result<(), ServiceError>;
-}
\ No newline at end of file
+}
+
+pub const MIN_PASSWORD_LENGTH: usize = 12;
+pub const MAX_PASSWORD_LENGTH: usize = 128;
+
+/// Password chosen by the user has to be long enough and contain both letters and numbers
+pub fn validate_password_policy(password: &str) -> Result<(), ServiceError> {
+    let length = password.chars().count();
+    if !(MIN_PASSWORD_LENGTH..=MAX_PASSWORD_LENGTH).contains(&length)
+        || !password.chars().any(|c| c.is_alphabetic())
+        || !password.chars().any(|c| c.is_numeric())
+    {
+        return Err(ServiceError::WeakPassword);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::validate_password_policy;
+
+    #[test]
+    fn test_password_policy() {
+        assert!(validate_password_policy("correcthorse1").is_ok());
+        assert!(validate_password_policy("Příliš žluťoučký 1").is_ok());
+
+        assert!(validate_password_policy("short1").is_err());
+        assert!(validate_password_policy("onlylettershere").is_err());
+        assert!(validate_password_policy("123456789012").is_err());
+        assert!(validate_password_policy(&"a1".repeat(65)).is_err());
+    }
+}
@@ -0,0 +1,64 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as base64;
+
+use crate::{crypto, error::ServiceError};
+
+const MIN_KEY_LENGTH: usize = 32;
+
+/// Key for blind indexes of encrypted candidate columns, read from base64 encoded `PORTFOLIO_BLIND_INDEX_KEY`.
+/// Personal data stay encrypted, the index only allows exact match lookups of a known value
+pub struct BlindIndexKey(Vec<u8>);
+
+impl BlindIndexKey {
+    pub fn new(key: Vec<u8>) -> Result<Self, ServiceError> {
+        if key.len() < MIN_KEY_LENGTH {
+            return Err(ServiceError::BlindIndexConfigError(
+                format!("key must be at least {} bytes long", MIN_KEY_LENGTH)
+            ));
+        }
+
+        Ok(Self(key))
+    }
+
+    /// Returns None when the key is not configured, lookups then fall back to decrypting every candidate
+    pub fn from_env() -> Result<Option<Self>, ServiceError> {
+        dotenv::dotenv().ok();
+        let Ok(key) = std::env::var("PORTFOLIO_BLIND_INDEX_KEY") else {
+            return Ok(None);
+        };
+
+        let key = base64.decode(key.trim())
+            .map_err(|e| ServiceError::BlindIndexConfigError(e.to_string()))?;
+        Self::new(key).map(Some)
+    }
+
+    /// Separators are ignored, "010101/1234" and "0101011234" are the same number.
+    /// Candidates without blind index are compared in the same form
+    pub fn normalize_personal_id_number(personal_id_number: &str) -> String {
+        personal_id_number.chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .collect()
+    }
+
+    pub fn personal_id_number(&self, personal_id_number: &str) -> String {
+        let normalized = Self::normalize_personal_id_number(personal_id_number);
+        crypto::hmac_sha256(&self.0, &format!("personal_id_number:{}", normalized))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BlindIndexKey;
+
+    #[test]
+    fn test_blind_index() {
+        let key = BlindIndexKey::new(vec![1; 32]).unwrap();
+        let other_key = BlindIndexKey::new(vec![2; 32]).unwrap();
+
+        assert_eq!(key.personal_id_number("010101/1234"), key.personal_id_number(" 0101011234"));
+        assert_ne!(key.personal_id_number("0101011234"), key.personal_id_number("0101011235"));
+        assert_ne!(key.personal_id_number("0101011234"), other_key.personal_id_number("0101011234"));
+
+        assert!(BlindIndexKey::new(vec![1; 16]).is_err());
+    }
+}
@@ -1,5 +1,4 @@
 use chrono::NaiveDate;
-use sea_orm::strum::Display;
 use entity::{application, candidate};
 use serde::{Deserialize, Serialize};
 use validator::Validate;
@@ -8,55 +7,50 @@ use crate::{
     error::ServiceError,
 };
 
-use super::{candidate_details::{EncryptedString, EncryptedCandidateDetails}, grade::GradeList, school::School};
+use super::{admission_cycle::AdmissionCycle, candidate_details::{EncryptedString, EncryptedCandidateDetails}, grade::GradeList, school::School};
 
-#[derive(Debug, Clone, Serialize, Display)]
-pub enum FieldOfStudy {
-    G,
-    IT,
-    KB,
+/// Code of a program from the admission cycle configuration, e.g. `IT`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct FieldOfStudy(String);
+
+impl FieldOfStudy {
+    pub fn code(&self) -> &str {
+        &self.0
+    }
 }
 
-impl Into<String> for FieldOfStudy {
-    fn into(self) -> String {
-        match self {
-            FieldOfStudy::G => "G".to_string(),
-            FieldOfStudy::IT => "IT".to_string(),
-            FieldOfStudy::KB => "KB".to_string(),
-        }
+impl std::fmt::Display for FieldOfStudy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
     }
 }
 
-impl From<i32> for FieldOfStudy {
-    fn from(id: i32) -> Self {
-        match &id.to_string().as_str()[0..3] {
-            "101" => FieldOfStudy::G,
-            "102" => FieldOfStudy::IT,
-            "103" => FieldOfStudy::KB,
-            _ => panic!("Invalid field of study id"), // TODO: handle using TryFrom
-        }
+impl From<FieldOfStudy> for String {
+    fn from(field: FieldOfStudy) -> Self {
+        field.0
     }
 }
 
-impl TryFrom<String> for FieldOfStudy {
+/// Field of study by application id prefix
+impl TryFrom<i32> for FieldOfStudy {
     type Error = ServiceError;
-    fn try_from(s: String) -> Result<Self, ServiceError> {
-        match s.as_str() {
-            "7941K41-Gymnázium" => Ok(FieldOfStudy::G),
-            "1820M01-Informační technologie" => Ok(FieldOfStudy::IT), // TODO: constants
-            "1820M01-Informační technologie - Kybernetická bezpečnost" => Ok(FieldOfStudy::KB),
-            _ => Err(ServiceError::InvalidFieldOfStudy),
-        }
+    fn try_from(application_id: i32) -> Result<Self, ServiceError> {
+        AdmissionCycle::get()
+            .program_by_application_id(application_id)
+            .map(|p| FieldOfStudy(p.code.to_owned()))
+            .ok_or(ServiceError::InvalidFieldOfStudy)
     }
 }
 
-impl Into<i32> for FieldOfStudy {
-    fn into(self) -> i32 {
-        match self {
-            FieldOfStudy::G => 101,
-            FieldOfStudy::IT => 102,
-            FieldOfStudy::KB => 103,
-        }
+/// Field of study by official program name as filled in by candidates
+impl TryFrom<String> for FieldOfStudy {
+    type Error = ServiceError;
+    fn try_from(s: String) -> Result<Self, ServiceError> {
+        AdmissionCycle::get()
+            .program_by_official_name(&s)
+            .map(|p| FieldOfStudy(p.code.to_owned()))
+            .ok_or(ServiceError::InvalidFieldOfStudy)
     }
 }
 
@@ -152,7 +146,7 @@ impl NewCandidateResponse {
         private_key: &String,
         c: candidate::Model,
     ) -> Result<Self, ServiceError> {
-        let field_of_study = FieldOfStudy::from(current_application).into();
+        let field_of_study = FieldOfStudy::try_from(current_application)?.into();
         let id_number = EncryptedString::from(c.personal_identification_number.to_owned())
             .decrypt(private_key)
             .await?;
@@ -170,89 +164,84 @@ impl NewCandidateResponse {
     }
 }
 
-#[derive(Debug, Serialize, PartialEq)]
-pub enum FieldsCombination {
-    #[serde(rename = "Žádný obor na SSPŠ")]
-    Unknown,
-    #[serde(rename = "G")]
-    G,
-    #[serde(rename = "IT")]
-    IT,
-    #[serde(rename = "KB")]
-    KB,
-    #[serde(rename = "G a IT")]
-    GIt,
-    #[serde(rename = "G a KB")]
-    GKb,
-    #[serde(rename = "IT a KB")]
-    ItKb,
-}
+/// Distinct fields of study a candidate applied for, ordered as in the admission cycle configuration
+#[derive(Debug, PartialEq)]
+pub struct FieldsCombination(Vec<FieldOfStudy>);
 
 impl FieldsCombination {
     pub fn from_fields(first: &Option<FieldOfStudy>, second: &Option<FieldOfStudy>) -> Self {
-        match (first, second) {
-            (None, None) => FieldsCombination::Unknown,
-            (Some(FieldOfStudy::G), None) => FieldsCombination::G,
-            (Some(FieldOfStudy::IT), None) => FieldsCombination::IT,
-            (Some(FieldOfStudy::KB), None) => FieldsCombination::KB,
-            (None, Some(FieldOfStudy::G)) => FieldsCombination::G,
-            (None, Some(FieldOfStudy::IT)) => FieldsCombination::IT,
-            (None, Some(FieldOfStudy::KB)) => FieldsCombination::KB,
-            // Field combinations
-            (Some(FieldOfStudy::G), Some(FieldOfStudy::IT)) => FieldsCombination::GIt,
-            (Some(FieldOfStudy::G), Some(FieldOfStudy::KB)) => FieldsCombination::GKb,
-            (Some(FieldOfStudy::IT), Some(FieldOfStudy::KB)) => FieldsCombination::ItKb,
-            (Some(FieldOfStudy::IT), Some(FieldOfStudy::G)) => FieldsCombination::GIt,
-            (Some(FieldOfStudy::KB), Some(FieldOfStudy::G)) => FieldsCombination::GKb,
-            (Some(FieldOfStudy::KB), Some(FieldOfStudy::IT)) => FieldsCombination::ItKb,
-            // Some candidates filled in the same field twice
-            (Some(FieldOfStudy::G), Some(FieldOfStudy::G)) => FieldsCombination::G,
-            (Some(FieldOfStudy::IT), Some(FieldOfStudy::IT)) => FieldsCombination::IT,
-            (Some(FieldOfStudy::KB), Some(FieldOfStudy::KB)) => FieldsCombination::KB,
+        let cycle = AdmissionCycle::get();
+        let mut fields: Vec<FieldOfStudy> = first.iter().chain(second.iter()).cloned().collect();
+        // Some candidates filled in the same field twice
+        fields.sort_by_key(|f| cycle.program_index(f.code()));
+        fields.dedup();
+
+        Self(fields)
+    }
+}
+
+impl Serialize for FieldsCombination {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.0.is_empty() {
+            let unknown = format!("Žádný obor na {}", AdmissionCycle::get().school_short_name);
+            return serializer.serialize_str(&unknown);
         }
+
+        let codes: Vec<&str> = self.0.iter().map(|f| f.code()).collect();
+        serializer.serialize_str(&codes.join(" a "))
     }
 }
 
+/// Row of the candidates export, headers are written by `CandidateRow::headers` in field order
 #[derive(Debug, Serialize)]
 pub struct CandidateRow {
-    #[serde(rename = "Číslo uchazeče (přiděleno systémem)")]
     pub id: i32,
-    #[serde(rename = "Ev. č. první přihlášky")]
     pub first_application: i32,
-    #[serde(rename = "Ev. č. druhé přihlášky (pokud podával dvě)")]
     pub second_application: Option<i32>,
-    #[serde(rename = "Rodné číslo")]
     pub personal_id_number: String,
-    #[serde(rename = "Bude dělat JPZ na SSPŠ 13. 4.")]
     pub first_day_admissions: bool,
-    #[serde(rename = "Bude dělat JPZ na SSPŠ 14. 4.")]
     pub second_day_admissions: bool,
-    #[serde(rename = "Obor první přihlášky SSPŠ 13. 4.")]
     pub first_day_field: Option<FieldOfStudy>,
-    #[serde(rename = "Obor druhé přihlášky SSPŠ 14. 4.")]
     pub second_day_field: Option<FieldOfStudy>,
-    #[serde(rename = "Kombinace SSPŠ oborů")]
     pub fields_combination: FieldsCombination,
-    #[serde(rename = "Název první školy (JPZ 13. 4.)")]
     pub first_school: String,
-    #[serde(rename = "Obor první školy")]
     pub first_school_field: String,
-    #[serde(rename = "Název druhé školy (JPZ 14. 4.)")]
     pub second_school: String,
-    #[serde(rename = "Obor druhé školy")]
     pub second_school_field: String,
-    #[serde(rename = "Obory vyplněné uchazečem odpovídají s přihláškami")]
     pub fields_match: bool,
-    #[serde(rename = "Jméno (pokud vyplnil)")]
     pub name: String,
-    #[serde(rename = "Příjmení (pokud vyplnil)")]
     pub surname: String,
-    #[serde(rename = "Email uchazeče (pokud vyplnil)")]
     pub email: String,
-    #[serde(rename = "Telefon uchazeče (pokud vyplnil)")]
     pub telephone: String,
-    #[serde(rename = "Email zákonného zástupce (pokud vyplnil)")]
     pub parent_email: Option<String>,
-    #[serde(rename = "Telefon zákonného zástupce (pokud vyplnil)")]
     pub parent_telephone: Option<String>,
-}
\ No newline at end of file
+}
+
+impl CandidateRow {
+    pub fn headers(cycle: &AdmissionCycle) -> Vec<String> {
+        let school = &cycle.school_short_name;
+        let (first_day, second_day) = (cycle.exam_day(0), cycle.exam_day(1));
+        vec![
+            "Číslo uchazeče (přiděleno systémem)".to_string(),
+            "Ev. č. první přihlášky".to_string(),
+            "Ev. č. druhé přihlášky (pokud podával dvě)".to_string(),
+            "Rodné číslo".to_string(),
+            format!("Bude dělat JPZ na {} {}", school, first_day),
+            format!("Bude dělat JPZ na {} {}", school, second_day),
+            format!("Obor první přihlášky {} {}", school, first_day),
+            format!("Obor druhé přihlášky {} {}", school, second_day),
+            format!("Kombinace {} oborů", school),
+            format!("Název první školy (JPZ {})", first_day),
+            "Obor první školy".to_string(),
+            format!("Název druhé školy (JPZ {})", second_day),
+            "Obor druhé školy".to_string(),
+            "Obory vyplněné uchazečem odpovídají s přihláškami".to_string(),
+            "Jméno (pokud vyplnil)".to_string(),
+            "Příjmení (pokud vyplnil)".to_string(),
+            "Email uchazeče (pokud vyplnil)".to_string(),
+            "Telefon uchazeče (pokud vyplnil)".to_string(),
+            "Email zákonného zástupce (pokud vyplnil)".to_string(),
+            "Telefon zákonného zástupce (pokud vyplnil)".to_string(),
+        ]
+    }
+}
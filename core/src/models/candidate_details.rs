@@ -79,6 +79,35 @@ impl EncryptedString {
         }
     }
 
+    /// Decrypts with the first matching private key and encrypts again to new recipients
+    pub async fn reencrypt(
+        &self,
+        private_keys: &[String],
+        recipients: &Vec<String>,
+    ) -> Result<Self, ServiceError> {
+        let mut result = Err(ServiceError::CryptoDecryptFailed);
+        for private_key in private_keys {
+            result = self.decrypt(private_key).await;
+            if result.is_ok() {
+                break;
+            }
+        }
+
+        Self::new(&result?, recipients).await
+    }
+
+    /// Empty values were never encrypted, so they are kept as they are
+    pub async fn reencrypt_option(
+        s: &Option<EncryptedString>,
+        private_keys: &[String],
+        recipients: &Vec<String>,
+    ) -> Result<Option<Self>, ServiceError> {
+        match s {
+            Some(s) if !s.0.is_empty() => Ok(Some(s.reencrypt(private_keys, recipients).await?)),
+            _ => Ok(s.clone()),
+        }
+    }
+
     pub fn to_string(self) -> String {
         self.0
     }
@@ -215,6 +244,55 @@ impl EncryptedCandidateDetails {
         )
     }
 
+    pub async fn reencrypt(
+        &self,
+        private_keys: &[String],
+        recipients: &Vec<String>,
+    ) -> Result<EncryptedCandidateDetails, ServiceError> {
+        let d = tokio::try_join!(
+            EncryptedString::reencrypt_option(&self.name, private_keys, recipients),
+            EncryptedString::reencrypt_option(&self.surname, private_keys, recipients),
+            EncryptedString::reencrypt_option(&self.birth_surname, private_keys, recipients),
+            EncryptedString::reencrypt_option(&self.birthplace, private_keys, recipients),
+            EncryptedString::reencrypt_option(&self.birthdate, private_keys, recipients),
+            EncryptedString::reencrypt_option(&self.address, private_keys, recipients),
+            EncryptedString::reencrypt_option(&self.letter_address, private_keys, recipients),
+            EncryptedString::reencrypt_option(&self.telephone, private_keys, recipients),
+            EncryptedString::reencrypt_option(&self.citizenship, private_keys, recipients),
+            EncryptedString::reencrypt_option(&self.email, private_keys, recipients),
+            EncryptedString::reencrypt_option(&self.sex, private_keys, recipients),
+            EncryptedString::reencrypt_option(&self.personal_id_number, private_keys, recipients),
+            EncryptedString::reencrypt_option(&self.school_name, private_keys, recipients),
+            EncryptedString::reencrypt_option(&self.health_insurance, private_keys, recipients),
+            EncryptedString::reencrypt_option(&self.grades_json, private_keys, recipients),
+            EncryptedString::reencrypt_option(&self.first_school, private_keys, recipients),
+            EncryptedString::reencrypt_option(&self.second_school, private_keys, recipients),
+        )?;
+
+        Ok(
+            EncryptedCandidateDetails {
+                name: d.0,
+                surname: d.1,
+                birth_surname: d.2,
+                birthplace: d.3,
+                birthdate: d.4,
+                address: d.5,
+                letter_address: d.6,
+                telephone: d.7,
+                citizenship: d.8,
+                email: d.9,
+                sex: d.10,
+                personal_id_number: d.11,
+                school_name: d.12,
+                health_insurance: d.13,
+                grades_json: d.14,
+                first_school: d.15,
+                second_school: d.16,
+                test_language: self.test_language.to_owned(),
+            }
+        )
+    }
+
     pub fn is_filled(&self) -> bool {
         self.name.is_some() &&
         self.surname.is_some() &&
@@ -298,6 +376,28 @@ impl EncryptedParentDetails {
         )
     }
 
+    pub async fn reencrypt(
+        &self,
+        private_keys: &[String],
+        recipients: &Vec<String>,
+    ) -> Result<EncryptedParentDetails, ServiceError> {
+        let d = tokio::try_join!(
+            EncryptedString::reencrypt_option(&self.name, private_keys, recipients),
+            EncryptedString::reencrypt_option(&self.surname, private_keys, recipients),
+            EncryptedString::reencrypt_option(&self.telephone, private_keys, recipients),
+            EncryptedString::reencrypt_option(&self.email, private_keys, recipients),
+        )?;
+
+        Ok(
+            EncryptedParentDetails {
+                name: d.0,
+                surname: d.1,
+                telephone: d.2,
+                email: d.3,
+            }
+        )
+    }
+
     pub fn is_filled(&self) -> bool {
         self.name.is_some() &&
         self.surname.is_some() &&
@@ -0,0 +1,32 @@
+use serde::{Serialize, Deserialize};
+
+use super::{candidate::CreateCandidateResponse, import::ImportRowResult};
+
+/// Generated credentials of one application printed as a letter for the candidate
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialLetter {
+    pub application_id: i32,
+    pub password: String,
+}
+
+impl From<CreateCandidateResponse> for CredentialLetter {
+    fn from(response: CreateCandidateResponse) -> Self {
+        Self {
+            application_id: response.application_id,
+            password: response.password,
+        }
+    }
+}
+
+impl CredentialLetter {
+    /// Letter for an imported row, failed rows and dry run rows have no password
+    pub fn from_import_result(result: &ImportRowResult) -> Option<Self> {
+        Some(
+            Self {
+                application_id: result.application_id?,
+                password: result.password.to_owned()?,
+            }
+        )
+    }
+}
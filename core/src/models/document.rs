@@ -0,0 +1,64 @@
+use serde::{Serialize, Deserialize};
+
+/// Limit of the letters before documents were configurable
+const LETTER_MAX_SIZE: u64 = 11_000_000;
+/// Limit of the portfolio zip before documents were configurable
+const ZIP_MAX_SIZE: u64 = 101_000_000;
+
+fn required_default() -> bool {
+    true
+}
+
+/// Attachment candidates of a program upload before submitting the portfolio
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentType {
+    /// Identifies the document in upload routes, e.g. `cover_letter`
+    pub slug: String,
+    /// Shown to candidates
+    pub name: String,
+    /// Name of the file in cache and in the submitted archive
+    pub file_name: String,
+    /// Types detected from the file content, the request header and file name are not trusted
+    pub mime_types: Vec<String>,
+    /// Bytes
+    pub max_size: u64,
+    #[serde(default = "required_default")]
+    pub required: bool,
+}
+
+impl DocumentType {
+    fn new(slug: &str, name: &str, file_name: &str, mime_type: &str, max_size: u64) -> Self {
+        Self {
+            slug: slug.to_string(),
+            name: name.to_string(),
+            file_name: file_name.to_string(),
+            mime_types: vec![mime_type.to_string()],
+            max_size,
+            required: true,
+        }
+    }
+
+    pub fn is_valid_slug(slug: &str) -> bool {
+        !slug.is_empty() && slug.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+    }
+}
+
+/// Documents of programs which don't configure their own
+pub fn default_documents() -> Vec<DocumentType> {
+    vec![
+        DocumentType::new("cover_letter", "Motivační dopis", "MOTIVACNI_DOPIS.pdf", "application/pdf", LETTER_MAX_SIZE),
+        DocumentType::new("portfolio_letter", "Portfolio", "PORTFOLIO.pdf", "application/pdf", LETTER_MAX_SIZE),
+        DocumentType::new("portfolio_zip", "Další data portfolia", "PORTFOLIO.zip", "application/zip", ZIP_MAX_SIZE),
+    ]
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentStatus {
+    #[serde(flatten)]
+    pub document: DocumentType,
+    pub uploaded: bool,
+    /// Last upload was infected, the document has to be uploaded again
+    pub quarantined: bool,
+}
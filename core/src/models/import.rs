@@ -0,0 +1,49 @@
+use serde::{Serialize, Deserialize};
+
+/// Row of the imported spreadsheet, headers are the same as in the exports
+#[derive(Debug, Deserialize)]
+pub struct ImportRow {
+    #[serde(rename = "Ev. č. přihlášky", alias = "applicationId")]
+    pub application_id: i32,
+    #[serde(rename = "Rodné číslo", alias = "personalIdNumber")]
+    pub personal_id_number: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportStatus {
+    /// New candidate was created
+    Created,
+    /// Application was linked to an existing candidate with the same personal id number
+    Linked,
+    /// Row would be imported, returned only in dry run
+    Valid,
+    Error,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportRowResult {
+    /// Line in the imported file, header is line 1
+    pub line: usize,
+    pub application_id: Option<i32>,
+    pub personal_id_number: Option<String>,
+    pub status: ImportStatus,
+    pub field_of_study: Option<String>,
+    pub password: Option<String>,
+    pub error: Option<String>,
+}
+
+impl ImportRowResult {
+    pub fn error(line: usize, row: Option<&ImportRow>, error: String) -> Self {
+        Self {
+            line,
+            application_id: row.map(|r| r.application_id),
+            personal_id_number: row.map(|r| r.personal_id_number.to_owned()),
+            status: ImportStatus::Error,
+            field_of_study: None,
+            password: None,
+            error: Some(error),
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use crate::error::ServiceError;
+
+/// What failed login attempts are counted against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoginScope {
+    Admin,
+    Application,
+    Ip,
+}
+
+impl LoginScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LoginScope::Admin => "admin",
+            LoginScope::Application => "application",
+            LoginScope::Ip => "ip",
+        }
+    }
+}
+
+impl TryFrom<&str> for LoginScope {
+    type Error = ServiceError;
+    fn try_from(s: &str) -> Result<Self, ServiceError> {
+        match s {
+            "admin" => Ok(LoginScope::Admin),
+            "application" => Ok(LoginScope::Application),
+            "ip" => Ok(LoginScope::Ip),
+            _ => Err(ServiceError::InvalidLoginScope),
+        }
+    }
+}
+
+/// Login throttling thresholds, read from `PORTFOLIO_LOGIN_*` environment variables
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoginThrottleConfig {
+    /// Failed attempts on one account before it gets locked
+    pub max_attempts: i32,
+    /// Failed attempts from one IP address before it gets locked
+    pub ip_max_attempts: i32,
+    /// Length of the first lockout, every following failed attempt doubles it
+    pub lockout_seconds: i64,
+    /// Upper bound of the lockout, failures older than this are forgotten
+    pub max_lockout_seconds: i64,
+}
+
+impl Default for LoginThrottleConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            ip_max_attempts: 20,
+            lockout_seconds: 30,
+            max_lockout_seconds: 3600,
+        }
+    }
+}
+
+impl LoginThrottleConfig {
+    pub fn from_env() -> Self {
+        fn var<T: std::str::FromStr>(name: &str, default: T) -> T {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        }
+
+        dotenv::dotenv().ok();
+        let default = Self::default();
+        Self {
+            max_attempts: var("PORTFOLIO_LOGIN_MAX_ATTEMPTS", default.max_attempts),
+            ip_max_attempts: var("PORTFOLIO_LOGIN_IP_MAX_ATTEMPTS", default.ip_max_attempts),
+            lockout_seconds: var("PORTFOLIO_LOGIN_LOCKOUT_SECONDS", default.lockout_seconds),
+            max_lockout_seconds: var("PORTFOLIO_LOGIN_MAX_LOCKOUT_SECONDS", default.max_lockout_seconds),
+        }
+    }
+
+    pub fn threshold(&self, scope: LoginScope) -> i32 {
+        match scope {
+            LoginScope::Ip => self.ip_max_attempts,
+            _ => self.max_attempts,
+        }
+    }
+
+    /// Lockout length after `failed_count` failed attempts, `None` while under the threshold
+    pub fn lockout_duration(&self, scope: LoginScope, failed_count: i32) -> Option<chrono::Duration> {
+        let over = failed_count - self.threshold(scope);
+        if over < 0 {
+            return None;
+        }
+
+        let seconds = self.lockout_seconds
+            .saturating_mul(1 << over.min(20))
+            .min(self.max_lockout_seconds);
+        Some(chrono::Duration::seconds(seconds))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LoginScope, LoginThrottleConfig};
+
+    #[test]
+    fn test_lockout_duration_backs_off() {
+        let config = LoginThrottleConfig::default();
+
+        assert_eq!(config.lockout_duration(LoginScope::Application, 4), None);
+        assert_eq!(config.lockout_duration(LoginScope::Application, 5).unwrap().num_seconds(), 30);
+        assert_eq!(config.lockout_duration(LoginScope::Application, 6).unwrap().num_seconds(), 60);
+        assert_eq!(config.lockout_duration(LoginScope::Admin, 7).unwrap().num_seconds(), 120);
+        assert_eq!(config.lockout_duration(LoginScope::Admin, 100).unwrap().num_seconds(), 3600);
+
+        assert_eq!(config.lockout_duration(LoginScope::Ip, 19), None);
+        assert_eq!(config.lockout_duration(LoginScope::Ip, 20).unwrap().num_seconds(), 30);
+    }
+}
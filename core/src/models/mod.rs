@@ -3,4 +3,20 @@ pub mod candidate;
 pub mod auth;
 pub mod application;
 pub mod grade;
-pub mod school;
\ No newline at end of file
+pub mod school;
+pub mod admin;
+pub mod audit;
+pub mod login_attempt;
+pub mod admission_cycle;
+pub mod admission_round;
+pub mod search;
+pub mod blind_index;
+pub mod import;
+pub mod credential_letter;
+pub mod notification;
+pub mod timeline;
+pub mod portfolio_version;
+pub mod receipt;
+pub mod upload;
+pub mod document;
+pub mod review;
\ No newline at end of file
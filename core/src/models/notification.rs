@@ -0,0 +1,197 @@
+use std::{collections::HashMap, sync::OnceLock};
+
+use chrono::NaiveDateTime;
+use entity::notification;
+use serde::{Serialize, Deserialize};
+
+use crate::error::ServiceError;
+
+/// Used when `PORTFOLIO_NOTIFICATION_TEMPLATES` is not set
+const DEFAULT_TEMPLATES: &str = include_str!("../../notification_templates.json");
+
+static TEMPLATES: OnceLock<NotificationTemplates> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NotificationKind {
+    PortfolioSubmitted,
+    PasswordReset,
+    DetailsSubmitted,
+}
+
+impl NotificationKind {
+    const ALL: [NotificationKind; 3] = [
+        NotificationKind::PortfolioSubmitted,
+        NotificationKind::PasswordReset,
+        NotificationKind::DetailsSubmitted,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationKind::PortfolioSubmitted => "portfolio_submitted",
+            NotificationKind::PasswordReset => "password_reset",
+            NotificationKind::DetailsSubmitted => "details_submitted",
+        }
+    }
+}
+
+impl TryFrom<&str> for NotificationKind {
+    type Error = ServiceError;
+    fn try_from(s: &str) -> Result<Self, ServiceError> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == s)
+            .ok_or(ServiceError::NotificationTemplateError(format!("unknown notification {}", s)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationStatus {
+    /// Waiting for the first or next delivery attempt
+    Pending,
+    Sent,
+    /// Delivery failed too many times or there was nobody to send it to
+    Failed,
+}
+
+impl NotificationStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationStatus::Pending => "pending",
+            NotificationStatus::Sent => "sent",
+            NotificationStatus::Failed => "failed",
+        }
+    }
+}
+
+impl TryFrom<&str> for NotificationStatus {
+    type Error = ServiceError;
+    fn try_from(s: &str) -> Result<Self, ServiceError> {
+        match s {
+            "pending" => Ok(NotificationStatus::Pending),
+            "sent" => Ok(NotificationStatus::Sent),
+            "failed" => Ok(NotificationStatus::Failed),
+            _ => Err(ServiceError::InvalidNotificationStatus),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Template {
+    pub subject: String,
+    pub body: String,
+}
+
+/// Email templates for every notification kind, keyed by locale.
+/// `{name}`, `{applications}`, `{school}` and `{loginUrl}` are replaced when the email is sent
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationTemplates {
+    pub default_locale: String,
+    pub templates: HashMap<NotificationKind, HashMap<String, Template>>,
+}
+
+impl NotificationTemplates {
+    pub fn from_json(json: &str) -> Result<Self, ServiceError> {
+        let templates: Self = serde_json::from_str(json)
+            .map_err(|e| ServiceError::NotificationTemplateError(e.to_string()))?;
+        for kind in NotificationKind::ALL {
+            if !templates.templates.get(&kind).is_some_and(|t| t.contains_key(&templates.default_locale)) {
+                return Err(ServiceError::NotificationTemplateError(
+                    format!("{} has no template in the default locale", kind.as_str())
+                ));
+            }
+        }
+        Ok(templates)
+    }
+
+    /// Reads the file in `PORTFOLIO_NOTIFICATION_TEMPLATES`, falls back to the bundled templates
+    pub fn from_env() -> Result<Self, ServiceError> {
+        dotenv::dotenv().ok();
+        match std::env::var("PORTFOLIO_NOTIFICATION_TEMPLATES") {
+            Ok(path) => {
+                let json = std::fs::read_to_string(&path)
+                    .map_err(|e| ServiceError::NotificationTemplateError(format!("{}: {}", path, e)))?;
+                Self::from_json(&json)
+            },
+            Err(_) => Self::from_json(DEFAULT_TEMPLATES),
+        }
+    }
+
+    pub fn get() -> Result<&'static Self, ServiceError> {
+        if let Some(templates) = TEMPLATES.get() {
+            return Ok(templates);
+        }
+        let templates = Self::from_env()?;
+        Ok(TEMPLATES.get_or_init(|| templates))
+    }
+
+    /// Subject and body with placeholders replaced, missing locale falls back to the default one
+    pub fn render(
+        &self,
+        kind: NotificationKind,
+        locale: &str,
+        params: &[(&str, String)],
+    ) -> Template {
+        let templates = &self.templates[&kind];
+        let template = templates.get(locale)
+            .unwrap_or(&templates[&self.default_locale]);
+
+        let replace = |text: &str| params.iter()
+            .fold(text.to_string(), |text, (key, value)| text.replace(&format!("{{{}}}", key), value));
+        Template {
+            subject: replace(&template.subject),
+            body: replace(&template.body),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationResponse {
+    pub id: i32,
+    pub candidate_id: i32,
+    pub kind: String,
+    pub locale: String,
+    pub status: String,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub sent_at: Option<NaiveDateTime>,
+}
+
+impl From<notification::Model> for NotificationResponse {
+    fn from(n: notification::Model) -> Self {
+        Self {
+            id: n.id,
+            candidate_id: n.candidate_id,
+            kind: n.kind,
+            locale: n.locale,
+            status: n.status,
+            attempts: n.attempts,
+            last_error: n.last_error,
+            created_at: n.created_at,
+            sent_at: n.sent_at,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NotificationKind, NotificationTemplates, DEFAULT_TEMPLATES};
+
+    #[test]
+    fn test_render_templates() {
+        let templates = NotificationTemplates::from_json(DEFAULT_TEMPLATES).unwrap();
+        let params = [("name", "Jan Novák".to_string()), ("applications", "101101".to_string())];
+
+        let cs = templates.render(NotificationKind::PortfolioSubmitted, "cs", &params);
+        assert!(cs.body.contains("Jan Novák"));
+        assert!(cs.body.contains("101101"));
+        assert_eq!(templates.render(NotificationKind::PortfolioSubmitted, "de", &params), cs);
+        assert_ne!(templates.render(NotificationKind::PortfolioSubmitted, "en", &params), cs);
+
+        assert!(NotificationTemplates::from_json(&DEFAULT_TEMPLATES.replace("\"cs\": {", "\"sk\": {")).is_err());
+    }
+}
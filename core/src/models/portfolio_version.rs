@@ -0,0 +1,80 @@
+use chrono::NaiveDateTime;
+use entity::portfolio_version;
+use serde::{Serialize, Deserialize};
+
+/// File inside the submitted zip archive
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestFile {
+    pub name: String,
+    /// SHA-256 of the file, hex encoded
+    pub hash: String,
+    pub size: i64,
+}
+
+/// What was submitted, recorded before the archive is encrypted
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveManifest {
+    pub files: Vec<ManifestFile>,
+    /// SHA-256 of the zip archive, hex encoded
+    pub hash: String,
+    pub size: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortfolioVersionResponse {
+    pub version: i32,
+    /// SHA-256 of the submitted zip archive before encryption, hex encoded
+    pub hash: String,
+    /// Size of the submitted zip archive in bytes
+    pub size: i64,
+    /// Files inside the archive, empty for versions submitted before manifests were recorded
+    pub files: Vec<ManifestFile>,
+    pub created_at: NaiveDateTime,
+    /// True for the submitted version, false for replaced and withdrawn ones
+    pub current: bool,
+}
+
+impl PortfolioVersionResponse {
+    pub fn new(version: portfolio_version::Model, current: Option<i32>) -> Self {
+        Self {
+            current: current == Some(version.version),
+            files: manifest_files(&version),
+            version: version.version,
+            hash: version.hash,
+            size: version.size,
+            created_at: version.created_at,
+        }
+    }
+}
+
+pub fn manifest_files(version: &portfolio_version::Model) -> Vec<ManifestFile> {
+    version.files
+        .as_ref()
+        .and_then(|files| serde_json::from_str(files).ok())
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IntegrityStatus {
+    Ok,
+    /// Stored ciphertext differs from the recorded one
+    Mismatch,
+    /// Version is recorded, but its file is missing in the store
+    Missing,
+    /// Version was submitted before manifests were recorded, it can't be checked
+    NoManifest,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityReport {
+    pub candidate_id: i32,
+    pub version: i32,
+    pub status: IntegrityStatus,
+    pub expected_hash: Option<String>,
+    pub actual_hash: Option<String>,
+}
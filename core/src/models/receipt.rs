@@ -0,0 +1,112 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as base64;
+use chrono::NaiveDateTime;
+use serde::{Serialize, Deserialize};
+
+use crate::{crypto, error::ServiceError};
+
+use super::portfolio_version::ManifestFile;
+
+const MIN_KEY_LENGTH: usize = 32;
+
+/// Proof of submission for the candidate, the signature covers every other field
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SubmissionReceipt {
+    pub candidate_id: i32,
+    pub version: i32,
+    pub submitted_at: NaiveDateTime,
+    pub files: Vec<ManifestFile>,
+    /// SHA-256 of the submitted zip archive, hex encoded
+    pub archive_hash: String,
+    pub archive_size: i64,
+    /// `None` when `PORTFOLIO_RECEIPT_KEY` is not configured
+    pub signature: Option<String>,
+}
+
+impl SubmissionReceipt {
+    /// Signed content, ciphertext is not included, so receipts stay valid after the portfolio is re-encrypted
+    fn payload(&self) -> String {
+        let files: Vec<String> = self.files.iter()
+            .map(|f| format!("{}={}:{}", f.name, f.hash, f.size))
+            .collect();
+
+        format!(
+            "receipt:{}:{}:{}:{}:{}:{}",
+            self.candidate_id,
+            self.version,
+            self.submitted_at.format("%Y-%m-%dT%H:%M:%S%.f"),
+            self.archive_hash,
+            self.archive_size,
+            files.join(";"),
+        )
+    }
+}
+
+/// Key signing submission receipts, read from base64 encoded `PORTFOLIO_RECEIPT_KEY`
+pub struct ReceiptKey(Vec<u8>);
+
+impl ReceiptKey {
+    pub fn new(key: Vec<u8>) -> Result<Self, ServiceError> {
+        if key.len() < MIN_KEY_LENGTH {
+            return Err(ServiceError::ReceiptConfigError(
+                format!("key must be at least {} bytes long", MIN_KEY_LENGTH)
+            ));
+        }
+
+        Ok(Self(key))
+    }
+
+    /// Returns None when the key is not configured, receipts are then issued without signature
+    pub fn from_env() -> Result<Option<Self>, ServiceError> {
+        dotenv::dotenv().ok();
+        let Ok(key) = std::env::var("PORTFOLIO_RECEIPT_KEY") else {
+            return Ok(None);
+        };
+
+        let key = base64.decode(key.trim())
+            .map_err(|e| ServiceError::ReceiptConfigError(e.to_string()))?;
+        Self::new(key).map(Some)
+    }
+
+    pub fn sign(&self, receipt: &mut SubmissionReceipt) {
+        receipt.signature = Some(crypto::hmac_sha256(&self.0, &receipt.payload()));
+    }
+
+    pub fn verify(&self, receipt: &SubmissionReceipt) -> bool {
+        receipt.signature.as_deref() == Some(crypto::hmac_sha256(&self.0, &receipt.payload()).as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use crate::models::portfolio_version::ManifestFile;
+
+    use super::{ReceiptKey, SubmissionReceipt};
+
+    #[test]
+    fn test_sign_and_verify_receipt() {
+        let key = ReceiptKey::new(vec![1; 32]).unwrap();
+        let mut receipt = SubmissionReceipt {
+            candidate_id: 1,
+            version: 2,
+            submitted_at: NaiveDate::from_ymd_opt(2023, 3, 1).unwrap().and_hms_opt(12, 0, 0).unwrap(),
+            files: vec![ManifestFile { name: "PORTFOLIO.pdf".to_string(), hash: "ab".to_string(), size: 1 }],
+            archive_hash: "cd".to_string(),
+            archive_size: 100,
+            signature: None,
+        };
+        assert!(!key.verify(&receipt));
+
+        key.sign(&mut receipt);
+        assert!(key.verify(&receipt));
+        assert!(!ReceiptKey::new(vec![2; 32]).unwrap().verify(&receipt));
+
+        let tampered = SubmissionReceipt { archive_size: 101, ..receipt };
+        assert!(!key.verify(&tampered));
+
+        assert!(ReceiptKey::new(vec![1; 16]).is_err());
+    }
+}
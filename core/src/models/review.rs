@@ -0,0 +1,252 @@
+use std::collections::{BTreeMap, HashSet};
+
+use chrono::NaiveDateTime;
+use serde::{Serialize, Deserialize};
+
+use crate::error::ServiceError;
+
+/// Points given by one reviewer, keyed by criterion
+pub type ReviewScores = BTreeMap<String, u32>;
+
+fn weight_default() -> u32 {
+    1
+}
+
+fn required_reviews_default() -> u32 {
+    1
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Criterion {
+    /// Identifies the criterion in submitted scores, e.g. `creativity`
+    pub key: String,
+    /// Shown to reviewers
+    pub name: String,
+    pub max_score: u32,
+    /// Multiplies the average points of the criterion in the total
+    #[serde(default = "weight_default")]
+    pub weight: u32,
+}
+
+/// How portfolios of a program are scored
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Rubric {
+    pub criteria: Vec<Criterion>,
+    /// Submitted reviews needed before the score is final
+    #[serde(default = "required_reviews_default")]
+    pub required_reviews: u32,
+}
+
+impl Rubric {
+    pub fn is_valid_key(key: &str) -> bool {
+        !key.is_empty() && key.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+    }
+
+    pub fn max_total(&self) -> u32 {
+        self.criteria.iter().map(|c| c.weight * c.max_score).sum()
+    }
+
+    /// Every criterion has to be scored and nothing else
+    pub fn validate_scores(&self, scores: &ReviewScores) -> Result<(), ServiceError> {
+        let error = |msg: String| Err(ServiceError::InvalidReview(msg));
+
+        for criterion in self.criteria.iter() {
+            match scores.get(&criterion.key) {
+                None => return error(format!("missing score of {}", criterion.key)),
+                Some(score) if *score > criterion.max_score => {
+                    return error(format!("score of {} is over {}", criterion.key, criterion.max_score))
+                },
+                _ => {},
+            }
+        }
+        let keys: HashSet<&String> = self.criteria.iter().map(|c| &c.key).collect();
+        if let Some(key) = scores.keys().find(|key| !keys.contains(key)) {
+            return error(format!("unknown criterion {}", key));
+        }
+        Ok(())
+    }
+}
+
+/// Rubric of programs which don't configure their own
+pub fn default_rubric() -> Rubric {
+    Rubric {
+        criteria: vec![
+            Criterion {
+                key: "overall".to_string(),
+                name: "Celkové hodnocení portfolia".to_string(),
+                max_score: 10,
+                weight: 1,
+            },
+        ],
+        required_reviews: 1,
+    }
+}
+
+/// Application the admin was assigned to review
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReviewAssignmentResponse {
+    pub application_id: i32,
+    pub program: Option<String>,
+    pub rubric: Rubric,
+    pub assigned_at: NaiveDateTime,
+    pub submitted_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReviewResponse {
+    pub application_id: i32,
+    pub admin_id: i32,
+    /// Empty until the review is submitted
+    pub scores: ReviewScores,
+    pub comment: Option<String>,
+    pub assigned_at: NaiveDateTime,
+    pub submitted_at: Option<NaiveDateTime>,
+}
+
+/// Aggregate score of one application over all submitted reviews
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ScoreSummary {
+    pub candidate_id: i32,
+    pub application_id: i32,
+    pub program: Option<String>,
+    /// Submitted reviews
+    pub reviews: usize,
+    pub required_reviews: u32,
+    /// Average points per criterion
+    pub criteria: BTreeMap<String, f64>,
+    /// Weighted sum of the averages, none until the first review is submitted
+    pub total: Option<f64>,
+    pub max_total: u32,
+    /// Enough reviews were submitted for the score to be final
+    pub complete: bool,
+}
+
+impl ScoreSummary {
+    pub fn aggregate(
+        candidate_id: i32,
+        application_id: i32,
+        program: Option<String>,
+        rubric: &Rubric,
+        reviews: &[ReviewScores],
+    ) -> Self {
+        let mut criteria = BTreeMap::new();
+        let mut total = None;
+        if !reviews.is_empty() {
+            let mut sum = 0.0;
+            for criterion in rubric.criteria.iter() {
+                let points: u32 = reviews.iter()
+                    .filter_map(|r| r.get(&criterion.key))
+                    .sum();
+                let mean = points as f64 / reviews.len() as f64;
+                sum += mean * criterion.weight as f64;
+                criteria.insert(criterion.key.to_owned(), mean);
+            }
+            total = Some(sum);
+        }
+
+        Self {
+            candidate_id,
+            application_id,
+            program,
+            reviews: reviews.len(),
+            required_reviews: rubric.required_reviews,
+            criteria,
+            total,
+            max_total: rubric.max_total(),
+            complete: reviews.len() >= rubric.required_reviews as usize,
+        }
+    }
+
+    /// Columns of the score export, starting with the candidate number so it can be joined with the admissions export
+    pub fn headers() -> Vec<String> {
+        vec![
+            "Číslo uchazeče (přiděleno systémem)".to_string(),
+            "Ev. č. přihlášky".to_string(),
+            "Obor".to_string(),
+            "Počet hodnocení".to_string(),
+            "Požadovaný počet hodnocení".to_string(),
+            "Hodnocení podle kritérií".to_string(),
+            "Celkové skóre".to_string(),
+            "Maximální skóre".to_string(),
+            "Hodnocení dokončeno".to_string(),
+        ]
+    }
+
+    pub fn record(&self) -> Vec<String> {
+        let criteria = self.criteria
+            .iter()
+            .map(|(key, mean)| format!("{}: {:.2}", key, mean))
+            .collect::<Vec<_>>()
+            .join("; ");
+        vec![
+            self.candidate_id.to_string(),
+            self.application_id.to_string(),
+            self.program.to_owned().unwrap_or_default(),
+            self.reviews.to_string(),
+            self.required_reviews.to_string(),
+            criteria,
+            self.total.map(|t| format!("{:.2}", t)).unwrap_or_default(),
+            self.max_total.to_string(),
+            self.complete.to_string(),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{default_rubric, Criterion, ReviewScores, Rubric, ScoreSummary};
+
+    fn rubric() -> Rubric {
+        Rubric {
+            criteria: vec![
+                Criterion { key: "creativity".to_string(), name: "Kreativita".to_string(), max_score: 5, weight: 2 },
+                Criterion { key: "technique".to_string(), name: "Technika".to_string(), max_score: 10, weight: 1 },
+            ],
+            required_reviews: 2,
+        }
+    }
+
+    fn scores(creativity: u32, technique: u32) -> ReviewScores {
+        ReviewScores::from([("creativity".to_string(), creativity), ("technique".to_string(), technique)])
+    }
+
+    #[test]
+    fn test_validate_scores() {
+        let rubric = rubric();
+        assert!(rubric.validate_scores(&scores(5, 0)).is_ok());
+        assert!(rubric.validate_scores(&scores(6, 0)).is_err());
+        assert!(rubric.validate_scores(&ReviewScores::from([("creativity".to_string(), 1)])).is_err());
+
+        let mut extra = scores(1, 1);
+        extra.insert("overall".to_string(), 1);
+        assert!(rubric.validate_scores(&extra).is_err());
+    }
+
+    #[test]
+    fn test_aggregate_scores() {
+        let rubric = rubric();
+        assert_eq!(rubric.max_total(), 20);
+
+        let summary = ScoreSummary::aggregate(1, 103_151, Some("KB".to_string()), &rubric, &[]);
+        assert_eq!(summary.total, None);
+        assert!(summary.criteria.is_empty());
+        assert!(!summary.complete);
+
+        let summary = ScoreSummary::aggregate(1, 103_151, None, &rubric, &[scores(4, 6), scores(3, 9)]);
+        assert_eq!(summary.criteria["creativity"], 3.5);
+        assert_eq!(summary.criteria["technique"], 7.5);
+        assert_eq!(summary.total, Some(14.5));
+        assert!(summary.complete);
+        assert_eq!(summary.record()[5], "creativity: 3.50; technique: 7.50");
+        assert_eq!(summary.record().len(), ScoreSummary::headers().len());
+
+        let summary = ScoreSummary::aggregate(1, 103_151, None, &default_rubric(), &[ReviewScores::from([("overall".to_string(), 7)])]);
+        assert_eq!(summary.total, Some(7.0));
+        assert!(summary.complete);
+    }
+}
@@ -0,0 +1,68 @@
+use crate::error::ServiceError;
+
+/// Lowercases text and strips Czech diacritics, so that "Šťastný" is found by "stastny"
+pub fn normalize(text: &str) -> String {
+    text.chars()
+        .flat_map(char::to_lowercase)
+        .map(|c| match c {
+            'á' | 'ä' => 'a',
+            'č' => 'c',
+            'ď' => 'd',
+            'é' | 'ě' | 'ë' => 'e',
+            'í' => 'i',
+            'ĺ' | 'ľ' => 'l',
+            'ň' => 'n',
+            'ó' | 'ô' | 'ö' => 'o',
+            'ŕ' | 'ř' => 'r',
+            'š' => 's',
+            'ť' => 't',
+            'ú' | 'ů' | 'ü' => 'u',
+            'ý' => 'y',
+            'ž' => 'z',
+            c => c,
+        })
+        .collect()
+}
+
+/// Full-text query, every whitespace separated term has to be found in the searched text
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchQuery(Vec<String>);
+
+impl SearchQuery {
+    pub fn parse(query: &str) -> Result<Self, ServiceError> {
+        let terms: Vec<String> = query.split_whitespace()
+            .map(normalize)
+            .collect();
+        if terms.is_empty() {
+            return Err(ServiceError::InvalidSearchQuery);
+        }
+
+        Ok(Self(terms))
+    }
+
+    /// `haystack` has to be normalized already
+    pub fn matches(&self, haystack: &str) -> bool {
+        self.0.iter().all(|term| haystack.contains(term.as_str()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{normalize, SearchQuery};
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("Šťastný Čeněk"), "stastny cenek");
+        assert_eq!(normalize("ŮŽASNÁ Ř"), "uzasna r");
+    }
+
+    #[test]
+    fn test_search_query() {
+        let query = SearchQuery::parse("  Novák  jan ").unwrap();
+        assert!(query.matches(&normalize("Jan Novák jan.novak@example.com")));
+        assert!(query.matches(&normalize("Janek Nováková")));
+        assert!(!query.matches(&normalize("Jan Dvořák")));
+
+        assert!(SearchQuery::parse("   ").is_err());
+    }
+}
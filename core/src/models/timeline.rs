@@ -0,0 +1,121 @@
+use chrono::NaiveDateTime;
+use serde::{Serialize, Deserialize};
+
+/// Phases of the admission cycle in the order they follow each other,
+/// every phase allows less to candidates than the previous one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AdmissionPhase {
+    /// New applications are registered, candidates fill in details and upload portfolios.
+    /// Also the phase before the timeline starts and when no timeline is configured
+    RegistrationOpen,
+    /// Registration is closed, details and portfolios can still be changed
+    DetailsEditable,
+    /// Details are locked, portfolios can still be uploaded and submitted
+    PortfolioUpload,
+    /// Candidates can change nothing, admins review the applications
+    Review,
+    Closed,
+}
+
+/// What candidates do with their application, allowed only in some phases
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CandidateAction {
+    EditDetails,
+    EditPortfolio,
+}
+
+impl AdmissionPhase {
+    pub fn allows(&self, action: CandidateAction) -> bool {
+        match action {
+            CandidateAction::EditDetails => *self <= AdmissionPhase::DetailsEditable,
+            CandidateAction::EditPortfolio => *self <= AdmissionPhase::PortfolioUpload,
+        }
+    }
+}
+
+/// Start of every phase, each phase lasts until the next one starts
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Timeline {
+    pub registration_open: NaiveDateTime,
+    pub details_editable: NaiveDateTime,
+    pub portfolio_upload: NaiveDateTime,
+    pub review: NaiveDateTime,
+    pub closed: NaiveDateTime,
+}
+
+impl Timeline {
+    fn starts(&self) -> [(AdmissionPhase, NaiveDateTime); 5] {
+        [
+            (AdmissionPhase::RegistrationOpen, self.registration_open),
+            (AdmissionPhase::DetailsEditable, self.details_editable),
+            (AdmissionPhase::PortfolioUpload, self.portfolio_upload),
+            (AdmissionPhase::Review, self.review),
+            (AdmissionPhase::Closed, self.closed),
+        ]
+    }
+
+    pub fn is_ordered(&self) -> bool {
+        self.starts().windows(2).all(|w| w[0].1 <= w[1].1)
+    }
+
+    pub fn phase_at(&self, time: NaiveDateTime) -> AdmissionPhase {
+        self.starts()
+            .into_iter()
+            .rev()
+            .find(|(_, start)| *start <= time)
+            .map(|(phase, _)| phase)
+            .unwrap_or(AdmissionPhase::RegistrationOpen)
+    }
+
+    /// When the phase at `time` ends, `None` for the last phase
+    pub fn phase_ends_at(&self, time: NaiveDateTime) -> Option<NaiveDateTime> {
+        self.starts()
+            .into_iter()
+            .map(|(_, start)| start)
+            .find(|start| *start > time)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineResponse {
+    pub phase: AdmissionPhase,
+    pub phase_ends_at: Option<NaiveDateTime>,
+    pub timeline: Option<Timeline>,
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{AdmissionPhase, CandidateAction, Timeline};
+
+    #[test]
+    fn test_phase_at() {
+        let day = |d: u32| NaiveDate::from_ymd_opt(2023, 3, d).unwrap().and_hms_opt(0, 0, 0).unwrap();
+        let timeline = Timeline {
+            registration_open: day(1),
+            details_editable: day(10),
+            portfolio_upload: day(20),
+            review: day(25),
+            closed: day(30),
+        };
+        assert!(timeline.is_ordered());
+
+        assert_eq!(timeline.phase_at(day(1) - chrono::Duration::days(1)), AdmissionPhase::RegistrationOpen);
+        assert_eq!(timeline.phase_at(day(10)), AdmissionPhase::DetailsEditable);
+        assert_eq!(timeline.phase_at(day(21)), AdmissionPhase::PortfolioUpload);
+        assert_eq!(timeline.phase_at(day(31)), AdmissionPhase::Closed);
+        assert_eq!(timeline.phase_ends_at(day(21)), Some(day(25)));
+        assert_eq!(timeline.phase_ends_at(day(31)), None);
+
+        assert!(!AdmissionPhase::PortfolioUpload.allows(CandidateAction::EditDetails));
+        assert!(AdmissionPhase::PortfolioUpload.allows(CandidateAction::EditPortfolio));
+        assert!(!AdmissionPhase::Review.allows(CandidateAction::EditPortfolio));
+
+        let unordered = Timeline { review: day(5), ..timeline };
+        assert!(!unordered.is_ordered());
+    }
+}
@@ -0,0 +1,13 @@
+use sea_orm::prelude::Uuid;
+use serde::{Serialize, Deserialize};
+
+/// State of a resumable upload, the client continues by sending the chunk starting at `offset`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadResponse {
+    pub upload_id: Uuid,
+    /// Bytes received so far
+    pub offset: u64,
+    /// Declared size of the whole file
+    pub size: u64,
+}
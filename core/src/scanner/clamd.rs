@@ -0,0 +1,122 @@
+use async_trait::async_trait;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::error::ServiceError;
+
+use super::{MalwareScanner, ScanResult};
+
+/// clamd accepts chunks of at most `StreamMaxLength` in total (25 MB by default), the chunk size itself is not limited
+const CHUNK_SIZE: usize = 64 * 1024;
+const DEFAULT_ADDRESS: &str = "127.0.0.1:3310";
+
+/// Talks to ClamAV daemon over its socket protocol (`INSTREAM` command)
+pub struct ClamdScanner {
+    /// `host:port`, or path to unix socket starting with `/`
+    address: String,
+}
+
+impl ClamdScanner {
+    pub fn new(address: String) -> Self {
+        Self { address }
+    }
+
+    /// Address is taken from `PORTFOLIO_CLAMD_ADDRESS`, clamd on localhost is the default.
+    /// clamd has to be configured with `StreamMaxLength` and `MaxScanSize` over the largest document size
+    /// (101 MB), otherwise large uploads fail to scan
+    pub fn from_env() -> Self {
+        dotenv::dotenv().ok();
+        Self::new(std::env::var("PORTFOLIO_CLAMD_ADDRESS").unwrap_or_else(|_| DEFAULT_ADDRESS.to_string()))
+    }
+
+    async fn instream<S: AsyncRead + AsyncWrite + Unpin>(mut stream: S, data: &[u8]) -> Result<String, ServiceError> {
+        stream.write_all(b"zINSTREAM\0").await?;
+        for chunk in data.chunks(CHUNK_SIZE) {
+            stream.write_all(&(chunk.len() as u32).to_be_bytes()).await?;
+            stream.write_all(chunk).await?;
+        }
+        // Zero length chunk ends the stream
+        stream.write_all(&0u32.to_be_bytes()).await?;
+        stream.flush().await?;
+
+        let mut reply = vec![];
+        stream.read_to_end(&mut reply).await?;
+        Ok(String::from_utf8_lossy(&reply).trim_end_matches(['\0', '\n']).to_string())
+    }
+
+    /// Reply is `stream: OK`, `stream: <signature> FOUND` or `<reason> ERROR`
+    fn parse_reply(reply: &str) -> Result<ScanResult, ServiceError> {
+        let result = reply.strip_prefix("stream: ").unwrap_or(reply);
+        if result == "OK" {
+            Ok(ScanResult::Clean)
+        } else if let Some(signature) = result.strip_suffix(" FOUND") {
+            Ok(ScanResult::Infected(signature.to_string()))
+        } else {
+            Err(ServiceError::ScannerError(reply.to_string()))
+        }
+    }
+}
+
+#[async_trait]
+impl MalwareScanner for ClamdScanner {
+    async fn scan(&self, data: &[u8]) -> Result<ScanResult, ServiceError> {
+        let reply = if self.address.starts_with('/') {
+            Self::instream(tokio::net::UnixStream::connect(&self.address).await?, data).await?
+        } else {
+            Self::instream(tokio::net::TcpStream::connect(&self.address).await?, data).await?
+        };
+
+        Self::parse_reply(&reply)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use crate::scanner::{MalwareScanner, ScanResult};
+
+    use super::ClamdScanner;
+
+    /// Reads one `INSTREAM` command and replies like clamd, infected when the data starts with `X5O`
+    async fn fake_clamd() -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut command = [0; 10];
+            socket.read_exact(&mut command).await.unwrap();
+            assert_eq!(&command, b"zINSTREAM\0");
+
+            let mut data = vec![];
+            loop {
+                let length = socket.read_u32().await.unwrap() as usize;
+                if length == 0 {
+                    break;
+                }
+                let mut chunk = vec![0; length];
+                socket.read_exact(&mut chunk).await.unwrap();
+                data.extend(chunk);
+            }
+
+            let reply: &[u8] = if data.starts_with(b"X5O") { b"stream: Eicar-Signature FOUND\0" } else { b"stream: OK\0" };
+            socket.write_all(reply).await.unwrap();
+        });
+
+        address
+    }
+
+    #[tokio::test]
+    async fn test_clamd_scan() {
+        let scanner = ClamdScanner::new(fake_clamd().await);
+        assert_eq!(scanner.scan(&[1; 100_000]).await.unwrap(), ScanResult::Clean);
+
+        let scanner = ClamdScanner::new(fake_clamd().await);
+        assert_eq!(
+            scanner.scan(b"X5O!P%@AP[4\\PZX54(P^)7CC)7}$EICAR").await.unwrap(),
+            ScanResult::Infected("Eicar-Signature".to_string())
+        );
+
+        assert!(ClamdScanner::parse_reply("INSTREAM size limit exceeded. ERROR").is_err());
+    }
+}
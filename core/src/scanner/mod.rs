@@ -0,0 +1,60 @@
+use async_trait::async_trait;
+
+use crate::error::ServiceError;
+
+pub mod clamd;
+
+pub use self::clamd::ClamdScanner;
+
+/// Marker of the EICAR anti-virus test file, detected by every scanner
+const EICAR_MARKER: &[u8] = b"EICAR-STANDARD-ANTIVIRUS-TEST-FILE";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScanResult {
+    Clean,
+    /// Name of the detected signature
+    Infected(String),
+}
+
+/// Scans uploaded files before they are written into the candidate cache
+#[async_trait]
+pub trait MalwareScanner: Send + Sync {
+    async fn scan(&self, data: &[u8]) -> Result<ScanResult, ServiceError>;
+}
+
+/// Accepts everything, used when no scanner is configured
+pub struct NoopScanner;
+
+#[async_trait]
+impl MalwareScanner for NoopScanner {
+    async fn scan(&self, _data: &[u8]) -> Result<ScanResult, ServiceError> {
+        Ok(ScanResult::Clean)
+    }
+}
+
+/// Detects only the EICAR test file, so that quarantine can be tested without a running ClamAV
+pub struct FakeScanner;
+
+#[async_trait]
+impl MalwareScanner for FakeScanner {
+    async fn scan(&self, data: &[u8]) -> Result<ScanResult, ServiceError> {
+        if data.windows(EICAR_MARKER.len()).any(|w| w == EICAR_MARKER) {
+            Ok(ScanResult::Infected("Eicar-Test-Signature".to_string()))
+        } else {
+            Ok(ScanResult::Clean)
+        }
+    }
+}
+
+/// Returns scanner selected by `PORTFOLIO_SCANNER` (`none`, `clamd` or `fake`), files are not scanned by default
+pub fn from_env() -> Result<Box<dyn MalwareScanner>, ServiceError> {
+    dotenv::dotenv().ok();
+    match std::env::var("PORTFOLIO_SCANNER").as_deref() {
+        Ok("none") | Err(_) => Ok(Box::new(NoopScanner)),
+        Ok("clamd") => Ok(Box::new(ClamdScanner::from_env())),
+        Ok("fake") => Ok(Box::new(FakeScanner)),
+        Ok(scanner) => Err(ServiceError::ScannerConfigError(
+            format!("Unknown scanner {}", scanner)
+        )),
+    }
+}
@@ -1,10 +1,11 @@
 use async_trait::async_trait;
 use entity::{admin, admin_session};
-use sea_orm::{prelude::Uuid, DbConn, IntoActiveModel};
+use log::info;
+use sea_orm::{prelude::Uuid, DbConn, IntoActiveModel, TransactionTrait};
 
-use crate::{crypto, error::ServiceError, Query, Mutation, models::auth::AuthenticableTrait};
+use crate::{crypto, error::ServiceError, Query, Mutation, models::{admin::AdminRole, audit::{AuditAction, AuditActor, AuditContext}, auth::AuthenticableTrait, login_attempt::LoginScope}};
 
-use super::session_service::SessionService;
+use super::{audit_service::AuditService, key_rotation_service::KeyRotationService, login_throttle_service::LoginThrottleService, search_service::SearchService, session_service::SessionService};
 
 pub struct AdminService;
 
@@ -20,6 +21,94 @@ impl AdminService {
 
         Ok(private_key)
     }
+
+    /// Finds the admin owning `private_key`, only superadmins may add or remove other admins
+    async fn authorize_private_key(
+        db: &DbConn,
+        private_key: &str,
+    ) -> Result<admin::Model, ServiceError> {
+        let public_key = crypto::public_key_from_private_key(private_key)
+            .map_err(|_| ServiceError::Unauthorized)?;
+
+        let admin = Query::find_admin_by_public_key(db, &public_key)
+            .await?
+            .ok_or(ServiceError::Unauthorized)?;
+
+        if !AdminRole::try_from(&admin)?.has_permission(AdminRole::Superadmin) {
+            return Err(ServiceError::Forbidden);
+        }
+
+        Ok(admin)
+    }
+
+    /// Creates a new admin and re-encrypts all candidate data and portfolios,
+    /// so that the new admin can read them. `admin_private_key` belongs to an existing admin
+    pub async fn create_admin(
+        db: &DbConn,
+        admin_private_key: String,
+        name: String,
+        password: String,
+        role: AdminRole,
+    ) -> Result<admin::Model, ServiceError> {
+        let authorized_by = Self::authorize_private_key(db, &admin_private_key).await?;
+
+        let (pubkey, priv_key) = crypto::create_identity();
+        let encrypted_priv_key = crypto::encrypt_password(priv_key, password.to_owned()).await?;
+        let password_hash = crypto::hash_password(password).await?;
+
+        // Admin is inserted only once all candidates are readable with the new key,
+        // failed re-encryption leaves no admin behind and creating the admin can be started again
+        let mut admin_public_keys = Query::get_all_admin_public_keys(db).await?;
+        admin_public_keys.push(pubkey.to_owned());
+        KeyRotationService::reencrypt_all_candidates(db, &[admin_private_key], &admin_public_keys).await?;
+
+        let admin = Mutation::create_admin(db, name, pubkey, encrypted_priv_key, password_hash, role.into()).await?;
+        info!("ADMIN {} CREATED BY ADMIN {}", admin.id, authorized_by.id);
+
+        Ok(admin)
+    }
+
+    /// Re-encrypts all candidate data and portfolios without the removed admin's keys and deletes the admin.
+    /// If re-encryption fails, the admin is kept and removal can be started again
+    pub async fn remove_admin(
+        db: &DbConn,
+        admin_private_key: String,
+        admin_id: i32,
+    ) -> Result<(), ServiceError> {
+        let authorized_by = Self::authorize_private_key(db, &admin_private_key).await?;
+        let admin = Query::find_admin_by_id(db, admin_id)
+            .await?
+            .ok_or(ServiceError::AdminNotFound)?;
+
+        let rotation = Query::find_admin_key_rotation(db, admin_id).await?;
+
+        let mut removed_keys = vec![admin.public_key.to_owned()];
+        if let Some(rotation) = &rotation {
+            removed_keys.push(rotation.public_key.to_owned());
+        }
+        let admin_public_keys: Vec<String> = Query::get_all_admin_public_keys(db)
+            .await?
+            .into_iter()
+            .filter(|public_key| !removed_keys.contains(public_key))
+            .collect();
+        if admin_public_keys.is_empty() {
+            return Err(ServiceError::LastAdmin);
+        }
+
+        KeyRotationService::reencrypt_all_candidates(db, &[admin_private_key], &admin_public_keys).await?;
+
+        let txn = db.begin().await?;
+        Mutation::delete_admin_sessions(&txn, admin_id).await?;
+        Mutation::delete_admin_reviews(&txn, admin_id).await?;
+        if let Some(rotation) = rotation {
+            Mutation::delete_admin_key_rotation(&txn, rotation).await?;
+        }
+        Mutation::delete_admin(&txn, admin).await?;
+        txn.commit().await?;
+
+        info!("ADMIN {} REMOVED BY ADMIN {}", admin_id, authorized_by.id);
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -33,16 +122,28 @@ impl AuthenticableTrait for AdminService {
         password: String,
         ip_addr: String,
     ) -> Result<(String, String), ServiceError> {
-        let admin = Query::find_admin_by_id(db, admin_id).await?.ok_or(ServiceError::InvalidCredentials)?;
+        let account = (LoginScope::Admin, admin_id.to_string());
+        let (admin, session_id) = LoginThrottleService::throttle(db, account, &ip_addr, async {
+            let admin = Query::find_admin_by_id(db, admin_id).await?.ok_or(ServiceError::InvalidCredentials)?;
 
-        let session_id = Self::new_session(db,
-            &admin,
-            password.clone(),
-            ip_addr
-        )
-            .await?;
-        
-        let private_key = Self::decrypt_private_key(db, admin.id, password).await?;
+            let session_id = Self::new_session(db,
+                &admin,
+                password.clone(),
+                ip_addr.to_owned()
+            )
+                .await?;
+            Ok((admin, session_id))
+        }).await?;
+
+        let private_key = Self::decrypt_private_key(db, admin.id, password.clone()).await?;
+        if crypto::is_legacy_encrypted_password(&admin.private_key) {
+            let encrypted_priv_key = crypto::encrypt_password(private_key.clone(), password).await?;
+            let public_key = admin.public_key.clone();
+            Mutation::update_admin_keys(db, admin.clone(), public_key, encrypted_priv_key).await?;
+        }
+
+        let audit = AuditContext::new(AuditActor::Admin(admin.id), Some(ip_addr));
+        AuditService::log(db, &audit, AuditAction::Login, None).await?;
         Ok((session_id, private_key))
     }
 
@@ -52,6 +153,7 @@ impl AuthenticableTrait for AdminService {
             .ok_or(ServiceError::Unauthorized)?;
 
         if !SessionService::is_valid(&session).await? {
+            SearchService::drop_session(session.id);
             Mutation::delete_session(db, session.into_active_model()).await?;
             return Err(ServiceError::ExpiredSession);
         }
@@ -64,6 +166,7 @@ impl AuthenticableTrait for AdminService {
     }
 
     async fn logout(db: &DbConn, session: admin_session::Model) -> Result<(), ServiceError> {
+        SearchService::drop_session(session.id);
         Mutation::delete_session(db, session.into_active_model()).await?;
         Ok(())
     }
@@ -158,9 +261,79 @@ pub mod admin_tests {
 
         assert_eq!(logged_admin.id, 1);
         assert_eq!(logged_admin.name, "Admin");
-        
+
+        // legacy private key is upgraded on login
+        assert!(!crypto::is_legacy_encrypted_password(&logged_admin.private_key));
+        let (_, private_key) = AdminService::login(&db, admin.id, "test".to_owned(), "127.0.0.1".to_owned()).await?;
+        assert_eq!(private_key, "AGE-SECRET-KEY-14QG24502DMUUQDT2SPMX2YXPSES0X8UD6NT0PCTDAT6RH8V5Q3GQGSRXPS");
 
         Ok(())
 
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_create_and_remove_admin() {
+        use crate::{models::candidate_details::EncryptedApplicationDetails, services::candidate_service::tests::put_user_data};
+
+        let db = get_memory_sqlite_connection().await;
+        let admin = create_admin(&db).await;
+        let (application, candidate, _) = put_user_data(&db).await;
+        let private_key = crypto::decrypt_password(admin.private_key.clone(), "admin".to_string()).await.unwrap();
+        Mutation::create_review_assignment(&db, application.id, admin.id).await.unwrap();
+
+        let new_admin = AdminService::create_admin(&db, private_key.clone(), "new admin".to_string(), "new".to_string(), AdminRole::Superadmin)
+            .await
+            .unwrap();
+        let new_private_key = crypto::decrypt_password(new_admin.private_key.clone(), "new".to_string()).await.unwrap();
+        assert!(crypto::verify_password("new".to_string(), new_admin.password.clone()).await.unwrap());
+
+        let candidate = Query::find_candidate_by_id(&db, candidate.id).await.unwrap().unwrap();
+        let parents = Query::find_candidate_parents(&db, &candidate).await.unwrap();
+        let details = EncryptedApplicationDetails::from((&candidate, &parents));
+        assert!(details.clone().decrypt(new_private_key.clone()).await.is_ok());
+        assert!(details.decrypt(private_key.clone()).await.is_ok());
+
+        AdminService::remove_admin(&db, new_private_key.clone(), admin.id).await.unwrap();
+        assert!(Query::find_admin_by_id(&db, admin.id).await.unwrap().is_none());
+        assert!(Query::list_reviews_by_admin_id(&db, admin.id).await.unwrap().is_empty());
+
+        let candidate = Query::find_candidate_by_id(&db, candidate.id).await.unwrap().unwrap();
+        let details = EncryptedApplicationDetails::from((&candidate, &parents));
+        assert!(details.decrypt(private_key).await.is_err());
+
+        assert!(matches!(
+            AdminService::remove_admin(&db, new_private_key, new_admin.id).await,
+            Err(ServiceError::LastAdmin)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_create_admin_unauthorized() {
+        let db = get_memory_sqlite_connection().await;
+        create_admin(&db).await;
+        let (_, unknown_private_key) = crypto::create_identity();
+
+        assert!(matches!(
+            AdminService::create_admin(&db, unknown_private_key, "admin".to_string(), "admin".to_string(), AdminRole::Reviewer).await,
+            Err(ServiceError::Unauthorized)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_create_admin_forbidden_for_reviewer() {
+        let db = get_memory_sqlite_connection().await;
+        let admin = create_admin(&db).await;
+        let private_key = crypto::decrypt_password(admin.private_key.clone(), "admin".to_string()).await.unwrap();
+
+        let reviewer = AdminService::create_admin(&db, private_key, "reviewer".to_string(), "reviewer".to_string(), AdminRole::Reviewer)
+            .await
+            .unwrap();
+        assert_eq!(reviewer.role, "reviewer");
+        let reviewer_private_key = crypto::decrypt_password(reviewer.private_key, "reviewer".to_string()).await.unwrap();
+
+        assert!(matches!(
+            AdminService::remove_admin(&db, reviewer_private_key, admin.id).await,
+            Err(ServiceError::Forbidden)
+        ));
+    }
+}
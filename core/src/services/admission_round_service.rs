@@ -0,0 +1,322 @@
+use async_compat::CompatExt;
+use chrono::{Datelike, Duration};
+use entity::{admission_round, application, candidate, parent};
+use futures::io::AsyncWriteExt;
+use log::{info, warn};
+use sea_orm::{DbConn, IntoActiveModel, TransactionTrait};
+use serde_json::json;
+
+use crate::{error::ServiceError, crypto, models::admission_round::AdmissionRoundResponse, Mutation, Query};
+
+use super::{portfolio_service::PortfolioService, search_service::SearchService};
+
+pub struct AdmissionRoundService;
+
+impl AdmissionRoundService {
+    /// Returns the open round, first round is created on demand and named after the current year
+    pub async fn current(db: &DbConn) -> Result<admission_round::Model, ServiceError> {
+        if let Some(round) = Query::find_open_admission_round(db).await? {
+            return Ok(round);
+        }
+
+        let name = chrono::offset::Local::now().year().to_string();
+        Ok(Mutation::create_admission_round(db, name).await?)
+    }
+
+    /// Closes the current round and opens a new one, new candidates are created in the new round
+    pub async fn open(db: &DbConn, name: String) -> Result<admission_round::Model, ServiceError> {
+        if let Some(round) = Query::find_open_admission_round(db).await? {
+            Mutation::close_admission_round(db, round).await?;
+        }
+
+        Ok(Mutation::create_admission_round(db, name).await?)
+    }
+
+    pub async fn list(db: &DbConn) -> Result<Vec<AdmissionRoundResponse>, ServiceError> {
+        Ok(
+            Query::list_admission_rounds(db)
+                .await?
+                .into_iter()
+                .map(AdmissionRoundResponse::from)
+                .collect()
+        )
+    }
+
+    async fn find(db: &DbConn, round_id: i32) -> Result<admission_round::Model, ServiceError> {
+        Query::find_admission_round_by_id(db, round_id)
+            .await?
+            .ok_or(ServiceError::AdmissionRoundNotFound)
+    }
+
+    /// Writes zip archive of a closed round encrypted to all admins.
+    /// Archive contains `round.json`, `candidates.json` with still encrypted candidate,
+    /// application and parent rows and submitted portfolios as `portfolios/<candidate_id>.age`
+    pub async fn archive<W: tokio::io::AsyncWrite + Unpin>(
+        db: &DbConn,
+        round_id: i32,
+        output: W,
+    ) -> Result<admission_round::Model, ServiceError> {
+        let round = Self::find(db, round_id).await?;
+        if round.closed_at.is_none() {
+            return Err(ServiceError::AdmissionRoundOpen);
+        }
+        if round.purged_at.is_some() {
+            return Err(ServiceError::Locked);
+        }
+
+        info!("ADMISSION ROUND {} ARCHIVE STARTED", round.id);
+
+        let admin_public_keys = Query::get_all_admin_public_keys(db).await?;
+        let recipients = admin_public_keys.iter().map(|s| &**s).collect();
+
+        let candidates = Query::list_candidates_in_round(db, round.id).await?;
+        let mut rows = vec![];
+        for candidate in candidates.iter() {
+            let applications = Query::find_applications_by_candidate_id(db, candidate.id).await?;
+            let parents = Query::find_candidate_parents(db, candidate).await?;
+            rows.push(Self::candidate_archive_row(candidate, &applications, &parents));
+        }
+
+        let encrypt_writer = crypto::age_encrypt_writer(output, &recipients).await?;
+        let mut writer = async_zip::base::write::ZipFileWriter::new(encrypt_writer);
+
+        let round_json = serde_json::to_vec_pretty(&AdmissionRoundResponse::from(round.clone()))
+            .map_err(|_| ServiceError::FormatError)?;
+        let candidates_json = serde_json::to_vec_pretty(&rows)
+            .map_err(|_| ServiceError::FormatError)?;
+        for (filename, data) in [("round.json", round_json), ("candidates.json", candidates_json)] {
+            let builder = async_zip::ZipEntryBuilder::new(
+                filename.to_string().into(),
+                async_zip::Compression::Deflate,
+            );
+            writer.write_entry_whole(builder, &data).await?;
+        }
+
+        for candidate in candidates.iter() {
+            if !PortfolioService::is_portfolio_submitted(candidate.id).await {
+                continue;
+            }
+
+            let portfolio = PortfolioService::get_encrypted_portfolio_stream(candidate.id).await?;
+            // Portfolio is already encrypted, compression would not help
+            let builder = async_zip::ZipEntryBuilder::new(
+                format!("portfolios/{}.age", candidate.id).into(),
+                async_zip::Compression::Stored,
+            );
+
+            let mut entry_writer = writer.write_entry_stream(builder).await?;
+            futures::io::copy(portfolio.compat(), &mut entry_writer).await?;
+            entry_writer.close().await?;
+        }
+
+        let mut encrypt_writer = writer.close().await?;
+        encrypt_writer.close().await?;
+
+        let round = Mutation::set_admission_round_archived(db, round).await?;
+
+        info!("ADMISSION ROUND {} ARCHIVE FINISHED", round.id);
+        Ok(round)
+    }
+
+    fn candidate_archive_row(
+        candidate: &candidate::Model,
+        applications: &[application::Model],
+        parents: &[parent::Model],
+    ) -> serde_json::Value {
+        json!({
+            "id": candidate.id,
+            "name": candidate.name,
+            "surname": candidate.surname,
+            "birthSurname": candidate.birth_surname,
+            "birthplace": candidate.birthplace,
+            "birthdate": candidate.birthdate,
+            "address": candidate.address,
+            "letterAddress": candidate.letter_address,
+            "telephone": candidate.telephone,
+            "citizenship": candidate.citizenship,
+            "email": candidate.email,
+            "sex": candidate.sex,
+            "personalIdentificationNumber": candidate.personal_identification_number,
+            "schoolName": candidate.school_name,
+            "healthInsurance": candidate.health_insurance,
+            "gradesJson": candidate.grades_json,
+            "firstSchool": candidate.first_school,
+            "secondSchool": candidate.second_school,
+            "testLanguage": candidate.test_language,
+            "encryptedById": candidate.encrypted_by_id,
+            "createdAt": candidate.created_at,
+            "updatedAt": candidate.updated_at,
+            "applications": applications.iter().map(|a| json!({
+                "id": a.id,
+                "fieldOfStudy": a.field_of_study,
+                "personalIdNumber": a.personal_id_number,
+                "publicKey": a.public_key,
+                "createdAt": a.created_at,
+            })).collect::<Vec<_>>(),
+            "parents": parents.iter().map(|p| json!({
+                "id": p.id,
+                "name": p.name,
+                "surname": p.surname,
+                "telephone": p.telephone,
+                "email": p.email,
+            })).collect::<Vec<_>>(),
+        })
+    }
+
+    /// Deletes all candidates, applications, parents, sessions and portfolios of an archived round.
+    /// Round can be purged only after `retention` has passed since it was closed
+    pub async fn purge(
+        db: &DbConn,
+        round_id: i32,
+        retention: Duration,
+    ) -> Result<admission_round::Model, ServiceError> {
+        let round = Self::find(db, round_id).await?;
+        let Some(closed_at) = round.closed_at else {
+            return Err(ServiceError::AdmissionRoundOpen);
+        };
+        if round.archived_at.is_none() || closed_at + retention > chrono::offset::Local::now().naive_local() {
+            return Err(ServiceError::AdmissionRoundNotPurgeable);
+        }
+
+        warn!("ADMISSION ROUND {} PURGE STARTED", round.id);
+
+        let mut candidates = vec![];
+        for candidate in Query::list_candidates_in_round(db, round.id).await? {
+            let mut applications = vec![];
+            for application in Query::find_applications_by_candidate_id(db, candidate.id).await? {
+                let sessions = Query::find_related_application_sessions(db, &application).await?;
+                applications.push((application, sessions));
+            }
+            let parents = Query::find_candidate_parents(db, &candidate).await?;
+            candidates.push((candidate, applications, parents));
+        }
+
+        // Either the whole round is deleted or nothing, portfolios are removed only once the rows are gone
+        let txn = db.begin().await?;
+        let mut candidate_ids = vec![];
+        for (candidate, applications, parents) in candidates {
+            for (application, sessions) in applications {
+                for session in sessions {
+                    Mutation::delete_session(&txn, session.into_active_model()).await?;
+                }
+                Mutation::delete_application_reviews(&txn, application.id).await?;
+                Mutation::delete_application(&txn, application).await?;
+            }
+            for parent in parents {
+                Mutation::delete_parent(&txn, parent).await?;
+            }
+            Mutation::delete_candidate_notifications(&txn, candidate.id).await?;
+            Mutation::delete_candidate_portfolio_versions(&txn, candidate.id).await?;
+            candidate_ids.push(candidate.id);
+            Mutation::delete_candidate(&txn, candidate).await?;
+        }
+        let round = Mutation::set_admission_round_purged(&txn, round).await?;
+        txn.commit().await?;
+
+        for candidate_id in candidate_ids {
+            PortfolioService::delete_candidate_root(candidate_id).await?;
+        }
+        SearchService::invalidate();
+
+        warn!("ADMISSION ROUND {} PURGE FINISHED", round.id);
+        Ok(round)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Datelike, Duration};
+    use serial_test::serial;
+    use tokio::io::AsyncReadExt;
+
+    use crate::{
+        crypto,
+        error::ServiceError,
+        services::{
+            admin_service::admin_tests::create_admin,
+            candidate_service::tests::put_user_data,
+            portfolio_service::{tests::{audit_context, clear_data_store_temp_dir, create_data_store_temp_dir, put_portfolio_documents}, PortfolioService},
+        },
+        utils::db::get_memory_sqlite_connection,
+        Query,
+    };
+
+    use super::AdmissionRoundService;
+
+    #[tokio::test]
+    async fn test_current_and_open_round() {
+        let db = get_memory_sqlite_connection().await;
+
+        let first = AdmissionRoundService::current(&db).await.unwrap();
+        assert_eq!(first.name, chrono::offset::Local::now().year().to_string());
+        assert_eq!(AdmissionRoundService::current(&db).await.unwrap().id, first.id);
+
+        let second = AdmissionRoundService::open(&db, "next".to_string()).await.unwrap();
+        assert_ne!(second.id, first.id);
+        assert_eq!(AdmissionRoundService::current(&db).await.unwrap().id, second.id);
+
+        let rounds = AdmissionRoundService::list(&db).await.unwrap();
+        assert_eq!(rounds.len(), 2);
+        assert!(rounds[0].closed_at.is_some());
+        assert!(rounds[1].closed_at.is_none());
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_archive_and_purge_round() {
+        let db = get_memory_sqlite_connection().await;
+        let admin = create_admin(&db).await;
+        let (application, candidate, _) = put_user_data(&db).await;
+
+        let (temp_dir, _, _) = create_data_store_temp_dir(candidate.id).await;
+        put_portfolio_documents(&db, candidate.id, 0).await;
+        PortfolioService::submit(&candidate, &db, &audit_context()).await.unwrap();
+
+        let round_id = candidate.admission_round_id;
+        assert!(matches!(
+            AdmissionRoundService::archive(&db, round_id, vec![]).await,
+            Err(ServiceError::AdmissionRoundOpen)
+        ));
+
+        AdmissionRoundService::open(&db, "next".to_string()).await.unwrap();
+        assert!(matches!(
+            AdmissionRoundService::purge(&db, round_id, Duration::zero()).await,
+            Err(ServiceError::AdmissionRoundNotPurgeable)
+        ));
+
+        let mut archive = vec![];
+        let round = AdmissionRoundService::archive(&db, round_id, &mut archive).await.unwrap();
+        assert!(round.archived_at.is_some());
+
+        let admin_private_key = crypto::decrypt_password(admin.private_key, "admin".to_string())
+            .await
+            .unwrap();
+        let mut zip = vec![];
+        crypto::age_decrypt_reader(archive.as_slice(), &admin_private_key)
+            .await
+            .unwrap()
+            .read_to_end(&mut zip)
+            .await
+            .unwrap();
+        let zip = async_zip::base::read::mem::ZipFileReader::new(zip).await.unwrap();
+        let filenames: Vec<&str> = zip.file()
+            .entries()
+            .iter()
+            .map(|e| e.entry().filename().as_str().unwrap())
+            .collect();
+        assert_eq!(filenames, vec!["round.json", "candidates.json", &format!("portfolios/{}.age", candidate.id)]);
+
+        assert!(matches!(
+            AdmissionRoundService::purge(&db, round_id, Duration::days(1)).await,
+            Err(ServiceError::AdmissionRoundNotPurgeable)
+        ));
+        let round = AdmissionRoundService::purge(&db, round_id, Duration::zero()).await.unwrap();
+        assert!(round.purged_at.is_some());
+
+        assert!(Query::find_candidate_by_id(&db, candidate.id).await.unwrap().is_none());
+        assert!(Query::find_application_by_id(&db, application.id).await.unwrap().is_none());
+        assert!(!PortfolioService::is_portfolio_submitted(candidate.id).await);
+
+        clear_data_store_temp_dir(temp_dir).await;
+    }
+}
@@ -4,11 +4,9 @@ use entity::{candidate, parent, application, session};
 use log::warn;
 use sea_orm::{DbConn, prelude::Uuid, IntoActiveModel};
 
-use crate::{error::ServiceError, Query, utils::db::get_recipients, models::candidate_details::EncryptedApplicationDetails, models::{candidate::{ApplicationDetails, CreateCandidateResponse}, candidate_details::{EncryptedString, EncryptedCandidateDetails}, auth::AuthenticableTrait, application::ApplicationResponse}, Mutation, crypto::{hash_password, self}};
+use crate::{error::ServiceError, Query, utils::db::get_recipients, models::candidate_details::EncryptedApplicationDetails, models::{admission_cycle::AdmissionCycle, blind_index::BlindIndexKey, audit::{AuditAction, AuditActor, AuditContext}, candidate::{ApplicationDetails, CreateCandidateResponse}, candidate_details::{EncryptedString, EncryptedCandidateDetails}, auth::{AuthenticableTrait, validate_password_policy}, application::ApplicationResponse, login_attempt::LoginScope, notification::NotificationKind, timeline::CandidateAction}, Mutation, crypto::{hash_password, self}};
 
-use super::{parent_service::ParentService, candidate_service::CandidateService, session_service::SessionService, portfolio_service::{PortfolioService, SubmissionProgress}};
-
-const FIELD_OF_STUDY_PREFIXES: [&str; 3] = ["101", "102", "103"];
+use super::{admission_round_service::AdmissionRoundService, audit_service::AuditService, login_throttle_service::LoginThrottleService, notification_service::NotificationService, parent_service::ParentService, candidate_service::CandidateService, search_service::SearchService, session_service::SessionService, portfolio_service::PortfolioService, timeline_service::TimelineService};
 
 pub struct ApplicationService;
 
@@ -25,7 +23,7 @@ impl ApplicationService {
         plain_text_password: &String,
         personal_id_number: String,
     ) -> Result<(application::Model, Vec<application::Model>, String), ServiceError> {
-        // Check if application id starts with 101, 102 or 103
+        // Check if application id starts with a prefix of one of the programs
         if !Self::is_application_id_valid(application_id) {
             return Err(ServiceError::InvalidApplicationId);
         }
@@ -58,17 +56,18 @@ impl ApplicationService {
         let application = Mutation::create_application(
             db,
             application_id,
-            candidate.id,
+            &candidate,
             hashed_password,
             enc_personal_id_number.to_string(),
             pubkey,
             encrypted_priv_key,
         ).await?;
+        SearchService::invalidate();
 
         let applications = Query::find_applications_by_candidate_id(db, candidate.id).await?;
         if applications.len() >= 3 {
             for application in applications {
-                ApplicationService::delete(db, application).await?;
+                Self::delete_application(db, application).await?;
             }
             return Err(ServiceError::InternalServerError);
         }
@@ -100,32 +99,42 @@ impl ApplicationService {
         pubkey: &String,
         // enc_personal_id_number: &EncryptedString,
     ) -> Result<(candidate::Model, String), ServiceError> {
-        let candidates = Query::list_candidates_full(db).await?;
-        let ids_decrypted = futures::future::join_all(
-        candidates.iter().map(|c| async {(
-                c.id,
-                EncryptedString::from(c.personal_identification_number.clone())
-                    .decrypt(admin_private_key)
-                    .await
-                    .unwrap_or_default(),
-            )}
-        ))
-            .await;
+        // Candidates from past rounds get a new candidate record
+        let round = AdmissionRoundService::current(db).await?;
+        let blind_index_key = BlindIndexKey::from_env()?;
+        let personal_id_blind_index = blind_index_key.as_ref()
+            .map(|key| key.personal_id_number(personal_id_number));
+
+        let found_id = match &personal_id_blind_index {
+            Some(blind_index) => match Query::find_candidate_by_personal_id_blind_index(db, round.id, blind_index).await? {
+                Some(candidate) => Some(candidate.id),
+                // Only candidates which were not backfilled yet have to be decrypted
+                None => {
+                    let candidates = Query::list_candidates_without_personal_id_blind_index(db, round.id).await?;
+                    Self::find_candidate_id_by_decryption(&candidates, admin_private_key, personal_id_number).await
+                },
+            },
+            None => {
+                let candidates = Query::list_candidates_in_round(db, round.id).await?;
+                Self::find_candidate_id_by_decryption(&candidates, admin_private_key, personal_id_number).await
+            },
+        };
+
+        if let Some(candidate_id) = found_id {
+            let (candidate, enc_personal_id_number) = Self::find_linkable_candidate(db, 
+                application_id,
+                candidate_id,
+                pubkey,
+                personal_id_number.to_owned()
+            ).await?;
 
-        let found_ids: Vec<&(i32, String)> = ids_decrypted
-            .iter()
-            .filter(|(_, id)| id == personal_id_number)
-            .collect();
-            
-        if let Some((candidate_id, _)) = found_ids.first() {
-            Ok(
-                Self::find_linkable_candidate(db, 
-                    application_id,
-                    *candidate_id,
-                    pubkey,
-                    personal_id_number.to_owned()
-                ).await?
-            )
+            let candidate = match personal_id_blind_index {
+                Some(blind_index) if candidate.personal_id_blind_index.is_none() => {
+                    Mutation::update_candidate_blind_index(db, candidate, Some(blind_index)).await?
+                },
+                _ => candidate,
+            };
+            Ok((candidate, enc_personal_id_number))
         } else {
             let recipients = get_recipients(db, pubkey).await?;
 
@@ -135,13 +144,37 @@ impl ApplicationService {
             ).await?;
             Ok(
                 (
-                    CandidateService::create(db, enc_personal_id_number.to_owned().to_string()).await?,
+                    CandidateService::create(db, enc_personal_id_number.to_owned().to_string(), personal_id_blind_index).await?,
                     enc_personal_id_number.to_string(),
                 )
             )
         }
     }
 
+    async fn find_candidate_id_by_decryption(
+        candidates: &[candidate::Model],
+        admin_private_key: &String,
+        personal_id_number: &str,
+    ) -> Option<i32> {
+        let ids_decrypted = futures::future::join_all(
+            candidates.iter().map(|c| async {(
+                c.id,
+                EncryptedString::from(c.personal_identification_number.clone())
+                    .decrypt(admin_private_key)
+                    .await
+                    .unwrap_or_default(),
+            )})
+        )
+            .await;
+
+        // Same normalization as the blind index, so both lookups match the same numbers
+        let personal_id_number = BlindIndexKey::normalize_personal_id_number(personal_id_number);
+        ids_decrypted
+            .into_iter()
+            .find(|(_, id)| BlindIndexKey::normalize_personal_id_number(id) == personal_id_number)
+            .map(|(candidate_id, _)| candidate_id)
+    }
+
     async fn find_linkable_candidate(
         db: &DbConn,
         new_application_id: i32,
@@ -161,7 +194,7 @@ impl ApplicationService {
 
         let linked_application = linked_applications.first().ok_or(ServiceError::CandidateNotFound)?;//TODO
 
-        if linked_application.id.to_string()[0..3] == new_application_id.to_string()[0..3] {
+        if Self::is_same_program(linked_application.id, new_application_id) {
             return Err(ServiceError::TooManyFieldsForOnePerson);
         }
 
@@ -181,18 +214,31 @@ impl ApplicationService {
         )
     }
 
-    pub async fn delete(db: &DbConn, application: application::Model) -> Result<(), ServiceError> {
+    pub async fn delete(
+        db: &DbConn,
+        application: application::Model,
+        audit: &AuditContext,
+    ) -> Result<(), ServiceError> {
+        let application_id = application.id;
+        Self::delete_application(db, application).await?;
+
+        AuditService::log(db, audit, AuditAction::Delete, Some(application_id)).await
+    }
+
+    async fn delete_application(db: &DbConn, application: application::Model) -> Result<(), ServiceError> {
         let candidate = ApplicationService::find_related_candidate(db, &application).await?;
         
         let applications = Query::find_applications_by_candidate_id(db, candidate.id).await?;
         if applications.len() <= 1 &&
             (EncryptedCandidateDetails::from(&candidate).is_filled() ||
-            PortfolioService::get_submission_progress(candidate.id).await?.index() > 1) {
+            PortfolioService::get_submission_progress(db, candidate.id).await?.index() > 1) {
             warn!("FAILED TO DELETE APPLICATION {} (CANDIDATE {}) - LOCKED", application.id, candidate.id);
             return Err(ServiceError::Forbidden);
         }
 
+        Mutation::delete_application_reviews(db, application.id).await?;
         Mutation::delete_application(db, application).await?;
+        SearchService::invalidate();
 
         let remaining_applications = Query::find_applications_by_candidate_id(db, candidate.id).await?;
         if remaining_applications.is_empty() {
@@ -202,14 +248,20 @@ impl ApplicationService {
         Ok(())
     }
 
-    fn is_application_id_valid(application_id: i32) -> bool {
-        let s = &application_id.to_string();
-        if s.len() <= 3 {
-            // TODO: does the field of study prefix have to be exactly 6 digits? VYRESIT PODLE PRIHLASEK!!!
-            return false;
+    /// One person can apply to every program only once
+    pub(in crate::services) fn is_same_program(application_id: i32, other_application_id: i32) -> bool {
+        let cycle = AdmissionCycle::get();
+        match (cycle.program_by_application_id(application_id), cycle.program_by_application_id(other_application_id)) {
+            (Some(program), Some(other)) => program.code == other.code,
+            _ => false,
         }
-        let field_of_study_prefix = &s[0..3];
-        FIELD_OF_STUDY_PREFIXES.contains(&field_of_study_prefix)
+    }
+
+    pub(in crate::services) fn is_application_id_valid(application_id: i32) -> bool {
+        // TODO: does the field of study prefix have to be exactly 6 digits? VYRESIT PODLE PRIHLASEK!!!
+        AdmissionCycle::get()
+            .program_by_application_id(application_id)
+            .is_some()
     }
 
     pub async fn find_related_candidate(
@@ -230,6 +282,7 @@ impl ApplicationService {
         candidate: candidate::Model,
         form: &ApplicationDetails,
     ) -> Result<(candidate::Model, Vec<parent::Model>), ServiceError> {
+        TimelineService::ensure_allowed(db, candidate.id, CandidateAction::EditDetails).await?;
         let mut recipients = Query::get_all_admin_public_keys(db).await?;
         let applications = Query::find_applications_by_candidate_id(db, candidate.id).await?;
         recipients.append(&mut applications.iter().map(|a| a.public_key.to_owned()).collect());
@@ -237,6 +290,7 @@ impl ApplicationService {
 
         let candidate = CandidateService::add_candidate_details(db, candidate, &form.candidate, &recipients, application.id).await?;
         let parents = ParentService::add_parents_details(db, &candidate, &form.parents, &recipients).await?;
+        NotificationService::enqueue(db, candidate.id, NotificationKind::DetailsSubmitted).await;
         Ok(
             (
                 candidate,
@@ -249,27 +303,32 @@ impl ApplicationService {
         private_key: String,
         db: &DbConn,
         application: &application::Model,
+        audit: &AuditContext,
     ) -> Result<ApplicationDetails, ServiceError>  {
         let candidate = ApplicationService::find_related_candidate(db, application).await?;
 
         let parents = Query::find_candidate_parents(db, &candidate).await?;
         let enc_details = EncryptedApplicationDetails::from((&candidate, &parents));
 
-        if enc_details.is_filled() {
-            enc_details.decrypt(private_key).await
-        } else {
-            Err(ServiceError::Forbidden)
+        if !enc_details.is_filled() {
+            return Err(ServiceError::Forbidden);
         }
+
+        let details = enc_details.decrypt(private_key).await?;
+        AuditService::log(db, audit, AuditAction::ViewDetails, Some(application.id)).await?;
+
+        Ok(details)
     }
 
     pub async fn list_applications(
         private_key: &String,
         db: &DbConn,
         field_of_study: Option<String>,
+        admission_round_id: Option<i32>,
         page: Option<u64>,
         sort: Option<String>,
     ) -> Result<Vec<ApplicationResponse>, ServiceError> {
-        let applications = Query::list_applications(db, field_of_study, page, sort).await?;
+        let applications = Query::list_applications(db, field_of_study, admission_round_id, page, sort).await?;
 
         futures::future::try_join_all(
             applications
@@ -286,6 +345,42 @@ impl ApplicationService {
         ).await
     }
 
+    /// Encrypts the application's private key with a new password and logs out all other sessions.
+    /// Returns a new session id for the current client
+    pub async fn change_password(
+        db: &DbConn,
+        application: application::Model,
+        old_password: String,
+        new_password: String,
+        audit: &AuditContext,
+    ) -> Result<String, ServiceError> {
+        if !crypto::verify_password(old_password.clone(), application.password.clone()).await? {
+            return Err(ServiceError::InvalidCredentials);
+        }
+        validate_password_policy(&new_password)?;
+        if old_password == new_password {
+            return Err(ServiceError::WeakPassword);
+        }
+
+        let private_key = crypto::decrypt_password(application.private_key.clone(), old_password).await?;
+        let encrypted_priv_key = crypto::encrypt_password(private_key, new_password.clone()).await?;
+        let new_password_hash = crypto::hash_password(new_password.clone()).await?;
+
+        let application = Mutation::update_application_password(db,
+            application,
+            new_password_hash,
+            encrypted_priv_key
+        ).await?;
+
+        Self::delete_old_sessions(db, &application, 0).await?;
+        let ip_addr = audit.ip_address.to_owned().unwrap_or_default();
+        let session_id = Self::new_session(db, &application, new_password, ip_addr).await?;
+
+        AuditService::log(db, audit, AuditAction::ChangePassword, Some(application.id)).await?;
+
+        Ok(session_id)
+    }
+
     async fn decrypt_private_key(
         application: application::Model,
         password: String,
@@ -312,6 +407,7 @@ impl ApplicationService {
         admin_private_key: String,
         db: &DbConn,
         id: i32,
+        audit: &AuditContext,
     ) -> Result<CreateCandidateResponse, ServiceError> {
         let application = Query::find_application_by_id(db, id).await?
             .ok_or(ServiceError::CandidateNotFound)?;
@@ -353,13 +449,17 @@ impl ApplicationService {
              &admin_private_key
         ).await?;
 
-        if PortfolioService::get_submission_progress(candidate.id).await? == SubmissionProgress::Submitted {
-            PortfolioService::reencrypt_portfolio(
-                candidate.id,
-                admin_private_key,
-                &recipients
-            ).await?;
-        }
+        // Withdrawn versions are re-encrypted too, so that the candidate doesn't lose access to them
+        PortfolioService::reencrypt_portfolio(
+            db,
+            candidate.id,
+            admin_private_key.clone(),
+            &recipients
+        ).await?;
+
+        AuditService::log(db, audit, AuditAction::ResetPassword, Some(id)).await?;
+        NotificationService::enqueue(db, candidate.id, NotificationKind::PasswordReset).await;
+        NotificationService::flush(db, &admin_private_key, candidate.id).await;
 
         Ok(
             CreateCandidateResponse {
@@ -417,13 +517,26 @@ impl AuthenticableTrait for ApplicationService {
         password: String,
         ip_addr: String,
     ) -> Result<(String, String), ServiceError> {
-        let application = Query::find_application_by_id(db, application_id)
-            .await?
-            .ok_or(ServiceError::CandidateNotFound)?;
-
-        let session_id = Self::new_session(db, &application, password.clone(), ip_addr).await?;
+        let account = (LoginScope::Application, application_id.to_string());
+        let (application, session_id) = LoginThrottleService::throttle(db, account, &ip_addr, async {
+            let application = Query::find_application_by_id(db, application_id)
+                .await?
+                .ok_or(ServiceError::CandidateNotFound)?;
+
+            let session_id = Self::new_session(db, &application, password.clone(), ip_addr.to_owned()).await?;
+            Ok((application, session_id))
+        }).await?;
+
+        let audit = AuditContext::new(AuditActor::Application(application.id), Some(ip_addr));
+        AuditService::log(db, &audit, AuditAction::Login, None).await?;
+
+        let legacy = crypto::is_legacy_encrypted_password(&application.private_key);
+        let private_key = Self::decrypt_private_key(application.clone(), password.clone()).await?;
+        if legacy {
+            let encrypted_priv_key = crypto::encrypt_password(private_key.clone(), password).await?;
+            Mutation::update_application_private_key(db, application, encrypted_priv_key).await?;
+        }
 
-        let private_key = Self::decrypt_private_key(application, password).await?;
         Ok((session_id, private_key))
     }
 
@@ -488,7 +601,9 @@ impl AuthenticableTrait for ApplicationService {
 
 #[cfg(test)]
 mod application_tests {
-    use crate::{services::{application_service::ApplicationService, candidate_service::tests::put_user_data}, utils::db::get_memory_sqlite_connection, crypto, models::auth::AuthenticableTrait};
+    use serial_test::serial;
+
+    use crate::{services::{application_service::ApplicationService, candidate_service::{CandidateService, tests::put_user_data}}, utils::db::get_memory_sqlite_connection, crypto, error::ServiceError, models::{audit::{AuditActor, AuditContext}, auth::AuthenticableTrait}, Mutation, Query};
     use crate::services::admin_service::admin_tests::create_admin;
 
     #[tokio::test]
@@ -514,7 +629,8 @@ mod application_tests {
             ApplicationService::login(&db, application.id, "test".to_string(), "127.0.0.1".to_string()).await.is_ok()
         );
 
-        let new_password = ApplicationService::reset_password(private_key, &db, application.id).await.unwrap().password;
+        let audit = AuditContext::new(AuditActor::Admin(admin.id), None);
+        let new_password = ApplicationService::reset_password(private_key, &db, application.id, &audit).await.unwrap().password;
 
         assert!(
             ApplicationService::login(&db, application.id, "test".to_string(), "127.0.0.1".to_string()).await.is_err()
@@ -525,6 +641,79 @@ mod application_tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_change_password() {
+        let db = get_memory_sqlite_connection().await;
+        let (application, _, _) = put_user_data(&db).await;
+        let (_, private_key) = ApplicationService::login(&db, application.id, "test".to_string(), "127.0.0.1".to_string()).await.unwrap();
+        let audit = AuditContext::new(AuditActor::Application(application.id), Some("127.0.0.1".to_string()));
+
+        assert!(matches!(
+            ApplicationService::change_password(&db, application.clone(), "wrong".to_string(), "newpassword123".to_string(), &audit).await,
+            Err(ServiceError::InvalidCredentials)
+        ));
+        assert!(matches!(
+            ApplicationService::change_password(&db, application.clone(), "test".to_string(), "weak".to_string(), &audit).await,
+            Err(ServiceError::WeakPassword)
+        ));
+
+        let session_id = ApplicationService::change_password(&db, application.clone(), "test".to_string(), "newpassword123".to_string(), &audit).await.unwrap();
+
+        let sessions = Query::find_related_application_sessions(&db, &application).await.unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].id.to_string(), session_id);
+
+        assert!(
+            ApplicationService::login(&db, application.id, "test".to_string(), "127.0.0.1".to_string()).await.is_err()
+        );
+        let (_, new_private_key) = ApplicationService::login(&db, application.id, "newpassword123".to_string(), "127.0.0.1".to_string()).await.unwrap();
+        assert_eq!(private_key, new_private_key);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_blind_index_lookup_and_backfill() {
+        // base64 of 32 zero bytes
+        std::env::set_var("PORTFOLIO_BLIND_INDEX_KEY", "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=");
+        let db = get_memory_sqlite_connection().await;
+        let admin = create_admin(&db).await;
+        let private_key = crypto::decrypt_password(admin.private_key, "admin".to_string()).await.unwrap();
+        let (_, candidate, _) = put_user_data(&db).await;
+        assert!(candidate.personal_id_blind_index.is_some());
+
+        // Linked by the blind index, nothing has to be decrypted with the admin key
+        let (_, _, _) = ApplicationService::create(&"".to_string(), &db, 101151, &"test".to_string(), "0000001111".to_string())
+            .await
+            .unwrap();
+        let applications = Query::find_applications_by_candidate_id(&db, candidate.id).await.unwrap();
+        assert_eq!(applications.len(), 2);
+
+        let candidate = Query::find_candidate_by_id(&db, candidate.id).await.unwrap().unwrap();
+        let blind_index = candidate.personal_id_blind_index.clone();
+        Mutation::update_candidate_blind_index(&db, candidate.clone(), None).await.unwrap();
+
+        assert_eq!(CandidateService::backfill_blind_indexes(&db, &private_key).await.unwrap(), 1);
+        let candidate = Query::find_candidate_by_id(&db, candidate.id).await.unwrap().unwrap();
+        assert_eq!(candidate.personal_id_blind_index, blind_index);
+
+        std::env::remove_var("PORTFOLIO_BLIND_INDEX_KEY");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_link_by_decryption_ignores_separators() {
+        let db = get_memory_sqlite_connection().await;
+        let admin = create_admin(&db).await;
+        let private_key = crypto::decrypt_password(admin.private_key, "admin".to_string()).await.unwrap();
+        let (_, candidate, _) = put_user_data(&db).await;
+
+        ApplicationService::create(&private_key, &db, 101151, &"test".to_string(), "000000/1111".to_string())
+            .await
+            .unwrap();
+        let applications = Query::find_applications_by_candidate_id(&db, candidate.id).await.unwrap();
+        assert_eq!(applications.len(), 2);
+    }
+
     #[tokio::test]
     async fn test_encrypt_decrypt_private_key_with_passphrase() {
         let db = get_memory_sqlite_connection().await;
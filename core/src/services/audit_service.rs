@@ -0,0 +1,82 @@
+use sea_orm::DbConn;
+
+use crate::{
+    error::ServiceError,
+    models::audit::{AuditAction, AuditContext, AuditLogFilter, AuditLogResponse},
+    Mutation, Query,
+};
+
+pub struct AuditService;
+
+impl AuditService {
+    /// Records an action into the audit log, `target_id` is the application the action was performed on
+    pub async fn log(
+        db: &DbConn,
+        context: &AuditContext,
+        action: AuditAction,
+        target_id: Option<i32>,
+    ) -> Result<(), ServiceError> {
+        Mutation::insert_audit_log(db,
+            context.actor.actor_type().to_string(),
+            context.actor.id(),
+            action.into(),
+            target_id,
+            context.ip_address.to_owned(),
+        ).await?;
+
+        Ok(())
+    }
+
+    pub async fn list(
+        db: &DbConn,
+        filter: AuditLogFilter,
+        page: Option<u64>,
+    ) -> Result<Vec<AuditLogResponse>, ServiceError> {
+        filter.validate()?;
+
+        let logs = Query::list_audit_logs(db, &filter, page).await?;
+
+        Ok(logs.into_iter().map(AuditLogResponse::from).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        models::audit::{AuditAction, AuditActor, AuditContext, AuditLogFilter},
+        utils::db::get_memory_sqlite_connection,
+    };
+
+    use super::AuditService;
+
+    #[tokio::test]
+    async fn test_log_and_filter() {
+        let db = get_memory_sqlite_connection().await;
+        let admin = AuditContext::new(AuditActor::Admin(1), Some("10.0.0.1".to_string()));
+        let application = AuditContext::new(AuditActor::Application(103151), None);
+
+        AuditService::log(&db, &admin, AuditAction::ViewDetails, Some(103151)).await.unwrap();
+        AuditService::log(&db, &admin, AuditAction::ResetPassword, Some(102151)).await.unwrap();
+        AuditService::log(&db, &application, AuditAction::Submit, Some(103151)).await.unwrap();
+
+        let all = AuditService::list(&db, AuditLogFilter::default(), None).await.unwrap();
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[0].action, "submit");
+
+        let filter = AuditLogFilter {
+            actor_type: Some("admin".to_string()),
+            target_id: Some(103151),
+            ..Default::default()
+        };
+        let logs = AuditService::list(&db, filter, None).await.unwrap();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].action, "view_details");
+        assert_eq!(logs[0].ip_address, Some("10.0.0.1".to_string()));
+
+        let filter = AuditLogFilter {
+            action: Some("sudo".to_string()),
+            ..Default::default()
+        };
+        assert!(AuditService::list(&db, filter, None).await.is_err());
+    }
+}
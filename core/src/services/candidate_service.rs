@@ -1,13 +1,14 @@
 use entity::candidate;
+use log::info;
 use sea_orm::DbConn;
 
 use crate::{
-    models::{candidate_details::EncryptedCandidateDetails, candidate::CandidateDetails},
+    models::{blind_index::BlindIndexKey, candidate_details::{EncryptedCandidateDetails, EncryptedString}, candidate::CandidateDetails},
     error::ServiceError,
-    Mutation,
+    Mutation, Query,
 };
 
-use super::{portfolio_service::PortfolioService};
+use super::{admission_round_service::AdmissionRoundService, portfolio_service::PortfolioService, search_service::SearchService};
 
 pub struct CandidateService;
 
@@ -20,10 +21,14 @@ impl CandidateService {
     pub(in crate::services) async fn create(
         db: &DbConn,
         enc_personal_id_number: String,
+        personal_id_blind_index: Option<String>,
     ) -> Result<candidate::Model, ServiceError> {
+        let round = AdmissionRoundService::current(db).await?;
         let candidate = Mutation::create_candidate(
             db,
             enc_personal_id_number,
+            personal_id_blind_index,
+            round.id,
         )
             .await?;
         
@@ -36,7 +41,10 @@ impl CandidateService {
     pub async fn delete_candidate(db: &DbConn, candidate: candidate::Model) -> Result<(), ServiceError> {
         PortfolioService::delete_candidate_root(candidate.id).await?;
 
+        Mutation::delete_candidate_notifications(db, candidate.id).await?;
+        Mutation::delete_candidate_portfolio_versions(db, candidate.id).await?;
         Mutation::delete_candidate(db, candidate).await?;
+        SearchService::invalidate();
         Ok(())
     }
 
@@ -54,8 +62,27 @@ impl CandidateService {
             enc_details,
             encrypted_by
         ).await?;
+        SearchService::invalidate();
         Ok(model)
     }
+
+    /// Fills blind indexes of candidates created before they were introduced, returns number of updated candidates
+    pub async fn backfill_blind_indexes(db: &DbConn, admin_private_key: &String) -> Result<usize, ServiceError> {
+        let key = BlindIndexKey::from_env()?
+            .ok_or(ServiceError::BlindIndexConfigError("PORTFOLIO_BLIND_INDEX_KEY is not set".to_string()))?;
+
+        let candidates = Query::list_candidates_without_blind_index(db).await?;
+        let count = candidates.len();
+        for candidate in candidates {
+            let personal_id_number = EncryptedString::from(candidate.personal_identification_number.to_owned())
+                .decrypt(admin_private_key)
+                .await?;
+            Mutation::update_candidate_blind_index(db, candidate, Some(key.personal_id_number(&personal_id_number))).await?;
+        }
+
+        info!("BLIND INDEX BACKFILLED FOR {} CANDIDATES", count);
+        Ok(count)
+    }
 }
 
 #[cfg(test)]
@@ -79,12 +106,12 @@ pub mod tests {
         let db = get_memory_sqlite_connection().await;
         let admin = create_admin(&db).await;
         let private_key = crypto::decrypt_password(admin.private_key, "admin".to_string()).await.unwrap();
-        let candidates = ApplicationService::list_applications(&private_key, &db, None, None, None).await.unwrap();
+        let candidates = ApplicationService::list_applications(&private_key, &db, None, None, None, None).await.unwrap();
         assert_eq!(candidates.len(), 0);
 
         put_user_data(&db).await;
 
-        let candidates = ApplicationService::list_applications(&private_key, &db, None, None, None).await.unwrap();
+        let candidates = ApplicationService::list_applications(&private_key, &db, None, None, None, None).await.unwrap();
         assert_eq!(candidates.len(), 1);
     }
 
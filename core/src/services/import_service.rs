@@ -0,0 +1,256 @@
+use std::collections::HashMap;
+
+use log::info;
+use sea_orm::DbConn;
+
+use crate::{crypto, error::ServiceError, models::{admission_cycle::AdmissionCycle, blind_index::BlindIndexKey, import::{ImportRow, ImportRowResult, ImportStatus}}, Query};
+
+use super::{admission_round_service::AdmissionRoundService, application_service::ApplicationService};
+
+pub struct ImportService;
+
+impl ImportService {
+    /// Creates an application with a generated password for every row of the CSV file.
+    /// Rows are imported independently, a failed row is reported and the import continues.
+    /// In dry run nothing is written, rows are only validated against the database and the rows before them
+    pub async fn import_csv(
+        db: &DbConn,
+        admin_private_key: &String,
+        csv: &[u8],
+        dry_run: bool,
+    ) -> Result<Vec<ImportRowResult>, ServiceError> {
+        let mut results = vec![];
+        // Applications of the same person imported earlier in this file, used only in dry run
+        let mut imported: HashMap<String, Vec<i32>> = HashMap::new();
+
+        for (line, row) in Self::parse(csv) {
+            let row = match row {
+                Ok(row) => row,
+                Err(e) => {
+                    results.push(ImportRowResult::error(line, None, e));
+                    continue;
+                },
+            };
+
+            let result = if dry_run {
+                // Database errors are reported with the row, like errors of a real import
+                Self::validate_row(db, line, &row, &imported)
+                    .await
+                    .unwrap_or_else(|e| ImportRowResult::error(line, Some(&row), e.to_string()))
+            } else {
+                Self::import_row(db, admin_private_key, line, &row).await
+            };
+
+            if result.status != ImportStatus::Error {
+                imported.entry(row.personal_id_number.trim().to_string())
+                    .or_default()
+                    .push(row.application_id);
+            }
+            results.push(result);
+        }
+
+        info!(
+            "IMPORT{} FINISHED: {} ROWS, {} FAILED",
+            if dry_run { " DRY RUN" } else { "" },
+            results.len(),
+            results.iter().filter(|r| r.status == ImportStatus::Error).count(),
+        );
+        Ok(results)
+    }
+
+    /// Output file with generated credentials
+    pub fn results_to_csv(results: &[ImportRowResult]) -> Result<Vec<u8>, ServiceError> {
+        let mut wtr = csv::Writer::from_writer(vec![]);
+        for result in results {
+            wtr.serialize(result)?;
+        }
+
+        wtr.into_inner()
+            .map_err(|_| ServiceError::CsvIntoInnerError)
+    }
+
+    /// Spreadsheets exported by Czech Excel are separated by semicolons
+    fn parse(csv: &[u8]) -> Vec<(usize, Result<ImportRow, String>)> {
+        let header = csv.split(|b| *b == b'\n').next().unwrap_or_default();
+        let delimiter = if header.contains(&b';') && !header.contains(&b',') { b';' } else { b',' };
+
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(delimiter)
+            .trim(csv::Trim::All)
+            .from_reader(csv);
+
+        let headers = match reader.headers() {
+            Ok(headers) => headers.clone(),
+            Err(e) => return vec![(1, Err(e.to_string()))],
+        };
+
+        reader.records()
+            .enumerate()
+            .map(|(i, record)| {
+                // Header is the first line
+                let line = |position: Option<&csv::Position>| position.map(|p| p.line() as usize).unwrap_or(i + 2);
+                match record {
+                    Ok(record) => (
+                        line(record.position()),
+                        record.deserialize::<ImportRow>(Some(&headers)).map_err(|e| e.to_string()),
+                    ),
+                    Err(e) => (line(e.position()), Err(e.to_string())),
+                }
+            })
+            .collect()
+    }
+
+    async fn import_row(
+        db: &DbConn,
+        admin_private_key: &String,
+        line: usize,
+        row: &ImportRow,
+    ) -> ImportRowResult {
+        let password = crypto::random_12_char_string();
+
+        match ApplicationService::create(
+            admin_private_key,
+            db,
+            row.application_id,
+            &password,
+            row.personal_id_number.trim().to_string(),
+        ).await {
+            Ok((application, applications, _)) => ImportRowResult {
+                line,
+                application_id: Some(application.id),
+                personal_id_number: Some(row.personal_id_number.to_owned()),
+                status: if applications.len() > 1 { ImportStatus::Linked } else { ImportStatus::Created },
+                field_of_study: Some(application.field_of_study),
+                password: Some(password),
+                error: None,
+            },
+            Err(e) => ImportRowResult::error(line, Some(row), e.to_string()),
+        }
+    }
+
+    /// Runs the same checks as `ApplicationService::create`. Existing candidates are found only
+    /// when blind index is configured, otherwise only rows of the imported file are checked for linking
+    async fn validate_row(
+        db: &DbConn,
+        line: usize,
+        row: &ImportRow,
+        imported: &HashMap<String, Vec<i32>>,
+    ) -> Result<ImportRowResult, ServiceError> {
+        let Some(program) = AdmissionCycle::get().program_by_application_id(row.application_id) else {
+            return Ok(ImportRowResult::error(line, Some(row), ServiceError::InvalidApplicationId.to_string()));
+        };
+        if row.personal_id_number.trim().is_empty() {
+            return Ok(ImportRowResult::error(line, Some(row), ServiceError::MissingDetails.to_string()));
+        }
+        if imported.values().flatten().any(|id| *id == row.application_id) ||
+            Query::find_application_by_id(db, row.application_id).await?.is_some() {
+            return Ok(ImportRowResult::error(line, Some(row), ServiceError::UserAlreadyExists.to_string()));
+        }
+
+        let mut linked = imported.get(row.personal_id_number.trim()).cloned().unwrap_or_default();
+        if let Some(key) = BlindIndexKey::from_env()? {
+            let round = AdmissionRoundService::current(db).await?;
+            let blind_index = key.personal_id_number(&row.personal_id_number);
+            if let Some(candidate) = Query::find_candidate_by_personal_id_blind_index(db, round.id, &blind_index).await? {
+                linked.extend(
+                    Query::find_applications_by_candidate_id(db, candidate.id)
+                        .await?
+                        .iter()
+                        .map(|a| a.id)
+                );
+            }
+        }
+
+        if linked.len() > 1 {
+            return Ok(ImportRowResult::error(line, Some(row), ServiceError::TooManyApplications.to_string()));
+        }
+        if linked.iter().any(|id| ApplicationService::is_same_program(*id, row.application_id)) {
+            return Ok(ImportRowResult::error(line, Some(row), ServiceError::TooManyFieldsForOnePerson.to_string()));
+        }
+
+        Ok(
+            ImportRowResult {
+                line,
+                application_id: Some(row.application_id),
+                personal_id_number: Some(row.personal_id_number.to_owned()),
+                status: ImportStatus::Valid,
+                field_of_study: Some(program.code.to_owned()),
+                password: None,
+                error: None,
+            }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+
+    use crate::{crypto, models::import::ImportStatus, services::admin_service::admin_tests::create_admin, utils::db::get_memory_sqlite_connection, Query};
+
+    use super::ImportService;
+
+    const CSV: &str = "Ev. č. přihlášky;Rodné číslo
+101101;0101011234
+102101;0101011234
+103101;0101011234
+9;0202021234
+101101;0303031234
+abc;0404041234
+";
+
+    #[tokio::test]
+    #[serial]
+    async fn test_import_csv() {
+        let db = get_memory_sqlite_connection().await;
+        let admin = create_admin(&db).await;
+        let private_key = crypto::decrypt_password(admin.private_key, "admin".to_string()).await.unwrap();
+
+        let results = ImportService::import_csv(&db, &private_key, CSV.as_bytes(), true).await.unwrap();
+        let statuses: Vec<ImportStatus> = results.iter().map(|r| r.status).collect();
+        assert_eq!(statuses, vec![
+            ImportStatus::Valid,
+            ImportStatus::Valid,
+            ImportStatus::Error,
+            ImportStatus::Error,
+            ImportStatus::Error,
+            ImportStatus::Error,
+        ]);
+        assert_eq!(results.iter().map(|r| r.line).collect::<Vec<_>>(), vec![2, 3, 4, 5, 6, 7]);
+        assert!(Query::find_application_by_id(&db, 101101).await.unwrap().is_none());
+
+        let results = ImportService::import_csv(&db, &private_key, CSV.as_bytes(), false).await.unwrap();
+        let statuses: Vec<ImportStatus> = results.iter().map(|r| r.status).collect();
+        assert_eq!(statuses, vec![
+            ImportStatus::Created,
+            ImportStatus::Linked,
+            ImportStatus::Error,
+            ImportStatus::Error,
+            ImportStatus::Error,
+            ImportStatus::Error,
+        ]);
+        assert!(results[0].password.is_some());
+        assert!(results[2].password.is_none());
+        assert!(Query::find_application_by_id(&db, 102101).await.unwrap().is_some());
+
+        let csv = String::from_utf8(ImportService::results_to_csv(&results).unwrap()).unwrap();
+        assert_eq!(csv.lines().count(), results.len() + 1);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_import_dry_run_errors_per_row() {
+        let db = get_memory_sqlite_connection().await;
+        let admin = create_admin(&db).await;
+        let private_key = crypto::decrypt_password(admin.private_key, "admin".to_string()).await.unwrap();
+
+        std::env::set_var("PORTFOLIO_BLIND_INDEX_KEY", "not base64");
+        let results = ImportService::import_csv(&db, &private_key, CSV.as_bytes(), true).await;
+        std::env::remove_var("PORTFOLIO_BLIND_INDEX_KEY");
+
+        let results = results.unwrap();
+        assert_eq!(results.len(), 6);
+        assert_eq!(results[0].status, ImportStatus::Error);
+        assert!(results[0].error.as_ref().unwrap().contains("blind index"));
+    }
+}
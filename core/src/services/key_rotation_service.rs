@@ -0,0 +1,319 @@
+use entity::{admin_key_rotation, application, candidate, parent};
+use log::{info, warn};
+use sea_orm::{ConnectionTrait, DbConn, TransactionTrait};
+
+use crate::{
+    crypto,
+    error::ServiceError,
+    models::candidate_details::{EncryptedCandidateDetails, EncryptedParentDetails, EncryptedString},
+    Mutation, Query,
+};
+
+use super::portfolio_service::PortfolioService;
+
+pub struct KeyRotationService;
+
+impl KeyRotationService {
+    /// Generates a new age identity for the admin and re-encrypts all candidate data to it.
+    /// Progress is saved after every candidate, so an interrupted rotation continues
+    /// where it stopped when started again with the same password.
+    /// Returns the new public key
+    pub async fn rotate_admin_key(
+        db: &DbConn,
+        admin_id: i32,
+        password: String,
+    ) -> Result<String, ServiceError> {
+        let admin = Query::find_admin_by_id(db, admin_id)
+            .await?
+            .ok_or(ServiceError::InvalidCredentials)?;
+        let old_private_key = crypto::decrypt_password(admin.private_key.to_owned(), password.to_owned())
+            .await
+            .map_err(|_| ServiceError::InvalidCredentials)?;
+
+        let rotation = Self::find_or_create_rotation(db, admin_id, &password).await?;
+        let new_private_key = crypto::decrypt_password(rotation.private_key.to_owned(), password).await?;
+        // Data is either still encrypted to the old key or was already re-encrypted
+        let private_keys = vec![old_private_key, new_private_key];
+
+        // Public keys of pending rotations (including this one) are already in the list
+        let admin_public_keys: Vec<String> = Query::get_all_admin_public_keys(db)
+            .await?
+            .into_iter()
+            .filter(|public_key| *public_key != admin.public_key)
+            .collect();
+
+        let mut rotation = rotation;
+        let candidates = Query::list_candidates_after_id(db, rotation.last_candidate_id).await?;
+        for candidate in candidates {
+            let candidate_id = candidate.id;
+            let parents = Query::find_candidate_parents(db, &candidate).await?;
+            let applications = Query::find_applications_by_candidate_id(db, candidate_id).await?;
+
+            let txn = db.begin().await?;
+            Self::reencrypt_candidate(&txn,
+                candidate,
+                parents,
+                applications,
+                &private_keys,
+                &admin_public_keys,
+            ).await?;
+            rotation = Mutation::update_admin_key_rotation_progress(&txn, rotation, candidate_id).await?;
+            txn.commit().await?;
+        }
+
+        let new_public_key = rotation.public_key.to_owned();
+        let txn = db.begin().await?;
+        Mutation::update_admin_keys(&txn,
+            admin,
+            rotation.public_key.to_owned(),
+            rotation.private_key.to_owned(),
+        ).await?;
+        Mutation::delete_admin_key_rotation(&txn, rotation).await?;
+        // Sessions hold the old private key
+        Mutation::delete_admin_sessions(&txn, admin_id).await?;
+        txn.commit().await?;
+
+        info!("ADMIN {} KEY ROTATION FINISHED", admin_id);
+        Ok(new_public_key)
+    }
+
+    /// Re-encrypts data of all candidates to `admin_public_keys`, one transaction per candidate
+    pub async fn reencrypt_all_candidates(
+        db: &DbConn,
+        private_keys: &[String],
+        admin_public_keys: &[String],
+    ) -> Result<(), ServiceError> {
+        for candidate in Query::list_candidates_full(db).await? {
+            let parents = Query::find_candidate_parents(db, &candidate).await?;
+            let applications = Query::find_applications_by_candidate_id(db, candidate.id).await?;
+
+            let txn = db.begin().await?;
+            Self::reencrypt_candidate(&txn,
+                candidate,
+                parents,
+                applications,
+                private_keys,
+                admin_public_keys,
+            ).await?;
+            txn.commit().await?;
+        }
+
+        Ok(())
+    }
+
+    async fn find_or_create_rotation(
+        db: &DbConn,
+        admin_id: i32,
+        password: &str,
+    ) -> Result<admin_key_rotation::Model, ServiceError> {
+        if let Some(rotation) = Query::find_admin_key_rotation(db, admin_id).await? {
+            info!("ADMIN {} KEY ROTATION RESUMED AFTER CANDIDATE {}", admin_id, rotation.last_candidate_id);
+            return Ok(rotation);
+        }
+
+        let (pubkey, priv_key) = crypto::create_identity();
+        let encrypted_priv_key = crypto::encrypt_password(priv_key, password.to_string()).await?;
+
+        Ok(Mutation::create_admin_key_rotation(db, admin_id, pubkey, encrypted_priv_key).await?)
+    }
+
+    /// Re-encrypts candidate details, parents, personal id numbers of applications
+    /// and all stored portfolio versions to `admin_public_keys` and the candidate's own keys.
+    /// Every value is decrypted with the first of `private_keys` that fits.
+    /// Database rows are written through `db`, so the caller may run it in a transaction
+    pub async fn reencrypt_candidate<C: ConnectionTrait>(
+        db: &C,
+        candidate: candidate::Model,
+        parents: Vec<parent::Model>,
+        applications: Vec<application::Model>,
+        private_keys: &[String],
+        admin_public_keys: &[String],
+    ) -> Result<candidate::Model, ServiceError> {
+        let candidate_id = candidate.id;
+        let mut recipients = admin_public_keys.to_vec();
+        recipients.append(&mut applications.iter().map(|a| a.public_key.to_owned()).collect());
+
+        Self::reencrypt_portfolio(db, candidate_id, private_keys, &recipients).await?;
+
+        let enc_candidate = EncryptedCandidateDetails::from(&candidate)
+            .reencrypt(private_keys, &recipients)
+            .await?;
+        let candidate = Mutation::update_candidate_encrypted_details(db, candidate, enc_candidate).await?;
+
+        for parent in parents {
+            let enc_parent = EncryptedParentDetails::from(&parent)
+                .reencrypt(private_keys, &recipients)
+                .await?;
+            Mutation::add_parent_details(db, parent, enc_parent).await?;
+        }
+
+        for application in applications {
+            Self::reencrypt_reviews(db, application.id, private_keys, admin_public_keys).await?;
+            let personal_id_number = EncryptedString::from(application.personal_id_number.to_owned())
+                .reencrypt(private_keys, &recipients)
+                .await?;
+            Mutation::update_application_personal_id_number(db, application, personal_id_number.into()).await?;
+        }
+
+        info!("CANDIDATE {} REENCRYPTED", candidate_id);
+        Ok(candidate)
+    }
+
+    /// Reviews are readable only by admins, candidates are not among the recipients
+    async fn reencrypt_reviews<C: ConnectionTrait>(
+        db: &C,
+        application_id: i32,
+        private_keys: &[String],
+        admin_public_keys: &[String],
+    ) -> Result<(), ServiceError> {
+        let recipients = admin_public_keys.to_vec();
+        for review in Query::list_reviews_by_application_id(db, application_id).await? {
+            let scores = EncryptedString::reencrypt_option(
+                &review.scores.to_owned().map(EncryptedString::from),
+                private_keys,
+                &recipients,
+            ).await?;
+            let comment = EncryptedString::reencrypt_option(
+                &review.comment.to_owned().map(EncryptedString::from),
+                private_keys,
+                &recipients,
+            ).await?;
+            Mutation::update_review_encrypted(
+                db,
+                review,
+                scores.map(EncryptedString::to_string),
+                comment.map(EncryptedString::to_string),
+            ).await?;
+        }
+        Ok(())
+    }
+
+    async fn reencrypt_portfolio<C: ConnectionTrait>(
+        db: &C,
+        candidate_id: i32,
+        private_keys: &[String],
+        recipients: &[String],
+    ) -> Result<(), ServiceError> {
+        let mut result = Err(ServiceError::CryptoDecryptFailed);
+        for private_key in private_keys {
+            result = PortfolioService::reencrypt_portfolio(db, candidate_id, private_key.to_owned(), recipients).await;
+            if result.is_ok() {
+                break;
+            }
+        }
+
+        if let Err(e) = &result {
+            warn!("PORTFOLIO {} REENCRYPT FAILED: {}", candidate_id, e);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+
+    use crate::{
+        crypto,
+        models::{candidate_details::EncryptedApplicationDetails, review::ReviewScores},
+        services::{
+            admin_service::admin_tests::create_admin,
+            candidate_service::tests::put_user_data,
+            portfolio_service::{tests::{audit_context, clear_data_store_temp_dir, create_data_store_temp_dir, put_portfolio_documents}, PortfolioService},
+            review_service::ReviewService,
+        },
+        utils::db::get_memory_sqlite_connection,
+        Mutation, Query,
+    };
+
+    use super::KeyRotationService;
+
+    const PASSWORD: &str = "admin";
+
+    #[tokio::test]
+    #[serial]
+    async fn test_rotate_admin_key() {
+        let db = get_memory_sqlite_connection().await;
+        let admin = create_admin(&db).await;
+        let (application, candidate, _) = put_user_data(&db).await;
+
+        let (temp_dir, _, _) = create_data_store_temp_dir(candidate.id).await;
+        put_portfolio_documents(&db, candidate.id, 0).await;
+        PortfolioService::submit(&candidate, &db, &audit_context()).await.unwrap();
+        ReviewService::assign(&db, application.id, admin.id, &audit_context()).await.unwrap();
+        let scores = ReviewScores::from([("overall".to_string(), 8)]);
+        ReviewService::submit(&db, admin.id, application.id, scores.to_owned(), Some("Dobré".to_string()), &audit_context())
+            .await
+            .unwrap();
+
+        let old_private_key = crypto::decrypt_password(admin.private_key.clone(), PASSWORD.to_string())
+            .await
+            .unwrap();
+
+        let new_public_key = KeyRotationService::rotate_admin_key(&db, admin.id, PASSWORD.to_string())
+            .await
+            .unwrap();
+
+        let admin = Query::find_admin_by_id(&db, admin.id).await.unwrap().unwrap();
+        assert_eq!(admin.public_key, new_public_key);
+        assert!(Query::find_admin_key_rotation(&db, admin.id).await.unwrap().is_none());
+
+        let new_private_key = crypto::decrypt_password(admin.private_key.clone(), PASSWORD.to_string())
+            .await
+            .unwrap();
+
+        let candidate = Query::find_candidate_by_id(&db, candidate.id).await.unwrap().unwrap();
+        let parents = Query::find_candidate_parents(&db, &candidate).await.unwrap();
+        let details = EncryptedApplicationDetails::from((&candidate, &parents));
+        assert!(details.clone().decrypt(new_private_key.clone()).await.is_ok());
+        assert!(details.decrypt(old_private_key.clone()).await.is_err());
+
+        let application = Query::find_application_by_id(&db, application.id).await.unwrap().unwrap();
+        let personal_id_number = crypto::decrypt_password_with_private_key(&application.personal_id_number, &new_private_key)
+            .await
+            .unwrap();
+        assert_eq!(personal_id_number, "0000001111");
+
+        let reviews = ReviewService::list_reviews(&db, &new_private_key, application.id).await.unwrap();
+        assert_eq!(reviews[0].scores, scores);
+        assert_eq!(reviews[0].comment.as_deref(), Some("Dobré"));
+        assert!(ReviewService::list_reviews(&db, &old_private_key, application.id).await.is_err());
+
+        assert!(PortfolioService::get_portfolio(candidate.id, new_private_key).await.is_ok());
+        assert!(PortfolioService::get_portfolio(candidate.id, old_private_key).await.is_err());
+
+        clear_data_store_temp_dir(temp_dir).await;
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_rotate_admin_key_resume() {
+        let db = get_memory_sqlite_connection().await;
+        let admin = create_admin(&db).await;
+        let (_, candidate, _) = put_user_data(&db).await;
+
+        // Rotation interrupted right after it was created
+        let (pubkey, priv_key) = crypto::create_identity();
+        let enc_priv_key = crypto::encrypt_password(priv_key.clone(), PASSWORD.to_string()).await.unwrap();
+        Mutation::create_admin_key_rotation(&db, admin.id, pubkey.clone(), enc_priv_key).await.unwrap();
+
+        let new_public_key = KeyRotationService::rotate_admin_key(&db, admin.id, PASSWORD.to_string())
+            .await
+            .unwrap();
+        assert_eq!(new_public_key, pubkey);
+
+        let candidate = Query::find_candidate_by_id(&db, candidate.id).await.unwrap().unwrap();
+        let parents = Query::find_candidate_parents(&db, &candidate).await.unwrap();
+        let details = EncryptedApplicationDetails::from((&candidate, &parents));
+        assert!(details.decrypt(priv_key).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_rotate_admin_key_wrong_password() {
+        let db = get_memory_sqlite_connection().await;
+        let admin = create_admin(&db).await;
+
+        assert!(KeyRotationService::rotate_admin_key(&db, admin.id, "wrong".to_string()).await.is_err());
+        assert!(Query::find_admin_key_rotation(&db, admin.id).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,122 @@
+use log::info;
+use sea_orm::DbConn;
+
+use crate::{
+    error::ServiceError,
+    models::{admission_cycle::AdmissionCycle, credential_letter::CredentialLetter},
+    utils::pdf::{render_pages, wrap, Font, TextLine},
+    Query,
+};
+
+/// Characters per line of wrapped paragraphs
+const LINE_LENGTH: usize = 85;
+
+pub struct LetterService;
+
+impl LetterService {
+    /// Renders credential letters of existing applications into one PDF, one letter per page.
+    /// Passwords are not stored in plain text, so they have to be passed in right after they were generated
+    pub async fn credential_letters(
+        db: &DbConn,
+        letters: &[CredentialLetter],
+    ) -> Result<Vec<u8>, ServiceError> {
+        for letter in letters {
+            Query::find_application_by_id(db, letter.application_id)
+                .await?
+                .ok_or(ServiceError::CandidateNotFound)?;
+        }
+
+        info!("CREDENTIAL LETTERS FOR {} APPLICATIONS RENDERED", letters.len());
+        Ok(Self::render(letters))
+    }
+
+    /// Renders letters without checking the applications, used right after they were created
+    pub fn render(letters: &[CredentialLetter]) -> Vec<u8> {
+        let pages: Vec<Vec<TextLine>> = letters.iter()
+            .map(Self::letter_page)
+            .collect();
+
+        render_pages(&pages)
+    }
+
+    fn letter_page(letter: &CredentialLetter) -> Vec<TextLine> {
+        let cycle = AdmissionCycle::get();
+        let field_of_study = cycle.program_by_application_id(letter.application_id)
+            .map(|p| p.official_name())
+            .unwrap_or_default();
+        let login_url = cycle.login_url.to_owned().unwrap_or_default();
+
+        let mut lines = vec![
+            TextLine::new(&cycle.school, Font::Regular, 11.0),
+            TextLine::new("Přístupové údaje k přihlášce", Font::Bold, 18.0).space_before(40.0),
+        ];
+
+        for (label, value, font) in [
+            ("Evidenční číslo přihlášky", letter.application_id.to_string(), Font::Bold),
+            ("Obor", field_of_study, Font::Bold),
+            ("Heslo", letter.password.to_owned(), Font::Monospace),
+            ("Adresa pro přihlášení", login_url.to_owned(), Font::Bold),
+        ] {
+            if value.is_empty() {
+                continue;
+            }
+            lines.push(TextLine::new(label, Font::Regular, 10.0).space_before(14.0));
+            lines.push(TextLine::new(value, font, 14.0));
+        }
+
+        lines.push(TextLine::new("Postup", Font::Bold, 12.0).space_before(30.0));
+        let login = if login_url.is_empty() {
+            "Přihlaste se evidenčním číslem přihlášky a heslem z tohoto dopisu.".to_string()
+        } else {
+            format!("Na adrese {} se přihlaste evidenčním číslem přihlášky a heslem z tohoto dopisu.", login_url)
+        };
+        let steps = [
+            login,
+            "Vyplňte osobní údaje, údaje zákonných zástupců a známky z vysvědčení.".to_string(),
+            "Nahrajte motivační dopis a portfolio a portfolio odešlete.".to_string(),
+        ];
+        for (i, step) in steps.iter().enumerate() {
+            for (j, line) in wrap(&format!("{}. {}", i + 1, step), LINE_LENGTH).into_iter().enumerate() {
+                lines.push(TextLine::new(line, Font::Regular, 11.0).space_before(if j == 0 { 6.0 } else { 0.0 }));
+            }
+        }
+
+        let note = "Heslo nikomu nesdělujte. Pokud jej ztratíte, kontaktujte kancelář školy, která vám vydá nové.";
+        for (j, line) in wrap(note, LINE_LENGTH).into_iter().enumerate() {
+            lines.push(TextLine::new(line, Font::Regular, 11.0).space_before(if j == 0 { 20.0 } else { 0.0 }));
+        }
+
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{error::ServiceError, models::credential_letter::CredentialLetter, services::candidate_service::tests::put_user_data, utils::db::get_memory_sqlite_connection};
+
+    use super::LetterService;
+
+    #[tokio::test]
+    async fn test_credential_letters() {
+        let db = get_memory_sqlite_connection().await;
+        let (application, _, _) = put_user_data(&db).await;
+
+        let letter = CredentialLetter {
+            application_id: application.id,
+            password: "abcdefgh1234".to_string(),
+        };
+        let pdf = LetterService::credential_letters(&db, &[letter.clone(), letter.clone()]).await.unwrap();
+        assert!(pdf.starts_with(b"%PDF"));
+        assert!(pdf.windows(8).any(|w| w == b"/Count 2"));
+        assert!(pdf.windows(12).any(|w| w == b"abcdefgh1234"));
+
+        let missing = CredentialLetter {
+            application_id: 101999,
+            ..letter
+        };
+        assert!(matches!(
+            LetterService::credential_letters(&db, &[missing]).await,
+            Err(ServiceError::CandidateNotFound)
+        ));
+    }
+}
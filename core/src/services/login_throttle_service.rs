@@ -0,0 +1,150 @@
+use std::future::Future;
+
+use log::warn;
+use sea_orm::DbConn;
+
+use crate::{
+    error::ServiceError,
+    models::login_attempt::{LoginScope, LoginThrottleConfig},
+    Mutation, Query,
+};
+
+pub struct LoginThrottleService;
+
+impl LoginThrottleService {
+    /// Runs `login` unless the account or the IP address is locked out.
+    /// Failed attempts are counted against both, a successful login clears the account's failures
+    pub async fn throttle<T>(
+        db: &DbConn,
+        account: (LoginScope, String),
+        ip_addr: &str,
+        login: impl Future<Output = Result<T, ServiceError>>,
+    ) -> Result<T, ServiceError> {
+        let config = LoginThrottleConfig::from_env();
+        let (scope, key) = account;
+
+        Self::check(db, scope, &key).await?;
+        Self::check(db, LoginScope::Ip, ip_addr).await?;
+
+        let result = login.await;
+        match &result {
+            Ok(_) => Self::unlock(db, scope, &key).await?,
+            Err(ServiceError::InvalidCredentials | ServiceError::CandidateNotFound) => {
+                Self::record_failure(db, &config, scope, &key).await?;
+                Self::record_failure(db, &config, LoginScope::Ip, ip_addr).await?;
+            },
+            Err(_) => {},
+        }
+
+        result
+    }
+
+    pub async fn check(db: &DbConn, scope: LoginScope, key: &str) -> Result<(), ServiceError> {
+        let now = chrono::offset::Local::now().naive_local();
+        let attempt = Query::find_login_attempt(db, scope.as_str(), key).await?;
+
+        match attempt.and_then(|a| a.locked_until) {
+            Some(locked_until) if locked_until > now => Err(ServiceError::Locked),
+            _ => Ok(()),
+        }
+    }
+
+    pub async fn record_failure(
+        db: &DbConn,
+        config: &LoginThrottleConfig,
+        scope: LoginScope,
+        key: &str,
+    ) -> Result<(), ServiceError> {
+        let now = chrono::offset::Local::now().naive_local();
+        let attempt = Query::find_login_attempt(db, scope.as_str(), key).await?;
+
+        let Some(attempt) = attempt else {
+            let locked_until = config.lockout_duration(scope, 1).map(|d| now + d);
+            Mutation::create_login_attempt(db, scope.as_str().to_string(), key.to_string(), locked_until).await?;
+            return Ok(());
+        };
+
+        let forget_before = now - chrono::Duration::seconds(config.max_lockout_seconds);
+        let failed_count = if attempt.last_failed_at < forget_before {
+            1
+        } else {
+            attempt.failed_count + 1
+        };
+
+        let locked_until = config.lockout_duration(scope, failed_count).map(|d| now + d);
+        if locked_until.is_some() {
+            warn!("LOGIN LOCKED FOR {} {} AFTER {} FAILED ATTEMPTS", scope.as_str().to_uppercase(), key, failed_count);
+        }
+        Mutation::update_login_attempt(db, attempt, failed_count, locked_until).await?;
+
+        Ok(())
+    }
+
+    /// Forgets all failed attempts of an account or an IP address
+    pub async fn unlock(db: &DbConn, scope: LoginScope, key: &str) -> Result<(), ServiceError> {
+        Mutation::delete_login_attempt(db, scope.as_str(), key).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        error::ServiceError,
+        models::login_attempt::{LoginScope, LoginThrottleConfig},
+        utils::db::get_memory_sqlite_connection,
+        Query,
+    };
+
+    use super::LoginThrottleService;
+
+    #[tokio::test]
+    async fn test_lockout_and_unlock() {
+        let db = get_memory_sqlite_connection().await;
+        let config = LoginThrottleConfig::default();
+
+        for _ in 0..config.max_attempts - 1 {
+            LoginThrottleService::record_failure(&db, &config, LoginScope::Application, "103151").await.unwrap();
+        }
+        LoginThrottleService::check(&db, LoginScope::Application, "103151").await.unwrap();
+
+        LoginThrottleService::record_failure(&db, &config, LoginScope::Application, "103151").await.unwrap();
+        assert!(matches!(
+            LoginThrottleService::check(&db, LoginScope::Application, "103151").await,
+            Err(ServiceError::Locked)
+        ));
+        LoginThrottleService::check(&db, LoginScope::Admin, "103151").await.unwrap();
+
+        LoginThrottleService::unlock(&db, LoginScope::Application, "103151").await.unwrap();
+        LoginThrottleService::check(&db, LoginScope::Application, "103151").await.unwrap();
+        assert!(Query::find_login_attempt(&db, "application", "103151").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_throttle_counts_failures() {
+        let db = get_memory_sqlite_connection().await;
+        let config = LoginThrottleConfig::default();
+
+        for _ in 0..config.max_attempts {
+            let result: Result<(), _> = LoginThrottleService::throttle(
+                &db,
+                (LoginScope::Admin, "1".to_string()),
+                "10.0.0.1",
+                async { Err(ServiceError::InvalidCredentials) },
+            ).await;
+            assert!(matches!(result, Err(ServiceError::InvalidCredentials)));
+        }
+
+        let result = LoginThrottleService::throttle(
+            &db,
+            (LoginScope::Admin, "1".to_string()),
+            "10.0.0.1",
+            async { Ok(()) },
+        ).await;
+        assert!(matches!(result, Err(ServiceError::Locked)));
+
+        let ip_attempt = Query::find_login_attempt(&db, "ip", "10.0.0.1").await.unwrap().unwrap();
+        assert_eq!(ip_attempt.failed_count, config.max_attempts);
+        assert!(ip_attempt.locked_until.is_none());
+    }
+}
@@ -3,4 +3,15 @@ pub mod candidate_service;
 pub mod admin_service;
 pub mod parent_service;
 pub mod application_service;
-pub mod portfolio_service;
\ No newline at end of file
+pub mod portfolio_service;
+pub mod key_rotation_service;
+pub mod audit_service;
+pub mod login_throttle_service;
+pub mod admission_round_service;
+pub mod search_service;
+pub mod import_service;
+pub mod letter_service;
+pub mod notification_service;
+pub mod timeline_service;
+pub mod upload_service;
+pub mod review_service;
\ No newline at end of file
@@ -0,0 +1,245 @@
+use log::{info, warn};
+use entity::notification;
+use sea_orm::DbConn;
+
+use crate::{
+    error::ServiceError,
+    mail::{self, Email},
+    models::{admission_cycle::AdmissionCycle, candidate_details::EncryptedString, notification::{NotificationKind, NotificationResponse, NotificationStatus, NotificationTemplates}},
+    Mutation,
+    Query,
+};
+
+/// Notification is marked as failed after this many unsuccessful delivery attempts
+const MAX_ATTEMPTS: i32 = 5;
+
+pub struct NotificationService;
+
+impl NotificationService {
+    /// Adds a notification for the candidate and their parents to the outbox.
+    /// Nothing is stored when no mail transport is configured.
+    /// Errors are only logged, notifications never fail the action that triggered them
+    pub async fn enqueue(db: &DbConn, candidate_id: i32, kind: NotificationKind) {
+        if let Err(e) = Self::try_enqueue(db, candidate_id, kind).await {
+            warn!("NOTIFICATION {} FOR CANDIDATE {} NOT QUEUED: {}", kind.as_str(), candidate_id, e);
+        }
+    }
+
+    async fn try_enqueue(db: &DbConn, candidate_id: i32, kind: NotificationKind) -> Result<(), ServiceError> {
+        if mail::from_env()?.is_none() {
+            return Ok(());
+        }
+        let templates = NotificationTemplates::get()?;
+
+        Mutation::create_notification(
+            db,
+            candidate_id,
+            kind.as_str().to_string(),
+            templates.default_locale.to_owned(),
+            NotificationStatus::Pending.as_str().to_string(),
+        ).await?;
+        Ok(())
+    }
+
+    /// Sends notifications of one candidate right after the action that queued them.
+    /// Errors are only logged, undelivered notifications stay in the outbox for `send_pending`
+    pub async fn flush(db: &DbConn, private_key: &String, candidate_id: i32) {
+        if let Err(e) = Self::send_pending(db, private_key, Some(candidate_id)).await {
+            warn!("NOTIFICATIONS FOR CANDIDATE {} NOT SENT: {}", candidate_id, e);
+        }
+    }
+
+    /// Sends pending notifications and returns how many were delivered.
+    /// Recipient emails are decrypted with `private_key` right before sending and never stored,
+    /// so the key has to be an admin key or, with `candidate_id`, a key of the candidate's application
+    pub async fn send_pending(
+        db: &DbConn,
+        private_key: &String,
+        candidate_id: Option<i32>,
+    ) -> Result<usize, ServiceError> {
+        let Some(transport) = mail::from_env()? else {
+            return Ok(0);
+        };
+        let templates = NotificationTemplates::get()?;
+
+        let mut sent = 0;
+        let pending = Query::list_notifications(db, Some(NotificationStatus::Pending.as_str()), candidate_id).await?;
+        for notification in pending {
+            let result = match Self::email(db, templates, private_key, &notification).await {
+                Ok(Some(email)) => transport.send(&email).await.map_err(|e| (e.to_string(), false)),
+                Ok(None) => Err(("No recipients".to_string(), true)),
+                Err(e) => Err((e.to_string(), false)),
+            };
+
+            let (status, error) = match result {
+                Ok(()) => {
+                    sent += 1;
+                    (NotificationStatus::Sent, None)
+                },
+                Err((error, permanent)) if permanent || notification.attempts + 1 >= MAX_ATTEMPTS => {
+                    warn!("NOTIFICATION {} FAILED: {}", notification.id, error);
+                    (NotificationStatus::Failed, Some(error))
+                },
+                Err((error, _)) => (NotificationStatus::Pending, Some(error)),
+            };
+            Mutation::update_notification_attempt(
+                db,
+                notification,
+                status.as_str().to_string(),
+                error,
+                status == NotificationStatus::Sent,
+            ).await?;
+        }
+
+        if sent > 0 {
+            info!("{} NOTIFICATIONS SENT", sent);
+        }
+        Ok(sent)
+    }
+
+    pub async fn list(
+        db: &DbConn,
+        status: Option<String>,
+    ) -> Result<Vec<NotificationResponse>, ServiceError> {
+        let status = status
+            .map(|s| NotificationStatus::try_from(s.as_str()))
+            .transpose()?;
+
+        Ok(
+            Query::list_notifications(db, status.as_ref().map(|s| s.as_str()), None)
+                .await?
+                .into_iter()
+                .map(NotificationResponse::from)
+                .collect()
+        )
+    }
+
+    /// Email to the candidate and all parents with an email, `None` when nobody has filled in an email
+    async fn email(
+        db: &DbConn,
+        templates: &NotificationTemplates,
+        private_key: &String,
+        notification: &notification::Model,
+    ) -> Result<Option<Email>, ServiceError> {
+        let candidate = Query::find_candidate_by_id(db, notification.candidate_id)
+            .await?
+            .ok_or(ServiceError::CandidateNotFound)?;
+        let parents = Query::find_candidate_parents(db, &candidate).await?;
+        let applications = Query::find_applications_by_candidate_id(db, candidate.id).await?;
+
+        let encrypted_emails: Vec<String> = std::iter::once(candidate.email.to_owned())
+            .chain(parents.iter().map(|p| p.email.to_owned()))
+            .flatten()
+            .collect();
+        let mut to = vec![];
+        for email in encrypted_emails {
+            let email = EncryptedString::from(email).decrypt(private_key).await?;
+            if !email.trim().is_empty() && !to.contains(&email) {
+                to.push(email);
+            }
+        }
+        if to.is_empty() {
+            return Ok(None);
+        }
+
+        let encrypted_name: Vec<String> = [candidate.name, candidate.surname]
+            .into_iter()
+            .flatten()
+            .collect();
+        let mut name = vec![];
+        for part in encrypted_name {
+            name.push(EncryptedString::from(part).decrypt(private_key).await?);
+        }
+
+        let cycle = AdmissionCycle::get();
+        let template = templates.render(
+            NotificationKind::try_from(notification.kind.as_str())?,
+            &notification.locale,
+            &[
+                ("name", name.join(" ")),
+                ("applications", applications.iter().map(|a| a.id.to_string()).collect::<Vec<_>>().join(", ")),
+                ("school", cycle.school.to_owned()),
+                ("loginUrl", cycle.login_url.to_owned().unwrap_or_default()),
+            ],
+        );
+
+        Ok(
+            Some(
+                Email {
+                    to,
+                    subject: template.subject,
+                    body: template.body,
+                }
+            )
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+
+    use crate::{
+        crypto,
+        mail::FileTransport,
+        models::notification::{NotificationKind, NotificationStatus},
+        services::{admin_service::admin_tests::create_admin, candidate_service::tests::put_user_data},
+        utils::db::get_memory_sqlite_connection,
+        Query,
+    };
+
+    use super::NotificationService;
+
+    #[tokio::test]
+    #[serial]
+    async fn test_enqueue_and_send() {
+        let db = get_memory_sqlite_connection().await;
+        let admin = create_admin(&db).await;
+        let mail_dir = std::env::temp_dir().join("portfolio_test_mail").join(crypto::random_12_char_string());
+        std::env::set_var("PORTFOLIO_MAIL_TRANSPORT", "file");
+        std::env::set_var("PORTFOLIO_MAIL_DIR", &mail_dir);
+
+        // Details submission queues the first notification
+        let (application, candidate, _) = put_user_data(&db).await;
+        NotificationService::enqueue(&db, candidate.id, NotificationKind::PasswordReset).await;
+        assert_eq!(Query::list_notifications(&db, Some("pending"), None).await.unwrap().len(), 2);
+
+        let private_key = crypto::decrypt_password(application.private_key.clone(), "test".to_string())
+            .await
+            .unwrap();
+        NotificationService::flush(&db, &private_key, candidate.id).await;
+
+        let emails = FileTransport::new(&mail_dir).read_all().await.unwrap();
+        assert_eq!(emails.len(), 2);
+        assert!(emails.iter().all(|e| e.to == vec!["email".to_string(), "parent_email".to_string()]));
+        assert!(emails.iter().all(|e| e.body.contains(&application.id.to_string())));
+        let notifications = NotificationService::list(&db, Some("sent".to_string())).await.unwrap();
+        assert_eq!(notifications.len(), 2);
+        assert!(notifications.iter().all(|n| n.sent_at.is_some()));
+
+        // Undecryptable notification stays in the outbox for retry
+        NotificationService::enqueue(&db, candidate.id, NotificationKind::PortfolioSubmitted).await;
+        let (_, other_key) = crypto::create_identity();
+        assert_eq!(NotificationService::send_pending(&db, &other_key, None).await.unwrap(), 0);
+        let pending = Query::list_notifications(&db, Some(NotificationStatus::Pending.as_str()), None).await.unwrap();
+        assert_eq!(pending[0].attempts, 1);
+        assert!(pending[0].last_error.is_some());
+
+        let admin_key = crypto::decrypt_password(admin.private_key, "admin".to_string()).await.unwrap();
+        assert_eq!(NotificationService::send_pending(&db, &admin_key, None).await.unwrap(), 1);
+
+        std::env::remove_var("PORTFOLIO_MAIL_TRANSPORT");
+        std::env::remove_var("PORTFOLIO_MAIL_DIR");
+        tokio::fs::remove_dir_all(mail_dir).await.unwrap();
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_disabled_notifications() {
+        let db = get_memory_sqlite_connection().await;
+        let (_, candidate, _) = put_user_data(&db).await;
+
+        NotificationService::enqueue(&db, candidate.id, NotificationKind::PortfolioSubmitted).await;
+        assert!(Query::list_notifications(&db, None, None).await.unwrap().is_empty());
+    }
+}
@@ -1,17 +1,32 @@
-use std::{path::{PathBuf, Path}};
-
-use entity::candidate;
+use entity::{application, candidate, portfolio_version};
 use log::{info, warn};
-use sea_orm::{DbConn};
+use sea_orm::{ConnectionTrait, DbConn};
 use serde::{Serialize, ser::{SerializeStruct}};
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use async_compat::CompatExt;
+use futures::io::AsyncWriteExt;
+use tokio::io::{AsyncReadExt, DuplexStream};
+
+use crate::{error::ServiceError, Mutation, Query, crypto, models::{admission_cycle::AdmissionCycle, audit::{AuditAction, AuditActor, AuditContext}, document::{DocumentStatus, DocumentType}, notification::NotificationKind, portfolio_version::{ArchiveManifest, IntegrityReport, IntegrityStatus, ManifestFile, PortfolioVersionResponse}, receipt::{ReceiptKey, SubmissionReceipt}, timeline::CandidateAction}, scanner::{self, ScanResult}, storage::{self, PortfolioStore}, utils::content::validate_document};
+
+use super::{audit_service::AuditService, notification_service::NotificationService, timeline_service::TimelineService};
+
+/// Size of in-memory pipe between archive encryption and the store
+const PIPE_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Used when `PORTFOLIO_MAX_VERSIONS` is not set
+const DEFAULT_MAX_VERSIONS: usize = 5;
 
-use crate::{error::ServiceError, Query, crypto};
+/// Name of the object holding the number of the submitted version
+const CURRENT_VERSION: &str = "CURRENT_VERSION";
+
+/// Portfolio submitted before versioning, stored directly in the candidate directory
+const LEGACY_PORTFOLIO: &str = "PORTFOLIO.age";
 
 #[derive(Debug, PartialEq)]
 pub enum SubmissionProgress {
     NoneInCache,
-    SomeInCache(Vec<FileType>),
+    /// Slugs of the uploaded documents
+    SomeInCache(Vec<String>),
     AllInCache,
     Submitted,
 }
@@ -25,6 +40,13 @@ impl SubmissionProgress {
             SubmissionProgress::Submitted => 4,
         }
     }
+
+    fn files(&self) -> &[String] {
+        match self {
+            SubmissionProgress::SomeInCache(files) => files,
+            _ => &[],
+        }
+    }
 }
 
 // Serialize the enum so that the JSON contains status field and a list of files present in cache
@@ -35,311 +57,493 @@ impl Serialize for SubmissionProgress {
     {
         let mut progress = serializer.serialize_struct("SubmissionProgress", 2)?;
         progress.serialize_field("status", &self.index())?;
-
-        match self {
-            SubmissionProgress::SomeInCache(files) => {
-                progress.serialize_field("files", files)?;
-            }
-            _ => {
-                progress.serialize_field("files", &Vec::<FileType>::new())?;
-            }
-        };
-
+        progress.serialize_field("files", self.files())?;
         progress.end()
     }
 }
 
-
-#[derive(Debug, Copy, PartialEq, Clone)]
-pub enum FileType {
-    CoverLetterPdf = 1,
-    PortfolioLetterPdf = 2,
-    PortfolioZip = 3,
-    Age = 4,
-}
-
-impl FileType {
-    pub fn index(&self) -> usize {
-        *self as usize
-    }
-
-    pub fn as_str(&self) -> &'static str {
-        match self {
-            FileType::CoverLetterPdf => "MOTIVACNI_DOPIS.pdf",
-            FileType::PortfolioLetterPdf => "PORTFOLIO.pdf",
-            FileType::PortfolioZip => "PORTFOLIO.zip",
-            FileType::Age => "PORTFOLIO.age",
-        }
-    }
-
-    pub fn iter_cache() -> impl Iterator<Item = Self> {
-        [
-            FileType::CoverLetterPdf,
-            FileType::PortfolioLetterPdf,
-            FileType::PortfolioZip,
-        ]
-        .iter()
-        .copied()
-    }
-}
-
-impl ToString for FileType {
-    fn to_string(&self) -> String {
-        self.as_str().to_string()
-    }
+/// Submission progress together with documents found infected on upload, they have to be uploaded again.
+/// Quarantine doesn't change the progress, previously uploaded clean files of the same document stay in cache
+#[derive(Debug, PartialEq)]
+pub struct SubmissionStatus {
+    pub progress: SubmissionProgress,
+    /// Slugs of the quarantined documents
+    pub quarantined: Vec<String>,
 }
 
-impl Serialize for FileType {
+// Same JSON as the progress with an extra list of quarantined files
+impl Serialize for SubmissionStatus {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer,
     {
-        serializer.serialize_u32(self.index() as u32)
+        let mut status = serializer.serialize_struct("SubmissionStatus", 3)?;
+        status.serialize_field("status", &self.progress.index())?;
+        status.serialize_field("files", self.progress.files())?;
+        status.serialize_field("quarantined", &self.quarantined)?;
+        status.end()
     }
 }
 
 
 pub struct PortfolioService;
 impl PortfolioService {
-    pub async fn get_submission_progress(candidate_id: i32) -> Result<SubmissionProgress, ServiceError> {
-        let path = Self::get_file_store_path().join(&candidate_id.to_string());
-        if !path.exists() {
-            return Err(ServiceError::CandidateNotFound);
-        }
-        let cache_path = path.join("cache");
-
-        if path.join(FileType::Age.as_str()).exists() {
-            return Ok(SubmissionProgress::Submitted);
-        }
+    /// Documents the candidate has to upload, given by the programs of all their applications
+    pub async fn documents(db: &DbConn, candidate_id: i32) -> Result<Vec<DocumentType>, ServiceError> {
+        let application_ids: Vec<i32> = Query::find_applications_by_candidate_id(db, candidate_id)
+            .await?
+            .iter()
+            .map(|a| a.id)
+            .collect();
 
-        let mut files = Vec::new();
-        for file in FileType::iter_cache() {
-            if cache_path.join(file.as_str()).exists() {
-                files.push(file);
-            }
-        }
-        match files.len() {
-            0 => Ok(SubmissionProgress::NoneInCache),
-            3 => Ok(SubmissionProgress::AllInCache),
-            _ => Ok(SubmissionProgress::SomeInCache(files)),
-        }
+        Ok(AdmissionCycle::get().documents_for(&application_ids))
     }
 
+    async fn document(db: &DbConn, candidate_id: i32, slug: &str) -> Result<DocumentType, ServiceError> {
+        Self::documents(db, candidate_id)
+            .await?
+            .into_iter()
+            .find(|d| d.slug == slug)
+            .ok_or(ServiceError::DocumentNotFound)
+    }
 
-    // Get root path or local directory
-    fn get_file_store_path() -> PathBuf {
-        dotenv::dotenv().ok();
-        Path::new(&std::env::var("PORTFOLIO_STORE_PATH").unwrap_or_else(|_| "".to_string())).to_path_buf()
+    /// Documents the candidate has to upload together with what is in cache and quarantine
+    pub async fn document_statuses(db: &DbConn, candidate_id: i32) -> Result<Vec<DocumentStatus>, ServiceError> {
+        let store = storage::from_env()?;
+        let cached = store.list(&Self::cache_prefix(candidate_id)).await?;
+        let quarantine = store.list(&Self::quarantine_prefix(candidate_id)).await?;
+
+        Ok(
+            Self::documents(db, candidate_id)
+                .await?
+                .into_iter()
+                .map(|document| DocumentStatus {
+                    uploaded: cached.contains(&Self::cache_key(candidate_id, &document.file_name)),
+                    quarantined: quarantine.contains(&Self::quarantine_key(candidate_id, &document.file_name)),
+                    document,
+                })
+                .collect()
+        )
     }
 
-    /// Writes file to desired location
-    async fn write_portfolio_file(
-        candidate_id: i32,
-        data: Vec<u8>,
-        filename: FileType,
-    ) -> Result<(), ServiceError> {
-        info!("PORTFOLIO {} CACHE {} WRITE STARTED", candidate_id, filename.as_str());
+    pub async fn get_submission_progress(db: &DbConn, candidate_id: i32) -> Result<SubmissionProgress, ServiceError> {
+        Ok(Self::get_submission_status(db, candidate_id).await?.progress)
+    }
 
-        let cache_path = Self::get_file_store_path().join(&candidate_id.to_string()).join("cache");
+    pub async fn get_submission_status(db: &DbConn, candidate_id: i32) -> Result<SubmissionStatus, ServiceError> {
+        let store = storage::from_env()?;
+        if !store.exists(&candidate_id.to_string()).await? {
+            return Err(ServiceError::CandidateNotFound);
+        }
 
-        let mut file = tokio::fs::File::create(cache_path.join(filename.as_str())).await?;
+        if Self::current_key(&*store, candidate_id).await?.is_some() {
+            return Ok(SubmissionStatus { progress: SubmissionProgress::Submitted, quarantined: vec![] });
+        }
 
-        file.write_all(&data).await?;
+        let statuses = Self::document_statuses(db, candidate_id).await?;
+        let slugs = |filter: fn(&DocumentStatus) -> bool| -> Vec<String> {
+            statuses.iter()
+                .filter(|s| filter(s))
+                .map(|s| s.document.slug.to_owned())
+                .collect()
+        };
+        let cached = slugs(|s| s.uploaded);
+        let quarantined = slugs(|s| s.quarantined);
+
+        let progress = if cached.is_empty() {
+            SubmissionProgress::NoneInCache
+        } else if statuses.iter().all(|s| s.uploaded || !s.document.required) {
+            SubmissionProgress::AllInCache
+        } else {
+            SubmissionProgress::SomeInCache(cached)
+        };
+        Ok(SubmissionStatus { progress, quarantined })
+    }
 
-        info!("PORTFOLIO {} CACHE {} WRITE FINISHED", candidate_id, filename.as_str());
-        Ok(())
+    pub(crate) fn cache_prefix(candidate_id: i32) -> String {
+        format!("{}/cache", candidate_id)
     }
 
-    pub async fn create_user_dir(application_id: i32) -> tokio::io::Result<()> {
-        tokio::fs::create_dir_all(
-            Self::get_file_store_path()
-            .join(&application_id.to_string())
-            .join("cache"))
-            .await
+    fn cache_key(candidate_id: i32, file_name: &str) -> String {
+        format!("{}/{}", Self::cache_prefix(candidate_id), file_name)
     }
 
-    
-    pub async fn add_cover_letter_to_cache(
-        candidate_id: i32,
-        letter: Vec<u8>,
-    ) -> Result<(), ServiceError> {
-        Self::write_portfolio_file(candidate_id, letter, FileType::CoverLetterPdf).await
+    fn quarantine_prefix(candidate_id: i32) -> String {
+        format!("{}/quarantine", candidate_id)
     }
 
-    pub async fn add_portfolio_letter_to_cache(
-        candidate_id: i32,
-        letter: Vec<u8>,
-    ) -> Result<(), ServiceError> {
-        Self::write_portfolio_file(candidate_id, letter, FileType::PortfolioLetterPdf).await
+    fn quarantine_key(candidate_id: i32, file_name: &str) -> String {
+        format!("{}/{}", Self::quarantine_prefix(candidate_id), file_name)
     }
 
-    pub async fn add_portfolio_zip_to_cache(
-        candidate_id: i32,
-        zip: Vec<u8>,
-    ) -> Result<(), ServiceError> {
-        Self::write_portfolio_file(candidate_id, zip, FileType::PortfolioZip).await
+    fn final_key(candidate_id: i32, file_name: &str) -> String {
+        format!("{}/{}", candidate_id, file_name)
     }
-    
-    
-    pub async fn is_cover_letter(candidate_id: i32) -> bool {
-        let cache_path = Self::get_file_store_path().join(&candidate_id.to_string()).join("cache");
-        
-        tokio::fs::metadata(cache_path.join(cache_path.join(FileType::CoverLetterPdf.as_str())))
-        .await
-        .is_ok()
+
+    fn version_key(candidate_id: i32, version: i32) -> String {
+        format!("{}/versions/{}.age", candidate_id, version)
     }
 
-    pub async fn is_portfolio_letter(candidate_id: i32) -> bool {
-        let cache_path = Self::get_file_store_path().join(&candidate_id.to_string()).join("cache");
+    fn current_version_key(candidate_id: i32) -> String {
+        format!("{}/{}", candidate_id, CURRENT_VERSION)
+    }
 
-        tokio::fs::metadata(
-            cache_path.join(
-                cache_path.join(FileType::PortfolioLetterPdf.as_str())
-            )
-        )
-            .await
-            .is_ok()
+    /// How many versions are kept for every candidate, read from `PORTFOLIO_MAX_VERSIONS`
+    fn max_versions() -> Result<usize, ServiceError> {
+        dotenv::dotenv().ok();
+        match std::env::var("PORTFOLIO_MAX_VERSIONS") {
+            Ok(max) => match max.parse() {
+                Ok(max) if max > 0 => Ok(max),
+                _ => Err(ServiceError::StorageConfigError(format!("Invalid PORTFOLIO_MAX_VERSIONS {}", max))),
+            },
+            Err(_) => Ok(DEFAULT_MAX_VERSIONS),
+        }
     }
 
-    pub async fn is_portfolio_zip(candidate_id: i32) -> bool {
-        let cache_path = Self::get_file_store_path().join(&candidate_id.to_string()).join("cache");
+    /// Number of the submitted version, `None` when nothing is submitted
+    /// or the portfolio was submitted before versioning
+    async fn current_version(store: &dyn PortfolioStore, candidate_id: i32) -> Result<Option<i32>, ServiceError> {
+        let key = Self::current_version_key(candidate_id);
+        if !store.exists(&key).await? {
+            return Ok(None);
+        }
 
-        tokio::fs::metadata(
-            cache_path.join(
-                cache_path.join(FileType::PortfolioZip.as_str())
-            )
-        )
-            .await
-            .is_ok()
+        let mut version = String::new();
+        store.get(&key).await?.read_to_string(&mut version).await?;
+        version.trim()
+            .parse()
+            .map(Some)
+            .map_err(|_| ServiceError::PortfolioWriteError)
     }
 
+    /// Key of the submitted portfolio, portfolios submitted before versioning are stored directly in the candidate directory
+    pub async fn current_key(store: &dyn PortfolioStore, candidate_id: i32) -> Result<Option<String>, ServiceError> {
+        if let Some(version) = Self::current_version(store, candidate_id).await? {
+            return Ok(Some(Self::version_key(candidate_id, version)));
+        }
 
-    /// Returns true if portfolio is ready to be moved to the final directory
-    async fn is_portfolio_prepared(candidate_id: i32) -> bool {
-        Self::get_submission_progress(candidate_id).await.ok() == Some(SubmissionProgress::AllInCache)
+        let legacy_key = Self::final_key(candidate_id, LEGACY_PORTFOLIO);
+        if store.exists(&legacy_key).await? {
+            return Ok(Some(legacy_key));
+        }
+        Ok(None)
     }
 
-    // Delete single item from cache
-    pub async fn delete_cache_item(candidate_id: i32, file_type: FileType) -> Result<(), ServiceError> {
-        let cache_path = Self::get_file_store_path().join(&candidate_id.to_string()).join("cache");
+    /// Scans file, validates its content against the document and writes it to cache.
+    /// Infected file is moved to quarantine instead, where it stays until a clean file of the same document is uploaded
+    pub(crate) async fn write_portfolio_file(
+        candidate_id: i32,
+        data: Vec<u8>,
+        document: &DocumentType,
+    ) -> Result<(), ServiceError> {
+        info!("PORTFOLIO {} CACHE {} WRITE STARTED", candidate_id, document.file_name);
+        let store = storage::from_env()?;
+        let quarantine_key = Self::quarantine_key(candidate_id, &document.file_name);
+
+        if let ScanResult::Infected(signature) = scanner::from_env()?.scan(&data).await? {
+            warn!("PORTFOLIO {} CACHE {} QUARANTINED: {}", candidate_id, document.file_name, signature);
+            store.put(&quarantine_key, &mut data.as_slice()).await?;
+            return Err(ServiceError::MalwareDetected(signature));
+        }
+        validate_document(&data, &document.mime_types).await?;
 
-        tokio::fs::remove_file(cache_path.join(file_type.as_str())).await?;
+        store.put(&Self::cache_key(candidate_id, &document.file_name), &mut data.as_slice()).await?;
+        store.delete(&quarantine_key).await?;
 
+        info!("PORTFOLIO {} CACHE {} WRITE FINISHED", candidate_id, document.file_name);
         Ok(())
     }
 
-    pub async fn delete_cover_letter_from_cache(
-        candidate_id: i32,
-    ) -> Result<(), ServiceError> {
-        Self::delete_cache_item(candidate_id,  FileType::CoverLetterPdf).await
+    pub async fn create_user_dir(application_id: i32) -> Result<(), ServiceError> {
+        storage::from_env()?
+            .create_prefix(&Self::cache_prefix(application_id))
+            .await
     }
 
-    pub async fn delete_portfolio_letter_from_cache(
+    /// Uploads document identified by `slug`, the document must be required or allowed by one of the candidate's programs
+    pub async fn add_document_to_cache(
+        db: &DbConn,
         candidate_id: i32,
+        slug: &str,
+        data: Vec<u8>,
     ) -> Result<(), ServiceError> {
-        Self::delete_cache_item(candidate_id,  FileType::PortfolioLetterPdf).await
+        TimelineService::ensure_allowed(db, candidate_id, CandidateAction::EditPortfolio).await?;
+        let document = Self::document(db, candidate_id, slug).await?;
+        if data.len() as u64 > document.max_size {
+            return Err(ServiceError::UploadTooLarge);
+        }
+
+        Self::write_portfolio_file(candidate_id, data, &document).await
+    }
+
+    /// Returns true if document identified by `slug` is uploaded
+    pub async fn is_document_cached(db: &DbConn, candidate_id: i32, slug: &str) -> bool {
+        let Ok(document) = Self::document(db, candidate_id, slug).await else {
+            return false;
+        };
+        let Ok(store) = storage::from_env() else {
+            return false;
+        };
+
+        store.exists(&Self::cache_key(candidate_id, &document.file_name))
+            .await
+            .unwrap_or(false)
+    }
+
+    /// Returns true if portfolio is ready to be moved to the final directory
+    async fn is_portfolio_prepared(db: &DbConn, candidate_id: i32) -> bool {
+        Self::get_submission_progress(db, candidate_id).await.ok() == Some(SubmissionProgress::AllInCache)
+    }
+
+    // Delete single item from cache
+    pub async fn delete_cache_item(candidate_id: i32, document: &DocumentType) -> Result<(), ServiceError> {
+        storage::from_env()?
+            .delete(&Self::cache_key(candidate_id, &document.file_name))
+            .await
     }
 
-    pub async fn delete_portfolio_zip_from_cache(
+    pub async fn delete_document_from_cache(
+        db: &DbConn,
         candidate_id: i32,
+        slug: &str,
     ) -> Result<(), ServiceError> {
-        Self::delete_cache_item(candidate_id,  FileType::PortfolioZip).await
+        TimelineService::ensure_allowed(db, candidate_id, CandidateAction::EditPortfolio).await?;
+        let document = Self::document(db, candidate_id, slug).await?;
+
+        Self::delete_cache_item(candidate_id, &document).await
     }
 
     /// Removes all files from cache
     pub async fn delete_cache(candidate_id: i32) -> Result<(), ServiceError> {
-        let cache_path = Self::get_file_store_path().join(&candidate_id.to_string()).join("cache");
-        tokio::fs::remove_dir_all(&cache_path).await?;
+        let store = storage::from_env()?;
+        let cache_prefix = Self::cache_prefix(candidate_id);
+
+        store.delete_prefix(&cache_prefix).await?;
         // Recreate blank cache directory
-        tokio::fs::create_dir_all(&cache_path).await?;
+        store.create_prefix(&cache_prefix).await?;
 
         Ok(())
     }
 
 
-    /// Move files from cache to final directory and delete cache afterwards
-    pub async fn submit(candidate: &candidate::Model, db: &DbConn) -> Result<(), ServiceError> {
+    /// Move files from cache to final directory and delete cache afterwards.
+    /// Manifest of the submitted files is recorded with the version and returned as a signed receipt
+    pub async fn submit(
+        candidate: &candidate::Model,
+        db: &DbConn,
+        audit: &AuditContext,
+    ) -> Result<SubmissionReceipt, ServiceError> {
         let candidate_id = candidate.id;
-        let path = Self::get_file_store_path().join(&candidate_id.to_string()).to_path_buf();
-        let cache_path = path.join("cache");
+        TimelineService::ensure_allowed(db, candidate_id, CandidateAction::EditPortfolio).await?;
 
-        if Self::is_portfolio_prepared(candidate_id).await == false {
+        if Self::is_portfolio_prepared(db, candidate_id).await == false {
             return Err(ServiceError::IncompletePortfolio);
         }
         
         info!("PORTFOLIO {} SUBMIT STARTED", candidate.id);
 
-        let mut archive = tokio::fs::File::create(path.join(FileType::PortfolioZip.as_str())).await?;
-        let mut writer = async_zip::tokio::write::ZipFileWriter::with_tokio(&mut archive);
-        let mut buffer = vec![vec![], vec![], vec![]];
-
-        let filenames = vec![FileType::CoverLetterPdf, FileType::PortfolioLetterPdf, FileType::PortfolioZip];
-        for (index, entry) in buffer.iter_mut().enumerate() {
-            let filename = filenames[index];
-            let mut entry_file = tokio::fs::File::open(cache_path.join(filename.as_str())).await?;
-
-            entry_file.read_to_end(entry).await?;
-        }
-
-        Self::delete_cache(candidate_id).await?;
-
-        for (index, entry) in buffer.iter_mut().enumerate() {
-            let filename = filenames[index];
-            let builder = async_zip::ZipEntryBuilder::new(
-                filename.to_string().into(),
-                async_zip::Compression::Deflate,
-            );
-
-            writer.write_entry_whole(builder, &entry).await?;
-        }
-
-        writer.close().await?;
-        archive.shutdown().await?;
-
         let applications_pubkeys: Vec<String> = Query::find_applications_by_candidate_id(db, candidate_id)
             .await?
             .iter()
             .map(|a| a.public_key.to_owned()).collect();
         let admin_public_keys = Query::get_all_admin_public_keys(db).await?;
+        let documents = Self::documents(db, candidate_id).await?;
 
         let mut recipients = vec![];
         recipients.append(&mut admin_public_keys.iter().map(|s| &**s).collect());
         recipients.append(&mut applications_pubkeys.iter().map(|s| &**s).collect());
 
-        let final_path = path.join(FileType::PortfolioZip.as_str());
-
-        crypto::encrypt_file_with_recipients(
-            &final_path,
-            &final_path.with_extension("age"),
-            recipients,
+        let store = storage::from_env()?;
+        let version = Self::next_version(db, candidate_id).await?;
+        let version_key = Self::version_key(candidate_id, version);
+        // Archive is written into one end of the pipe while the store uploads the other one
+        let (mut archive_reader, archive_writer) = tokio::io::duplex(PIPE_BUFFER_SIZE);
+
+        let res = tokio::try_join!(
+            Self::write_encrypted_archive(&*store, candidate_id, &documents, archive_writer, recipients),
+            store.put(&version_key, &mut archive_reader),
+        );
+        let manifest = match res {
+            Ok((manifest, _)) => manifest,
+            Err(e) => {
+                warn!("PORTFOLIO {} SUBMIT FAILED: {}", candidate_id, e);
+                return Err(e);
+            },
+        };
+        // Hash of what the store really holds, not of what was sent to it
+        let (ciphertext_hash, ciphertext_size) = crypto::sha256_stream(store.get(&version_key).await?).await?;
+
+        let portfolio_version = Mutation::create_portfolio_version(
+            db,
+            candidate_id,
+            version,
+            manifest.clone(),
+            ciphertext_hash,
+            ciphertext_size as i64,
         ).await?;
-        tokio::fs::remove_file(final_path).await?;
-
+        store.put(&Self::current_version_key(candidate_id), &mut version.to_string().as_bytes()).await?;
+        Self::delete_cache(candidate_id).await?;
         
         if !Self::is_portfolio_submitted(candidate_id).await {
             return Err(ServiceError::PortfolioWriteError)
         }
+        Self::delete_old_versions(db, &*store, candidate_id).await?;
 
-        info!("PORTFOLIO {} SUBMIT FINISHED", candidate_id);
+        info!("PORTFOLIO {} VERSION {} SUBMIT FINISHED", candidate_id, version);
 
-        Ok(())
+        // Portfolio is submitted by the candidate, the logged in application is the target
+        let target_id = match audit.actor {
+            AuditActor::Application(id) => Some(id),
+            AuditActor::Admin(_) => None,
+        };
+        AuditService::log(db, audit, AuditAction::Submit, target_id).await?;
+        NotificationService::enqueue(db, candidate_id, NotificationKind::PortfolioSubmitted).await;
+
+        let mut receipt = SubmissionReceipt {
+            candidate_id,
+            version,
+            submitted_at: portfolio_version.created_at,
+            files: manifest.files,
+            archive_hash: manifest.hash,
+            archive_size: manifest.size,
+            signature: None,
+        };
+        match ReceiptKey::from_env()? {
+            Some(key) => key.sign(&mut receipt),
+            None => warn!("PORTFOLIO {} RECEIPT NOT SIGNED, PORTFOLIO_RECEIPT_KEY IS NOT SET", candidate_id),
+        }
+        Ok(receipt)
     }
 
-    /// Delete PORTFOLIO.age file
-    pub async fn delete_portfolio(candidate_id: i32) -> Result<(), ServiceError> {
-        info!("PORTFOLIO {} DELETE STARTED", candidate_id);
-        let path = Self::get_file_store_path().join(&candidate_id.to_string()).to_path_buf();
+    /// Streams cached documents into a zip archive, which is encrypted on the fly and written to `archive`.
+    /// Optional documents which were not uploaded are left out. Only one chunk of each file is held in memory at a time.
+    /// Returns hashes and sizes of the files and of the zip archive before encryption
+    async fn write_encrypted_archive(
+        store: &dyn PortfolioStore,
+        candidate_id: i32,
+        documents: &[DocumentType],
+        archive: DuplexStream,
+        recipients: Vec<&str>,
+    ) -> Result<ArchiveManifest, ServiceError> {
+        let encrypt_writer = crypto::age_encrypt_writer(archive, &recipients).await?;
+        let mut writer = async_zip::base::write::ZipFileWriter::new(crypto::HashWriter::new(encrypt_writer));
+        let mut files = vec![];
+
+        for document in documents.iter() {
+            let key = Self::cache_key(candidate_id, &document.file_name);
+            if !document.required && !store.exists(&key).await? {
+                continue;
+            }
+            let entry_file = store.get(&key).await?;
+            let builder = async_zip::ZipEntryBuilder::new(
+                document.file_name.to_owned().into(),
+                async_zip::Compression::Deflate,
+            );
+
+            let mut entry_writer = crypto::HashWriter::new(writer.write_entry_stream(builder).await?);
+            futures::io::copy(entry_file.compat(), &mut entry_writer).await?;
+            let (entry_writer, hash, size) = entry_writer.finish();
+            entry_writer.close().await?;
+
+            files.push(
+                ManifestFile {
+                    name: document.file_name.to_owned(),
+                    hash,
+                    size: size as i64,
+                }
+            );
+        }
+
+        let (mut encrypt_writer, hash, size) = writer.close().await?.finish();
+        // Writes the final age chunk and closes the pipe
+        encrypt_writer.close().await?;
 
-        let portfolio_path = path.join(FileType::PortfolioZip.as_str());
-        let portfolio_age_path = portfolio_path.with_extension("age");
+        Ok(
+            ArchiveManifest {
+                files,
+                hash,
+                size: size as i64,
+            }
+        )
+    }
+
+    async fn next_version(db: &DbConn, candidate_id: i32) -> Result<i32, ServiceError> {
+        Ok(
+            Query::list_portfolio_versions(db, candidate_id)
+                .await?
+                .last()
+                .map(|v| v.version + 1)
+                .unwrap_or(1)
+        )
+    }
 
-        if tokio::fs::metadata(&portfolio_path).await.is_ok() {
-            tokio::fs::remove_file(&portfolio_path).await?;
+    /// Moves portfolio submitted before versioning into the version history, so that it is kept after withdrawal.
+    /// Hash and size of the archive are computed by decrypting it, the list of files is unknown
+    async fn migrate_legacy_portfolio(
+        db: &DbConn,
+        store: &dyn PortfolioStore,
+        candidate_id: i32,
+        private_key: &str,
+    ) -> Result<(), ServiceError> {
+        let legacy_key = Self::final_key(candidate_id, LEGACY_PORTFOLIO);
+        if Self::current_version(store, candidate_id).await?.is_some() || !store.exists(&legacy_key).await? {
+            return Ok(());
         }
 
-        if tokio::fs::metadata(&portfolio_age_path).await.is_ok() {
-            tokio::fs::remove_file(&portfolio_age_path).await?;
+        let version = Self::next_version(db, candidate_id).await?;
+        let version_key = Self::version_key(candidate_id, version);
+        store.put(&version_key, &mut store.get(&legacy_key).await?).await?;
+
+        let plain_portfolio = crypto::age_decrypt_reader(store.get(&version_key).await?, private_key).await?;
+        let (hash, size) = crypto::sha256_stream(plain_portfolio).await?;
+        let (ciphertext_hash, ciphertext_size) = crypto::sha256_stream(store.get(&version_key).await?).await?;
+        Mutation::create_portfolio_version(
+            db,
+            candidate_id,
+            version,
+            ArchiveManifest { files: vec![], hash, size: size as i64 },
+            ciphertext_hash,
+            ciphertext_size as i64,
+        ).await?;
+        // Copy is recorded, the original can go
+        store.delete(&legacy_key).await?;
+
+        info!("PORTFOLIO {} LEGACY PORTFOLIO MIGRATED TO VERSION {}", candidate_id, version);
+        Ok(())
+    }
+
+    /// Deletes the oldest versions above `PORTFOLIO_MAX_VERSIONS`, the submitted version is always kept
+    async fn delete_old_versions(
+        db: &DbConn,
+        store: &dyn PortfolioStore,
+        candidate_id: i32,
+    ) -> Result<(), ServiceError> {
+        let max_versions = Self::max_versions()?;
+        let current = Self::current_version(store, candidate_id).await?;
+        let versions: Vec<portfolio_version::Model> = Query::list_portfolio_versions(db, candidate_id)
+            .await?
+            .into_iter()
+            .filter(|v| Some(v.version) != current)
+            .collect();
+
+        let excess = (versions.len() + 1).saturating_sub(max_versions);
+        for version in versions.into_iter().take(excess) {
+            store.delete(&Self::version_key(candidate_id, version.version)).await?;
+            info!("PORTFOLIO {} VERSION {} DELETED", candidate_id, version.version);
+            Mutation::delete_portfolio_version(db, version).await?;
         }
+        Ok(())
+    }
+
+    /// Withdraws the submitted portfolio, so that a new one can be uploaded.
+    /// The submitted version is kept in the version history, portfolio submitted before versioning is moved there first
+    pub async fn delete_portfolio(db: &DbConn, candidate_id: i32, private_key: String) -> Result<(), ServiceError> {
+        TimelineService::ensure_allowed(db, candidate_id, CandidateAction::EditPortfolio).await?;
+        info!("PORTFOLIO {} DELETE STARTED", candidate_id);
+        let store = storage::from_env()?;
+
+        Self::migrate_legacy_portfolio(db, &*store, candidate_id, &private_key).await?;
+        store.delete(&Self::current_version_key(candidate_id)).await?;
 
         info!("PORTFOLIO {} DELETE FINISHED", candidate_id);
 
@@ -350,8 +554,9 @@ impl PortfolioService {
     pub async fn delete_candidate_root(candidate_id: i32) -> Result<(), ServiceError> {
         warn!("CANDIDATE {} ROOT DIRECTORY DELETE STARTED", candidate_id);
 
-        let path = Self::get_file_store_path().join(&candidate_id.to_string()).to_path_buf();
-        tokio::fs::remove_dir_all(path).await?;
+        storage::from_env()?
+            .delete_prefix(&candidate_id.to_string())
+            .await?;
 
         warn!("CANDIDATE {} ROOT DIRECTORY DELETE FINISHED", candidate_id);
 
@@ -360,67 +565,208 @@ impl PortfolioService {
 
     /// Returns true if portfolio is submitted
     pub async fn is_portfolio_submitted(candidate_id: i32) -> bool {
-        let path = Self::get_file_store_path().join(&candidate_id.to_string()).to_path_buf();
+        let Ok(store) = storage::from_env() else {
+            return false;
+        };
 
-        tokio::fs::metadata(path.join(FileType::Age.as_str())).await.is_ok()
+        Self::current_key(&*store, candidate_id)
+            .await
+            .is_ok_and(|key| key.is_some())
     }
 
     /// Returns decrypted portfolio zip as Vec of bytes
     pub async fn get_portfolio(candidate_id: i32, private_key: String) -> Result<Vec<u8>, ServiceError> {
         info!("PORTFOLIO {} DECRYPT STARTED", candidate_id);
-        let path = Self::get_file_store_path()
-            .join(&candidate_id.to_string())
-            .join(FileType::Age.as_str())
-            .to_path_buf();
 
-        let buffer = crypto::decrypt_file_with_private_key_as_buffer(path, &private_key).await?;
+        let mut reader = Self::get_portfolio_stream(candidate_id, private_key).await?;
+        let mut buffer = vec![];
+        reader.read_to_end(&mut buffer).await?;
 
         info!("PORTFOLIO {} DECRYPT FINISHED", candidate_id);
         Ok(buffer)
     }
 
-    pub async fn reencrypt_portfolio(candidate_id: i32,
+    /// Returns reader over decrypted portfolio zip, decrypted lazily as it is read
+    pub async fn get_portfolio_stream(
+        candidate_id: i32,
         private_key: String,
-        recipients: &Vec<String>
+    ) -> Result<impl tokio::io::AsyncRead + Send + Unpin, ServiceError> {
+        info!("PORTFOLIO {} DECRYPT STREAM OPENED", candidate_id);
+        let encrypted = Self::get_encrypted_portfolio_stream(candidate_id).await?;
+
+        crypto::age_decrypt_reader(encrypted, &private_key).await
+    }
+
+    /// Returns reader over portfolio exactly as it is stored, still encrypted
+    pub async fn get_encrypted_portfolio_stream(candidate_id: i32) -> Result<storage::StoreReader, ServiceError> {
+        let store = storage::from_env()?;
+        let key = Self::current_key(&*store, candidate_id)
+            .await?
+            .ok_or(ServiceError::PortfolioVersionNotFound)?;
+
+        store.get(&key).await
+    }
+
+    /// Returns reader over one decrypted version of the portfolio, replaced and withdrawn versions included
+    pub async fn get_portfolio_version_stream(
+        db: &DbConn,
+        candidate_id: i32,
+        version: i32,
+        private_key: String,
+    ) -> Result<impl tokio::io::AsyncRead + Send + Unpin, ServiceError> {
+        Query::find_portfolio_version(db, candidate_id, version)
+            .await?
+            .ok_or(ServiceError::PortfolioVersionNotFound)?;
+
+        info!("PORTFOLIO {} VERSION {} DECRYPT STREAM OPENED", candidate_id, version);
+        let encrypted = storage::from_env()?
+            .get(&Self::version_key(candidate_id, version))
+            .await?;
+
+        crypto::age_decrypt_reader(encrypted, &private_key).await
+    }
+
+    /// Same as `get_portfolio_stream`, or `get_portfolio_version_stream` with `version`,
+    /// the download is recorded in the audit log
+    pub async fn download_portfolio(
+        db: &DbConn,
+        application: &application::Model,
+        version: Option<i32>,
+        private_key: String,
+        audit: &AuditContext,
+    ) -> Result<Box<dyn tokio::io::AsyncRead + Send + Unpin>, ServiceError> {
+        let portfolio: Box<dyn tokio::io::AsyncRead + Send + Unpin> = match version {
+            Some(version) => Box::new(
+                Self::get_portfolio_version_stream(db, application.candidate_id, version, private_key).await?
+            ),
+            None => Box::new(Self::get_portfolio_stream(application.candidate_id, private_key).await?),
+        };
+        AuditService::log(db, audit, AuditAction::DownloadPortfolio, Some(application.id)).await?;
+
+        Ok(portfolio)
+    }
+
+    /// Stored versions, oldest first
+    pub async fn list_versions(
+        db: &DbConn,
+        candidate_id: i32,
+    ) -> Result<Vec<PortfolioVersionResponse>, ServiceError> {
+        let current = Self::current_version(&*storage::from_env()?, candidate_id).await?;
+
+        Ok(
+            Query::list_portfolio_versions(db, candidate_id)
+                .await?
+                .into_iter()
+                .map(|v| PortfolioVersionResponse::new(v, current))
+                .collect()
+        )
+    }
+
+    /// Compares every stored version against the ciphertext hash recorded at submission,
+    /// detects versions corrupted or replaced in the store
+    pub async fn verify_integrity(
+        db: &DbConn,
+        store: &dyn PortfolioStore,
+    ) -> Result<Vec<IntegrityReport>, ServiceError> {
+        let mut reports = vec![];
+        for version in Query::list_all_portfolio_versions(db).await? {
+            let key = Self::version_key(version.candidate_id, version.version);
+            let actual_hash = if store.exists(&key).await? {
+                Some(crypto::sha256_stream(store.get(&key).await?).await?.0)
+            } else {
+                None
+            };
+
+            let status = match (&version.ciphertext_hash, &actual_hash) {
+                (_, None) => IntegrityStatus::Missing,
+                (None, Some(_)) => IntegrityStatus::NoManifest,
+                (Some(expected), Some(actual)) if expected == actual => IntegrityStatus::Ok,
+                (Some(_), Some(_)) => IntegrityStatus::Mismatch,
+            };
+            if status == IntegrityStatus::Mismatch || status == IntegrityStatus::Missing {
+                warn!("PORTFOLIO {} VERSION {} INTEGRITY CHECK FAILED: {:?}", version.candidate_id, version.version, status);
+            }
+
+            reports.push(IntegrityReport {
+                candidate_id: version.candidate_id,
+                version: version.version,
+                status,
+                expected_hash: version.ciphertext_hash,
+                actual_hash,
+            });
+        }
+        info!("PORTFOLIO INTEGRITY CHECKED {} VERSIONS", reports.len());
+
+        Ok(reports)
+    }
+
+    /// Re-encrypts all stored versions to `recipients`.
+    /// Content and manifests of the versions don't change, new ciphertext hashes are recorded through `db`
+    pub async fn reencrypt_portfolio<C: ConnectionTrait>(
+        db: &C,
+        candidate_id: i32,
+        private_key: String,
+        recipients: &[String]
     ) -> Result<(), ServiceError> {
         info!("PORTFOLIO {} REENCRYPT STARTED", candidate_id);
-        let path = Self::get_file_store_path()
-            .join(&candidate_id.to_string())
-            .join(FileType::Age.as_str())
-            .to_path_buf();
-
-        let plain_portfolio = crypto::decrypt_file_with_private_key_as_buffer(
-            path.to_owned(),
-            &private_key
-        ).await?;
+        let store = storage::from_env()?;
+        let recipients: Vec<&str> = recipients.iter().map(|s| s.as_str()).collect();
 
-        
-        let enc_portfolio= crypto::encrypt_buffer_with_recipients(
-            &plain_portfolio, 
-            recipients
-        ).await?;
-        
-        tokio::fs::remove_file(path.to_owned()).await?;
-        
-        tokio::fs::write(path, enc_portfolio).await?;
+        for version in Query::list_portfolio_versions(db, candidate_id).await? {
+            let key = Self::version_key(candidate_id, version.version);
+            Self::reencrypt_object(&*store, &key, &private_key, &recipients).await?;
+
+            let (ciphertext_hash, ciphertext_size) = crypto::sha256_stream(store.get(&key).await?).await?;
+            Mutation::update_portfolio_version_ciphertext(db, version, ciphertext_hash, ciphertext_size as i64).await?;
+        }
+
+        let legacy_key = Self::final_key(candidate_id, LEGACY_PORTFOLIO);
+        if store.exists(&legacy_key).await? {
+            Self::reencrypt_object(&*store, &legacy_key, &private_key, &recipients).await?;
+        }
 
         info!("PORTFOLIO {} REENCRYPT FINISHED", candidate_id);
 
         Ok(())
     }
+
+    async fn reencrypt_object(
+        store: &dyn PortfolioStore,
+        key: &str,
+        private_key: &str,
+        recipients: &Vec<&str>,
+    ) -> Result<(), ServiceError> {
+        let plain_portfolio = crypto::age_decrypt_reader(store.get(key).await?, private_key).await?;
+        let (mut portfolio_reader, portfolio_writer) = tokio::io::duplex(PIPE_BUFFER_SIZE);
+
+        // Old portfolio stays intact until the new one is fully written
+        tokio::try_join!(
+            crypto::encrypt_stream_with_recipients(plain_portfolio, portfolio_writer, recipients),
+            store.put(key, &mut portfolio_reader),
+        )?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
-mod tests {
+pub mod tests {
     use serial_test::serial;
 
-    use crate::{services::{portfolio_service::{PortfolioService, FileType}, candidate_service::{CandidateService, tests::put_user_data}}, utils::db::get_memory_sqlite_connection, crypto};
+    use base64::Engine;
+
+    use sea_orm::DbConn;
+
+    use crate::{error::ServiceError, services::{portfolio_service::{PortfolioService, SubmissionProgress, SubmissionStatus}, candidate_service::{CandidateService, tests::put_user_data}}, utils::{content::{tests::{pdf, zip}, ContentIssue}, db::get_memory_sqlite_connection}, crypto, storage, models::{audit::{AuditActor, AuditContext}, document::default_documents, portfolio_version::IntegrityStatus, receipt::ReceiptKey}};
     use std::path::PathBuf;
 
     const APPLICATION_ID: i32 = 103151;
 
+    pub fn audit_context() -> AuditContext {
+        AuditContext::new(AuditActor::Application(APPLICATION_ID), None)
+    }
+
     #[cfg(test)]
-    async fn create_data_store_temp_dir(application_id: i32) -> (PathBuf, PathBuf, PathBuf) {
+    pub async fn create_data_store_temp_dir(application_id: i32) -> (PathBuf, PathBuf, PathBuf) {
         let random_number: u32 = rand::Rng::gen(&mut rand::thread_rng());
         
         let temp_dir = std::env::temp_dir().join("portfolio_test_tempdir").join(random_number.to_string());
@@ -435,12 +781,28 @@ mod tests {
     }
 
     #[cfg(test)]
-    async fn clear_data_store_temp_dir(temp_dir: PathBuf) {
+    pub async fn clear_data_store_temp_dir(temp_dir: PathBuf) {
         tokio::fs::remove_dir_all(temp_dir).await.unwrap();
 
         std::env::remove_var("PORTFOLIO_STORE_PATH");
     }
 
+    /// Portfolio zip with content depending on `seed`
+    pub async fn portfolio_zip(seed: u8) -> Vec<u8> {
+        zip(&[("portfolio.txt", vec![seed])]).await
+    }
+
+    /// Uploads all default documents, `seed` changes the content of the zip
+    pub async fn put_portfolio_documents(db: &DbConn, candidate_id: i32, seed: u8) {
+        for document in default_documents() {
+            let data = match document.slug.as_str() {
+                "portfolio_zip" => portfolio_zip(seed).await,
+                _ => pdf(false),
+            };
+            PortfolioService::add_document_to_cache(db, candidate_id, &document.slug, data).await.unwrap();
+        }
+    }
+
     #[tokio::test]
     #[serial]
     async fn test_folder_creation() {
@@ -449,7 +811,7 @@ mod tests {
         let temp_dir = std::env::temp_dir().join("portfolio_test_tempdir").join("create_folder");
         std::env::set_var("PORTFOLIO_STORE_PATH", temp_dir.to_str().unwrap());
 
-        let candidate = CandidateService::create(&db, "".to_string())
+        let candidate = CandidateService::create(&db, "".to_string(), None)
             .await
             .ok()
             .unwrap();
@@ -464,10 +826,16 @@ mod tests {
     #[serial]
     async fn test_write_portfolio_file() {
         let (temp_dir, _, application_cache_dir) = create_data_store_temp_dir(APPLICATION_ID).await;
+        let document = &default_documents()[1];
 
-        PortfolioService::write_portfolio_file(APPLICATION_ID, vec![0], crate::services::portfolio_service::FileType::PortfolioLetterPdf).await.unwrap();
+        PortfolioService::write_portfolio_file(APPLICATION_ID, pdf(false), document).await.unwrap();
         
-        assert!(tokio::fs::metadata(application_cache_dir.join(FileType::PortfolioLetterPdf.as_str())).await.is_ok());
+        assert!(tokio::fs::metadata(application_cache_dir.join(&document.file_name)).await.is_ok());
+
+        assert!(matches!(
+            PortfolioService::write_portfolio_file(APPLICATION_ID, portfolio_zip(0).await, document).await,
+            Err(ServiceError::InvalidContent(_))
+        ));
 
         clear_data_store_temp_dir(temp_dir).await;
     }
@@ -475,23 +843,59 @@ mod tests {
     #[tokio::test]
     #[serial]
     async fn test_add_cover_letter_to_cache() {
+        let db = get_memory_sqlite_connection().await;
         let (temp_dir, _, application_cache_dir) = create_data_store_temp_dir(APPLICATION_ID).await;
 
-        PortfolioService::add_cover_letter_to_cache(APPLICATION_ID, vec![0]).await.unwrap();
+        PortfolioService::add_document_to_cache(&db, APPLICATION_ID, "cover_letter", pdf(false)).await.unwrap();
         
         assert!(tokio::fs::metadata(application_cache_dir.join("MOTIVACNI_DOPIS.pdf")).await.is_ok());
 
         clear_data_store_temp_dir(temp_dir).await;
     }
 
+    #[tokio::test]
+    #[serial]
+    async fn test_quarantine_infected_file() {
+        let db = get_memory_sqlite_connection().await;
+        let (temp_dir, application_dir, application_cache_dir) = create_data_store_temp_dir(APPLICATION_ID).await;
+        std::env::set_var("PORTFOLIO_SCANNER", "fake");
+
+        PortfolioService::add_document_to_cache(&db, APPLICATION_ID, "portfolio_letter", pdf(false)).await.unwrap();
+        let infected = b"X5O!P%@AP[4\\PZX54(P^)7CC)7}$EICAR-STANDARD-ANTIVIRUS-TEST-FILE!$H+H*".to_vec();
+        assert!(matches!(
+            PortfolioService::add_document_to_cache(&db, APPLICATION_ID, "cover_letter", infected).await,
+            Err(ServiceError::MalwareDetected(_))
+        ));
+
+        assert!(tokio::fs::metadata(application_cache_dir.join("MOTIVACNI_DOPIS.pdf")).await.is_err());
+        assert!(tokio::fs::metadata(application_dir.join("quarantine").join("MOTIVACNI_DOPIS.pdf")).await.is_ok());
+        assert_eq!(
+            PortfolioService::get_submission_status(&db, APPLICATION_ID).await.unwrap(),
+            SubmissionStatus {
+                progress: SubmissionProgress::SomeInCache(vec!["portfolio_letter".to_string()]),
+                quarantined: vec!["cover_letter".to_string()],
+            }
+        );
+
+        PortfolioService::add_document_to_cache(&db, APPLICATION_ID, "cover_letter", pdf(false)).await.unwrap();
+        assert_eq!(
+            PortfolioService::get_submission_progress(&db, APPLICATION_ID).await.unwrap(),
+            SubmissionProgress::SomeInCache(vec!["cover_letter".to_string(), "portfolio_letter".to_string()])
+        );
+
+        std::env::remove_var("PORTFOLIO_SCANNER");
+        clear_data_store_temp_dir(temp_dir).await;
+    }
+
     #[tokio::test]
     #[serial]
     async fn test_delete_cover_letter_from_cache() {
+        let db = get_memory_sqlite_connection().await;
         let (temp_dir, _, application_cache_dir) = create_data_store_temp_dir(APPLICATION_ID).await;
 
-        PortfolioService::add_cover_letter_to_cache(APPLICATION_ID, vec![0]).await.unwrap();
+        PortfolioService::add_document_to_cache(&db, APPLICATION_ID, "cover_letter", pdf(false)).await.unwrap();
         
-        PortfolioService::delete_cover_letter_from_cache(APPLICATION_ID).await.unwrap();
+        PortfolioService::delete_document_from_cache(&db, APPLICATION_ID, "cover_letter").await.unwrap();
 
         assert!(tokio::fs::metadata(application_cache_dir.join("MOTIVACNI_DOPIS.pdf")).await.is_err());
 
@@ -501,11 +905,12 @@ mod tests {
     #[tokio::test]
     #[serial]
     async fn test_is_cover_letter() {
+        let db = get_memory_sqlite_connection().await;
         let (temp_dir, _, _) = create_data_store_temp_dir(APPLICATION_ID).await;
 
-        PortfolioService::add_cover_letter_to_cache(APPLICATION_ID, vec![0]).await.unwrap();
+        PortfolioService::add_document_to_cache(&db, APPLICATION_ID, "cover_letter", pdf(false)).await.unwrap();
         
-        assert!(PortfolioService::is_cover_letter(APPLICATION_ID).await);
+        assert!(PortfolioService::is_document_cached(&db, APPLICATION_ID, "cover_letter").await);
 
         clear_data_store_temp_dir(temp_dir).await;
     }
@@ -513,11 +918,12 @@ mod tests {
     #[tokio::test]
     #[serial]
     async fn test_delete_cache_item() {
+        let db = get_memory_sqlite_connection().await;
         let (temp_dir, _, application_cache_dir) = create_data_store_temp_dir(APPLICATION_ID).await;
 
-        PortfolioService::add_cover_letter_to_cache(APPLICATION_ID, vec![0]).await.unwrap();
+        PortfolioService::add_document_to_cache(&db, APPLICATION_ID, "cover_letter", pdf(false)).await.unwrap();
 
-        PortfolioService::delete_cache_item(APPLICATION_ID, FileType::CoverLetterPdf).await.unwrap();
+        PortfolioService::delete_cache_item(APPLICATION_ID, &default_documents()[0]).await.unwrap();
 
         assert!(tokio::fs::metadata(application_cache_dir.join("MOTIVACNI_DOPIS.pdf")).await.is_err());
         
@@ -528,9 +934,10 @@ mod tests {
     #[tokio::test]
     #[serial]
     async fn test_add_portfolio_letter_to_cache() {
+        let db = get_memory_sqlite_connection().await;
         let (temp_dir, _, application_cache_dir) = create_data_store_temp_dir(APPLICATION_ID).await;
         
-        PortfolioService::add_portfolio_letter_to_cache(APPLICATION_ID, vec![0]).await.unwrap();
+        PortfolioService::add_document_to_cache(&db, APPLICATION_ID, "portfolio_letter", pdf(false)).await.unwrap();
         
         assert!(tokio::fs::metadata(application_cache_dir.join("PORTFOLIO.pdf")).await.is_ok());
 
@@ -540,11 +947,12 @@ mod tests {
     #[tokio::test]
     #[serial]
     async fn test_delete_portfolio_letter_from_cache() {
+        let db = get_memory_sqlite_connection().await;
         let (temp_dir, _, application_cache_dir) = create_data_store_temp_dir(APPLICATION_ID).await;
 
-        PortfolioService::add_portfolio_letter_to_cache(APPLICATION_ID, vec![0]).await.unwrap();
+        PortfolioService::add_document_to_cache(&db, APPLICATION_ID, "portfolio_letter", pdf(false)).await.unwrap();
         
-        PortfolioService::delete_portfolio_letter_from_cache(APPLICATION_ID).await.unwrap();
+        PortfolioService::delete_document_from_cache(&db, APPLICATION_ID, "portfolio_letter").await.unwrap();
 
         assert!(tokio::fs::metadata(application_cache_dir.join("PORTFOLIO.pdf")).await.is_err());
 
@@ -554,11 +962,12 @@ mod tests {
     #[tokio::test]
     #[serial]
     async fn test_is_portfolio_letter() {
+        let db = get_memory_sqlite_connection().await;
         let (temp_dir, _, _) = create_data_store_temp_dir(APPLICATION_ID).await;
 
-        PortfolioService::add_portfolio_letter_to_cache(APPLICATION_ID, vec![0]).await.unwrap();
+        PortfolioService::add_document_to_cache(&db, APPLICATION_ID, "portfolio_letter", pdf(false)).await.unwrap();
         
-        assert!(PortfolioService::is_portfolio_letter(APPLICATION_ID).await);
+        assert!(PortfolioService::is_document_cached(&db, APPLICATION_ID, "portfolio_letter").await);
 
         clear_data_store_temp_dir(temp_dir).await;
     }
@@ -566,9 +975,10 @@ mod tests {
     #[tokio::test]
     #[serial]
     async fn test_add_portfolio_zip_to_cache() {
+        let db = get_memory_sqlite_connection().await;
         let (temp_dir, _, application_cache_dir) = create_data_store_temp_dir(APPLICATION_ID).await;
 
-        PortfolioService::add_portfolio_zip_to_cache(APPLICATION_ID, vec![0]).await.unwrap();
+        PortfolioService::add_document_to_cache(&db, APPLICATION_ID, "portfolio_zip", portfolio_zip(0).await).await.unwrap();
         
         assert!(tokio::fs::metadata(application_cache_dir.join("PORTFOLIO.zip")).await.is_ok());
 
@@ -578,11 +988,12 @@ mod tests {
     #[tokio::test]
     #[serial]
     async fn test_delete_portfolio_zip_from_cache() {
+        let db = get_memory_sqlite_connection().await;
         let (temp_dir, _, application_cache_dir) = create_data_store_temp_dir(APPLICATION_ID).await;
 
-        PortfolioService::add_portfolio_zip_to_cache(APPLICATION_ID, vec![0]).await.unwrap();
+        PortfolioService::add_document_to_cache(&db, APPLICATION_ID, "portfolio_zip", portfolio_zip(0).await).await.unwrap();
         
-        PortfolioService::delete_portfolio_zip_from_cache(APPLICATION_ID).await.unwrap();
+        PortfolioService::delete_document_from_cache(&db, APPLICATION_ID, "portfolio_zip").await.unwrap();
 
         assert!(tokio::fs::metadata(application_cache_dir.join("PORTFOLIO.zip")).await.is_err());
 
@@ -592,11 +1003,41 @@ mod tests {
     #[tokio::test]
     #[serial]
     async fn test_is_portfolio_zip() {
+        let db = get_memory_sqlite_connection().await;
         let (temp_dir, _, _) = create_data_store_temp_dir(APPLICATION_ID).await;
 
-        PortfolioService::add_portfolio_zip_to_cache(APPLICATION_ID, vec![0]).await.unwrap();
+        PortfolioService::add_document_to_cache(&db, APPLICATION_ID, "portfolio_zip", portfolio_zip(0).await).await.unwrap();
         
-        assert!(PortfolioService::is_portfolio_zip(APPLICATION_ID).await);
+        assert!(PortfolioService::is_document_cached(&db, APPLICATION_ID, "portfolio_zip").await);
+
+        clear_data_store_temp_dir(temp_dir).await;
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_add_document_validation() {
+        let db = get_memory_sqlite_connection().await;
+        let (temp_dir, _, _) = create_data_store_temp_dir(APPLICATION_ID).await;
+
+        assert!(matches!(
+            PortfolioService::add_document_to_cache(&db, APPLICATION_ID, "photo", pdf(false)).await,
+            Err(ServiceError::DocumentNotFound)
+        ));
+        match PortfolioService::add_document_to_cache(&db, APPLICATION_ID, "cover_letter", portfolio_zip(0).await).await {
+            Err(ServiceError::InvalidContent(issues)) => assert!(matches!(issues[..], [ContentIssue::DisallowedType { .. }])),
+            other => panic!("Expected invalid content, got {:?}", other),
+        }
+        let mut large = pdf(false);
+        large.resize(11_000_001, b' ');
+        assert!(matches!(
+            PortfolioService::add_document_to_cache(&db, APPLICATION_ID, "cover_letter", large).await,
+            Err(ServiceError::UploadTooLarge)
+        ));
+        assert!(!PortfolioService::is_document_cached(&db, APPLICATION_ID, "cover_letter").await);
+
+        let statuses = PortfolioService::document_statuses(&db, APPLICATION_ID).await.unwrap();
+        assert_eq!(statuses.iter().map(|s| s.document.slug.as_str()).collect::<Vec<_>>(), vec!["cover_letter", "portfolio_letter", "portfolio_zip"]);
+        assert!(statuses.iter().all(|s| !s.uploaded && !s.quarantined));
 
         clear_data_store_temp_dir(temp_dir).await;
     }
@@ -604,23 +1045,22 @@ mod tests {
     #[tokio::test]
     #[serial]
     async fn test_is_portfolio_prepared() {
+        let db = get_memory_sqlite_connection().await;
         let (temp_dir, _, _) = create_data_store_temp_dir(APPLICATION_ID).await;
 
-        PortfolioService::add_cover_letter_to_cache(APPLICATION_ID, vec![0]).await.unwrap();
-        PortfolioService::add_portfolio_letter_to_cache(APPLICATION_ID, vec![0]).await.unwrap();
-        PortfolioService::add_portfolio_zip_to_cache(APPLICATION_ID, vec![0]).await.unwrap();
+        put_portfolio_documents(&db, APPLICATION_ID, 0).await;
 
-        assert!(PortfolioService::is_portfolio_prepared(APPLICATION_ID).await);
+        assert!(PortfolioService::is_portfolio_prepared(&db, APPLICATION_ID).await);
 
         clear_data_store_temp_dir(temp_dir).await;
 
         let (temp_dir, _, _) = create_data_store_temp_dir(APPLICATION_ID).await;
 
-        PortfolioService::add_cover_letter_to_cache(APPLICATION_ID, vec![0]).await.unwrap();
-        //PortfolioService::add_portfolio_letter_to_cache(APPLICATION_ID, vec![0]).await.unwrap();
-        PortfolioService::add_portfolio_zip_to_cache(APPLICATION_ID, vec![0]).await.unwrap();
+        PortfolioService::add_document_to_cache(&db, APPLICATION_ID, "cover_letter", pdf(false)).await.unwrap();
+        //PortfolioService::add_document_to_cache(&db, APPLICATION_ID, "portfolio_letter", pdf(false)).await.unwrap();
+        PortfolioService::add_document_to_cache(&db, APPLICATION_ID, "portfolio_zip", portfolio_zip(0).await).await.unwrap();
 
-        assert!(!PortfolioService::is_portfolio_prepared(APPLICATION_ID).await);
+        assert!(!PortfolioService::is_portfolio_prepared(&db, APPLICATION_ID).await);
 
         clear_data_store_temp_dir(temp_dir).await;
     }
@@ -628,15 +1068,16 @@ mod tests {
     #[tokio::test]
     #[serial]
     async fn test_delete_cache() {
+        let db = get_memory_sqlite_connection().await;
         let (temp_dir, _, _) = create_data_store_temp_dir(APPLICATION_ID).await;
 
-        PortfolioService::add_portfolio_zip_to_cache(APPLICATION_ID, vec![0]).await.unwrap();
+        PortfolioService::add_document_to_cache(&db, APPLICATION_ID, "portfolio_zip", portfolio_zip(0).await).await.unwrap();
         
-        assert!(PortfolioService::is_portfolio_zip(APPLICATION_ID).await);
+        assert!(PortfolioService::is_document_cached(&db, APPLICATION_ID, "portfolio_zip").await);
 
         PortfolioService::delete_cache(APPLICATION_ID).await.unwrap();
 
-        assert!(!PortfolioService::is_portfolio_zip(APPLICATION_ID).await);
+        assert!(!PortfolioService::is_document_cached(&db, APPLICATION_ID, "portfolio_zip").await);
 
         clear_data_store_temp_dir(temp_dir).await;
     }
@@ -649,13 +1090,11 @@ mod tests {
         
         let (temp_dir, application_dir, _) = create_data_store_temp_dir(candidate.id).await;
 
-        PortfolioService::add_cover_letter_to_cache(candidate.id, vec![0]).await.unwrap();
-        PortfolioService::add_portfolio_letter_to_cache(candidate.id, vec![0]).await.unwrap();
-        PortfolioService::add_portfolio_zip_to_cache(candidate.id, vec![0]).await.unwrap();
+        put_portfolio_documents(&db, candidate.id, 0).await;
 
-        PortfolioService::submit(&candidate, &db).await.unwrap();
+        PortfolioService::submit(&candidate, &db, &audit_context()).await.unwrap();
         
-        assert!(tokio::fs::metadata(application_dir.join("PORTFOLIO.age")).await.is_ok());
+        assert!(tokio::fs::metadata(application_dir.join("versions").join("1.age")).await.is_ok());
 
         clear_data_store_temp_dir(temp_dir).await;
     }
@@ -664,25 +1103,143 @@ mod tests {
     #[serial]
     async fn test_delete_portfolio() {
         let db = get_memory_sqlite_connection().await;
-        let (_, candidate, _) = put_user_data(&db).await;
+        let (application, candidate, _) = put_user_data(&db).await;
+        let private_key = crypto::decrypt_password(application.private_key.clone(), "test".to_string())
+            .await
+            .unwrap();
 
         let (temp_dir, application_dir, _) = create_data_store_temp_dir(candidate.id).await;
 
-        PortfolioService::add_cover_letter_to_cache(candidate.id, vec![0]).await.unwrap();
-        PortfolioService::add_portfolio_letter_to_cache(candidate.id, vec![0]).await.unwrap();
-        PortfolioService::add_portfolio_zip_to_cache(candidate.id, vec![0]).await.unwrap();
+        put_portfolio_documents(&db, candidate.id, 0).await;
 
-        PortfolioService::submit(&candidate, &db).await.unwrap();
+        PortfolioService::submit(&candidate, &db, &audit_context()).await.unwrap();
         
-        assert!(tokio::fs::metadata(application_dir.join("PORTFOLIO.age")).await.is_ok());
+        assert!(PortfolioService::is_portfolio_submitted(candidate.id).await);
+
+        PortfolioService::delete_portfolio(&db, candidate.id, private_key).await.unwrap();
+
+        assert!(!PortfolioService::is_portfolio_submitted(candidate.id).await);
+        // Withdrawn portfolio stays in the version history
+        assert!(tokio::fs::metadata(application_dir.join("versions").join("1.age")).await.is_ok());
+
+        clear_data_store_temp_dir(temp_dir).await;
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_delete_legacy_portfolio() {
+        let db = get_memory_sqlite_connection().await;
+        let (application, candidate, _) = put_user_data(&db).await;
+        let (temp_dir, application_dir, _) = create_data_store_temp_dir(candidate.id).await;
+        let private_key = crypto::decrypt_password(application.private_key.clone(), "test".to_string())
+            .await
+            .unwrap();
 
-        PortfolioService::delete_portfolio(candidate.id).await.unwrap();
+        // Portfolio submitted before versioning has no version record
+        put_portfolio_documents(&db, candidate.id, 0).await;
+        let receipt = PortfolioService::submit(&candidate, &db, &audit_context()).await.unwrap();
+        tokio::fs::rename(application_dir.join("versions").join("1.age"), application_dir.join("PORTFOLIO.age")).await.unwrap();
+        tokio::fs::remove_file(application_dir.join("CURRENT_VERSION")).await.unwrap();
+        for version in crate::Query::list_portfolio_versions(&db, candidate.id).await.unwrap() {
+            crate::Mutation::delete_portfolio_version(&db, version).await.unwrap();
+        }
+        assert!(PortfolioService::is_portfolio_submitted(candidate.id).await);
 
-        assert!(!tokio::fs::metadata(application_dir.join("PORTFOLIO.age")).await.is_ok());
+        PortfolioService::delete_portfolio(&db, candidate.id, private_key.clone()).await.unwrap();
+
+        assert!(!PortfolioService::is_portfolio_submitted(candidate.id).await);
+        assert!(tokio::fs::metadata(application_dir.join("PORTFOLIO.age")).await.is_err());
+        let versions = PortfolioService::list_versions(&db, candidate.id).await.unwrap();
+        assert_eq!(versions.len(), 1);
+        assert_eq!(versions[0].hash, receipt.archive_hash);
+        PortfolioService::get_portfolio_version_stream(&db, candidate.id, 1, private_key).await.unwrap();
 
         clear_data_store_temp_dir(temp_dir).await;
     }
 
+    #[tokio::test]
+    #[serial]
+    async fn test_portfolio_versions() {
+        let db = get_memory_sqlite_connection().await;
+        let (application, candidate, _) = put_user_data(&db).await;
+        let (temp_dir, application_dir, _) = create_data_store_temp_dir(candidate.id).await;
+        std::env::set_var("PORTFOLIO_MAX_VERSIONS", "2");
+        let private_key = crypto::decrypt_password(application.private_key.clone(), "test".to_string())
+            .await
+            .unwrap();
+
+        for content in [1, 2, 3] {
+            put_portfolio_documents(&db, candidate.id, content).await;
+            PortfolioService::submit(&candidate, &db, &audit_context()).await.unwrap();
+            if content < 3 {
+                PortfolioService::delete_portfolio(&db, candidate.id, private_key.clone()).await.unwrap();
+            }
+        }
+
+        // Oldest version is deleted above the cap
+        let versions = PortfolioService::list_versions(&db, candidate.id).await.unwrap();
+        assert_eq!(versions.iter().map(|v| v.version).collect::<Vec<_>>(), vec![2, 3]);
+        assert_eq!(versions.iter().map(|v| v.current).collect::<Vec<_>>(), vec![false, true]);
+        assert_ne!(versions[0].hash, versions[1].hash);
+        assert!(tokio::fs::metadata(application_dir.join("versions").join("1.age")).await.is_err());
+
+        let mut previous = vec![];
+        let mut stream = PortfolioService::get_portfolio_version_stream(&db, candidate.id, 2, private_key.clone())
+            .await
+            .unwrap();
+        tokio::io::AsyncReadExt::read_to_end(&mut stream, &mut previous).await.unwrap();
+        let current = PortfolioService::get_portfolio(candidate.id, private_key.clone()).await.unwrap();
+        assert_ne!(previous, current);
+        assert_eq!(current.len() as i64, versions[1].size);
+
+        assert!(matches!(
+            PortfolioService::get_portfolio_version_stream(&db, candidate.id, 1, private_key).await,
+            Err(ServiceError::PortfolioVersionNotFound)
+        ));
+
+        std::env::remove_var("PORTFOLIO_MAX_VERSIONS");
+        clear_data_store_temp_dir(temp_dir).await;
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_submission_receipt_and_integrity() {
+        let db = get_memory_sqlite_connection().await;
+        let (_, candidate, _) = put_user_data(&db).await;
+        let (temp_dir, application_dir, _) = create_data_store_temp_dir(candidate.id).await;
+        std::env::set_var("PORTFOLIO_RECEIPT_KEY", base64::engine::general_purpose::STANDARD.encode([7; 32]));
+
+        put_portfolio_documents(&db, candidate.id, 3).await;
+        let receipt = PortfolioService::submit(&candidate, &db, &audit_context()).await.unwrap();
+
+        assert_eq!(receipt.version, 1);
+        assert_eq!(receipt.files.len(), 3);
+        assert_eq!(receipt.files[0].hash, crypto::sha256_stream(pdf(false).as_slice()).await.unwrap().0);
+        assert!(ReceiptKey::from_env().unwrap().unwrap().verify(&receipt));
+
+        let store = storage::from_env().unwrap();
+        let reports = PortfolioService::verify_integrity(&db, &*store).await.unwrap();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].status, IntegrityStatus::Ok);
+
+        // Tamper with the stored archive
+        let version_path = application_dir.join("versions").join("1.age");
+        let mut stored = tokio::fs::read(&version_path).await.unwrap();
+        let last = stored.len() - 1;
+        stored[last] ^= 1;
+        tokio::fs::write(&version_path, stored).await.unwrap();
+        let reports = PortfolioService::verify_integrity(&db, &*store).await.unwrap();
+        assert_eq!(reports[0].status, IntegrityStatus::Mismatch);
+        assert_ne!(reports[0].expected_hash, reports[0].actual_hash);
+
+        tokio::fs::remove_file(&version_path).await.unwrap();
+        let reports = PortfolioService::verify_integrity(&db, &*store).await.unwrap();
+        assert_eq!(reports[0].status, IntegrityStatus::Missing);
+
+        std::env::remove_var("PORTFOLIO_RECEIPT_KEY");
+        clear_data_store_temp_dir(temp_dir).await;
+    }
+
     #[tokio::test]
     #[serial]
     async fn test_is_portfolio_submitted() {
@@ -691,11 +1248,9 @@ mod tests {
         let (_, candidate, _) = put_user_data(&db).await;
         let (temp_dir, _, _) = create_data_store_temp_dir(candidate.id).await;
 
-        PortfolioService::add_cover_letter_to_cache(candidate.id, vec![0]).await.unwrap();
-        PortfolioService::add_portfolio_letter_to_cache(candidate.id, vec![0]).await.unwrap();
-        PortfolioService::add_portfolio_zip_to_cache(candidate.id, vec![0]).await.unwrap();
+        put_portfolio_documents(&db, candidate.id, 0).await;
 
-        PortfolioService::submit(&candidate, &db).await.unwrap();
+        PortfolioService::submit(&candidate, &db, &audit_context()).await.unwrap();
         
         assert!(PortfolioService::is_portfolio_submitted(candidate.id).await);
 
@@ -703,13 +1258,11 @@ mod tests {
 
         let (temp_dir, application_dir, _) = create_data_store_temp_dir(candidate.id).await;
 
-        PortfolioService::add_cover_letter_to_cache(candidate.id, vec![0]).await.unwrap();
-        PortfolioService::add_portfolio_letter_to_cache(candidate.id, vec![0]).await.unwrap();
-        PortfolioService::add_portfolio_zip_to_cache(candidate.id, vec![0]).await.unwrap();
+        put_portfolio_documents(&db, candidate.id, 0).await;
 
-        PortfolioService::submit(&candidate, &db).await.unwrap();
+        PortfolioService::submit(&candidate, &db, &audit_context()).await.unwrap();
 
-        tokio::fs::remove_file(application_dir.join("PORTFOLIO.age")).await.unwrap();
+        tokio::fs::remove_file(application_dir.join("CURRENT_VERSION")).await.unwrap();
         
         assert!(!PortfolioService::is_portfolio_submitted(candidate.id).await);
 
@@ -728,24 +1281,48 @@ mod tests {
             .await
             .unwrap();
 
-        PortfolioService::add_cover_letter_to_cache(candidate.id, vec![0])
-            .await
-            .unwrap();
-        PortfolioService::add_portfolio_letter_to_cache(candidate.id, vec![0])
+        put_portfolio_documents(&db, candidate.id, 0).await;
+
+        PortfolioService::submit(&candidate, &db, &audit_context())
             .await
             .unwrap();
-        PortfolioService::add_portfolio_zip_to_cache(candidate.id, vec![0])
+
+        PortfolioService::get_portfolio(candidate.id, private_key)
             .await
             .unwrap();
 
-        PortfolioService::submit(&candidate, &db)
+        clear_data_store_temp_dir(temp_dir).await;
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_get_portfolio_stream() {
+        let db = get_memory_sqlite_connection().await;
+        let (application, candidate, _parent) = put_user_data(&db).await;
+
+        let (temp_dir, _, application_cache_dir) = create_data_store_temp_dir(candidate.id).await;
+
+        let private_key = crypto::decrypt_password(application.private_key.clone(), "test".to_string())
             .await
             .unwrap();
 
-        PortfolioService::get_portfolio(candidate.id, private_key)
+        put_portfolio_documents(&db, candidate.id, 0).await;
+
+        PortfolioService::submit(&candidate, &db, &audit_context()).await.unwrap();
+
+        assert!(tokio::fs::read_dir(&application_cache_dir).await.unwrap().next_entry().await.unwrap().is_none());
+
+        let mut stream = PortfolioService::get_portfolio_stream(candidate.id, private_key.clone())
             .await
             .unwrap();
+        let mut streamed = vec![];
+        tokio::io::AsyncReadExt::read_to_end(&mut stream, &mut streamed).await.unwrap();
+
+        let buffered = PortfolioService::get_portfolio(candidate.id, private_key).await.unwrap();
+
+        assert_eq!(&streamed[..4], b"PK\x03\x04");
+        assert_eq!(streamed, buffered);
 
         clear_data_store_temp_dir(temp_dir).await;
     }
-}
\ No newline at end of file
+}
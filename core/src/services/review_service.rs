@@ -0,0 +1,260 @@
+use entity::review;
+use log::info;
+use sea_orm::DbConn;
+
+use crate::{
+    error::ServiceError,
+    models::{
+        admission_cycle::AdmissionCycle,
+        audit::{AuditAction, AuditContext},
+        candidate_details::EncryptedString,
+        review::{ReviewAssignmentResponse, ReviewResponse, ReviewScores, ScoreSummary},
+    },
+    Mutation, Query,
+};
+
+use super::{audit_service::AuditService, portfolio_service::PortfolioService};
+
+/// Scoring of submitted portfolios by assigned reviewers.
+/// Scores and comments are encrypted to admin keys only, candidates can't read them
+pub struct ReviewService;
+
+impl ReviewService {
+    fn program_code(application_id: i32) -> Option<String> {
+        AdmissionCycle::get()
+            .program_by_application_id(application_id)
+            .map(|p| p.code.to_owned())
+    }
+
+    async fn decrypt_scores(review: &review::Model, private_key: &String) -> Result<ReviewScores, ServiceError> {
+        match &review.scores {
+            Some(scores) => {
+                let scores = EncryptedString::from(scores.to_owned()).decrypt(private_key).await?;
+                serde_json::from_str(&scores).map_err(|_| ServiceError::CryptoDecryptFailed)
+            },
+            None => Ok(ReviewScores::new()),
+        }
+    }
+
+    /// Assigns the admin to review the application, assigning the same admin again does nothing
+    pub async fn assign(
+        db: &DbConn,
+        application_id: i32,
+        admin_id: i32,
+        audit: &AuditContext,
+    ) -> Result<review::Model, ServiceError> {
+        Query::find_application_by_id(db, application_id)
+            .await?
+            .ok_or(ServiceError::CandidateNotFound)?;
+        Query::find_admin_by_id(db, admin_id)
+            .await?
+            .ok_or(ServiceError::AdminNotFound)?;
+
+        if let Some(review) = Query::find_review(db, application_id, admin_id).await? {
+            return Ok(review);
+        }
+        let review = Mutation::create_review_assignment(db, application_id, admin_id).await?;
+        AuditService::log(db, audit, AuditAction::AssignReviewer, Some(application_id)).await?;
+
+        info!("ADMIN {} ASSIGNED TO REVIEW APPLICATION {}", admin_id, application_id);
+        Ok(review)
+    }
+
+    /// Removes the assignment together with the submitted review
+    pub async fn unassign(
+        db: &DbConn,
+        application_id: i32,
+        admin_id: i32,
+        audit: &AuditContext,
+    ) -> Result<(), ServiceError> {
+        let review = Query::find_review(db, application_id, admin_id)
+            .await?
+            .ok_or(ServiceError::ReviewNotFound)?;
+        Mutation::delete_review(db, review).await?;
+        AuditService::log(db, audit, AuditAction::UnassignReviewer, Some(application_id)).await?;
+
+        info!("ADMIN {} UNASSIGNED FROM APPLICATION {}", admin_id, application_id);
+        Ok(())
+    }
+
+    /// Applications the admin should review, with the rubric to score them by
+    pub async fn list_assigned(db: &DbConn, admin_id: i32) -> Result<Vec<ReviewAssignmentResponse>, ServiceError> {
+        let cycle = AdmissionCycle::get();
+
+        Ok(
+            Query::list_reviews_by_admin_id(db, admin_id)
+                .await?
+                .into_iter()
+                .map(|review| ReviewAssignmentResponse {
+                    application_id: review.application_id,
+                    program: Self::program_code(review.application_id),
+                    rubric: cycle.rubric_for(review.application_id),
+                    assigned_at: review.assigned_at,
+                    submitted_at: review.submitted_at,
+                })
+                .collect()
+        )
+    }
+
+    /// Stores scores of an assigned admin, a submitted review can be changed by submitting again
+    pub async fn submit(
+        db: &DbConn,
+        admin_id: i32,
+        application_id: i32,
+        scores: ReviewScores,
+        comment: Option<String>,
+        audit: &AuditContext,
+    ) -> Result<review::Model, ServiceError> {
+        let review = Query::find_review(db, application_id, admin_id)
+            .await?
+            .ok_or(ServiceError::Forbidden)?;
+        let application = Query::find_application_by_id(db, application_id)
+            .await?
+            .ok_or(ServiceError::CandidateNotFound)?;
+        if !PortfolioService::is_portfolio_submitted(application.candidate_id).await {
+            return Err(ServiceError::IncompletePortfolio);
+        }
+        AdmissionCycle::get()
+            .rubric_for(application_id)
+            .validate_scores(&scores)?;
+
+        let recipients = Query::get_all_admin_public_keys(db).await?;
+        let scores = serde_json::to_string(&scores).map_err(|_| ServiceError::CryptoEncryptFailed)?;
+        let scores = EncryptedString::new(&scores, &recipients).await?;
+        let comment = EncryptedString::new_option(comment.as_deref().unwrap_or_default(), &recipients).await?;
+
+        let review = Mutation::update_review(
+            db,
+            review,
+            scores.to_string(),
+            comment.map(EncryptedString::to_string),
+        ).await?;
+        AuditService::log(db, audit, AuditAction::Review, Some(application_id)).await?;
+
+        info!("ADMIN {} REVIEWED APPLICATION {}", admin_id, application_id);
+        Ok(review)
+    }
+
+    /// Decrypted reviews of the application, including assignments which were not submitted yet
+    pub async fn list_reviews(
+        db: &DbConn,
+        private_key: &String,
+        application_id: i32,
+    ) -> Result<Vec<ReviewResponse>, ServiceError> {
+        let mut reviews = vec![];
+        for review in Query::list_reviews_by_application_id(db, application_id).await? {
+            reviews.push(ReviewResponse {
+                scores: Self::decrypt_scores(&review, private_key).await?,
+                comment: EncryptedString::decrypt_option(
+                    &review.comment.to_owned().map(EncryptedString::from),
+                    private_key,
+                ).await?,
+                application_id: review.application_id,
+                admin_id: review.admin_id,
+                assigned_at: review.assigned_at,
+                submitted_at: review.submitted_at,
+            });
+        }
+        Ok(reviews)
+    }
+
+    /// Aggregate scores of all applications, applications without reviews have no total
+    pub async fn summaries(db: &DbConn, private_key: &String) -> Result<Vec<ScoreSummary>, ServiceError> {
+        let cycle = AdmissionCycle::get();
+        let reviews = Query::list_all_reviews(db).await?;
+
+        let mut summaries = vec![];
+        for application in Query::list_applications_compact(db).await? {
+            let mut scores = vec![];
+            for review in reviews.iter().filter(|r| r.application_id == application.id && r.submitted_at.is_some()) {
+                scores.push(Self::decrypt_scores(review, private_key).await?);
+            }
+            summaries.push(ScoreSummary::aggregate(
+                application.candidate_id,
+                application.id,
+                Self::program_code(application.id),
+                &cycle.rubric_for(application.id),
+                &scores,
+            ));
+        }
+        summaries.sort_by_key(|s| (s.candidate_id, s.application_id));
+        Ok(summaries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+
+    use crate::{
+        crypto,
+        error::ServiceError,
+        models::{audit::AuditLogFilter, review::ReviewScores},
+        services::{
+            admin_service::admin_tests::create_admin,
+            audit_service::AuditService,
+            candidate_service::tests::put_user_data,
+            portfolio_service::{tests::{audit_context, clear_data_store_temp_dir, create_data_store_temp_dir, put_portfolio_documents}, PortfolioService},
+        },
+        utils::{csv::{CsvExporter, ScoreCsv}, db::get_memory_sqlite_connection},
+    };
+
+    use super::ReviewService;
+
+    #[tokio::test]
+    #[serial]
+    async fn test_review_flow() {
+        let db = get_memory_sqlite_connection().await;
+        let admin = create_admin(&db).await;
+        let private_key = crypto::decrypt_password(admin.private_key.to_owned(), "admin".to_string()).await.unwrap();
+        let (application, candidate, _) = put_user_data(&db).await;
+        let (temp_dir, _, _) = create_data_store_temp_dir(candidate.id).await;
+        let audit = audit_context();
+        let scores = ReviewScores::from([("overall".to_string(), 7)]);
+
+        assert!(matches!(
+            ReviewService::submit(&db, admin.id, application.id, scores.to_owned(), None, &audit).await,
+            Err(ServiceError::Forbidden)
+        ));
+        ReviewService::assign(&db, application.id, admin.id, &audit).await.unwrap();
+        ReviewService::assign(&db, application.id, admin.id, &audit).await.unwrap();
+        assert_eq!(ReviewService::list_assigned(&db, admin.id).await.unwrap().len(), 1);
+        assert!(matches!(
+            ReviewService::submit(&db, admin.id, application.id, scores.to_owned(), None, &audit).await,
+            Err(ServiceError::IncompletePortfolio)
+        ));
+
+        put_portfolio_documents(&db, candidate.id, 1).await;
+        PortfolioService::submit(&candidate, &db, &audit).await.unwrap();
+
+        assert!(matches!(
+            ReviewService::submit(&db, admin.id, application.id, ReviewScores::from([("overall".to_string(), 11)]), None, &audit).await,
+            Err(ServiceError::InvalidReview(_))
+        ));
+        let review = ReviewService::submit(&db, admin.id, application.id, scores.to_owned(), Some("Pěkné".to_string()), &audit).await.unwrap();
+        assert!(!review.scores.unwrap().contains("overall"));
+
+        let reviews = ReviewService::list_reviews(&db, &private_key, application.id).await.unwrap();
+        assert_eq!(reviews[0].scores, scores);
+        assert_eq!(reviews[0].comment.as_deref(), Some("Pěkné"));
+
+        let summaries = ReviewService::summaries(&db, &private_key).await.unwrap();
+        assert_eq!(summaries[0].total, Some(7.0));
+        assert!(summaries[0].complete);
+
+        let csv = String::from_utf8(ScoreCsv::export(&db, private_key.to_owned()).await.unwrap()).unwrap();
+        assert!(csv.starts_with("Číslo uchazeče (přiděleno systémem)"));
+        assert!(csv.contains(&format!("{},{},KB,1,1,overall: 7.00,7.00,10,true", candidate.id, application.id)));
+
+        ReviewService::unassign(&db, application.id, admin.id, &audit).await.unwrap();
+        let filter = AuditLogFilter { action: Some("unassign_reviewer".to_string()), ..Default::default() };
+        assert_eq!(AuditService::list(&db, filter, None).await.unwrap().len(), 1);
+        assert!(ReviewService::list_reviews(&db, &private_key, application.id).await.unwrap().is_empty());
+        assert!(matches!(
+            ReviewService::unassign(&db, application.id, admin.id, &audit).await,
+            Err(ServiceError::ReviewNotFound)
+        ));
+
+        clear_data_store_temp_dir(temp_dir).await;
+    }
+}
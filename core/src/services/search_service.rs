@@ -0,0 +1,215 @@
+use std::{collections::HashMap, sync::{atomic::{AtomicU64, Ordering}, Mutex, OnceLock}};
+
+use chrono::{Duration, NaiveDateTime};
+use log::info;
+use sea_orm::{prelude::Uuid, DbConn};
+
+use crate::{error::ServiceError, models::{application::ApplicationResponse, search::{normalize, SearchQuery}}, Query};
+
+const PAGE_SIZE: usize = 20;
+/// Index is rebuilt after this time even without writes, so that changes made outside of this process show up
+const INDEX_TTL_MINUTES: i64 = 15;
+
+/// Incremented on every write to candidate data, indexes built before are stale
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+static INDEXES: OnceLock<Mutex<HashMap<Uuid, SearchIndex>>> = OnceLock::new();
+
+struct SearchEntry {
+    haystack: String,
+    admission_round_id: i32,
+    application: ApplicationResponse,
+}
+
+struct SearchIndex {
+    generation: u64,
+    built_at: NaiveDateTime,
+    entries: Vec<SearchEntry>,
+}
+
+impl SearchIndex {
+    fn is_fresh(&self) -> bool {
+        self.generation == GENERATION.load(Ordering::SeqCst) &&
+            self.built_at + Duration::minutes(INDEX_TTL_MINUTES) > chrono::offset::Local::now().naive_local()
+    }
+
+    fn find(
+        &self,
+        query: &SearchQuery,
+        field_of_study: Option<String>,
+        admission_round_id: Option<i32>,
+        page: Option<u64>,
+    ) -> Vec<ApplicationResponse> {
+        let results = self.entries
+            .iter()
+            .filter(|e| field_of_study.is_none() || e.application.field_of_study == field_of_study)
+            .filter(|e| admission_round_id.is_none() || Some(e.admission_round_id) == admission_round_id)
+            .filter(|e| query.matches(&e.haystack))
+            .map(|e| e.application.clone());
+
+        match page {
+            Some(page) => results.skip(page as usize * PAGE_SIZE).take(PAGE_SIZE).collect(),
+            None => results.collect(),
+        }
+    }
+}
+
+pub struct SearchService;
+
+impl SearchService {
+    fn indexes() -> &'static Mutex<HashMap<Uuid, SearchIndex>> {
+        INDEXES.get_or_init(|| Mutex::new(HashMap::new()))
+    }
+
+    /// Drops decrypted indexes of all sessions, must be called after candidate data changes
+    pub fn invalidate() {
+        GENERATION.fetch_add(1, Ordering::SeqCst);
+        Self::indexes().lock().unwrap().clear();
+    }
+
+    /// Drops decrypted index of one admin session, called on logout and when the session expires
+    pub fn drop_session(session_id: Uuid) {
+        Self::indexes().lock().unwrap().remove(&session_id);
+    }
+
+    /// Searches name, surname, email, telephone, personal id and application id of all applications.
+    /// Candidate data are decrypted with the admin key once and kept in memory for the admin session
+    pub async fn search(
+        db: &DbConn,
+        session_id: Uuid,
+        private_key: &String,
+        query: &str,
+        field_of_study: Option<String>,
+        admission_round_id: Option<i32>,
+        page: Option<u64>,
+    ) -> Result<Vec<ApplicationResponse>, ServiceError> {
+        let query = SearchQuery::parse(query)?;
+
+        {
+            let mut indexes = Self::indexes().lock().unwrap();
+            Self::evict_expired(&mut indexes);
+            if let Some(index) = indexes.get(&session_id) {
+                return Ok(index.find(&query, field_of_study, admission_round_id, page));
+            }
+        }
+
+        let index = Self::build_index(db, private_key).await?;
+        let results = index.find(&query, field_of_study, admission_round_id, page);
+        let mut indexes = Self::indexes().lock().unwrap();
+        Self::evict_expired(&mut indexes);
+        indexes.insert(session_id, index);
+
+        Ok(results)
+    }
+
+    /// Sessions which simply expire are never logged out, their decrypted data must not stay in memory past the TTL
+    fn evict_expired(indexes: &mut HashMap<Uuid, SearchIndex>) {
+        indexes.retain(|_, index| index.is_fresh());
+    }
+
+    async fn build_index(db: &DbConn, private_key: &String) -> Result<SearchIndex, ServiceError> {
+        let generation = GENERATION.load(Ordering::SeqCst);
+        let rows = Query::list_applications(db, None, None, None, None).await?;
+
+        let mut related_applications: HashMap<i32, Vec<i32>> = HashMap::new();
+        for row in rows.iter() {
+            related_applications.entry(row.candidate_id).or_default().push(row.application_id);
+        }
+
+        let entries = futures::future::try_join_all(
+            rows.into_iter().map(|row| {
+                let related = related_applications[&row.candidate_id].clone();
+                async move {
+                    let admission_round_id = row.admission_round_id;
+                    let application = ApplicationResponse::from_encrypted(private_key, row, related).await?;
+                    Ok::<_, ServiceError>(SearchEntry {
+                        haystack: Self::haystack(&application),
+                        admission_round_id,
+                        application,
+                    })
+                }
+            })
+        ).await?;
+
+        info!("SEARCH INDEX BUILT WITH {} APPLICATIONS", entries.len());
+        Ok(
+            SearchIndex {
+                generation,
+                built_at: chrono::offset::Local::now().naive_local(),
+                entries,
+            }
+        )
+    }
+
+    /// Telephone and personal id are indexed also without separators, so "777123456" finds "+420 777 123 456"
+    fn haystack(application: &ApplicationResponse) -> String {
+        let compact = |s: &str| s.chars().filter(|c| !c.is_whitespace() && *c != '/').collect::<String>();
+        normalize(&[
+            application.application_id.to_string(),
+            application.name.to_owned(),
+            application.surname.to_owned(),
+            application.email.to_owned(),
+            application.telephone.to_owned(),
+            compact(&application.telephone),
+            application.personal_id_number.to_owned(),
+            compact(&application.personal_id_number),
+        ].join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::prelude::Uuid;
+    use serial_test::serial;
+
+    use crate::{crypto, error::ServiceError, services::candidate_service::tests::put_user_data, utils::db::get_memory_sqlite_connection};
+
+    use chrono::Duration;
+
+    use super::{SearchService, INDEX_TTL_MINUTES};
+
+    #[tokio::test]
+    #[serial]
+    async fn test_search_and_invalidate() {
+        let db = get_memory_sqlite_connection().await;
+        let (application, _, _) = put_user_data(&db).await;
+        let private_key = crypto::decrypt_password(application.private_key.clone(), "test".to_string())
+            .await
+            .unwrap();
+        let session_id = Uuid::new_v4();
+
+        let results = SearchService::search(&db, session_id, &private_key, "NAME surname", None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].application_id, application.id);
+
+        let results = SearchService::search(&db, session_id, &private_key, "0000001111", None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+
+        let results = SearchService::search(&db, session_id, &private_key, "name", Some("G".to_string()), None, None)
+            .await
+            .unwrap();
+        assert!(results.is_empty());
+
+        assert!(matches!(
+            SearchService::search(&db, session_id, &private_key, " ", None, None, None).await,
+            Err(ServiceError::InvalidSearchQuery)
+        ));
+
+        SearchService::invalidate();
+        assert!(SearchService::indexes().lock().unwrap().get(&session_id).is_none());
+
+        SearchService::search(&db, session_id, &private_key, "name", None, None, None).await.unwrap();
+        // Index of another session past its TTL is evicted by any search
+        let expired_session_id = Uuid::new_v4();
+        SearchService::search(&db, expired_session_id, &private_key, "name", None, None, None).await.unwrap();
+        SearchService::indexes().lock().unwrap().get_mut(&expired_session_id).unwrap().built_at -= Duration::minutes(INDEX_TTL_MINUTES);
+        SearchService::search(&db, session_id, &private_key, "name", None, None, None).await.unwrap();
+        assert!(SearchService::indexes().lock().unwrap().get(&expired_session_id).is_none());
+
+        SearchService::drop_session(session_id);
+        assert!(SearchService::indexes().lock().unwrap().get(&session_id).is_none());
+    }
+}
@@ -0,0 +1,126 @@
+use chrono::NaiveDateTime;
+use log::info;
+use sea_orm::DbConn;
+
+use crate::{
+    error::ServiceError,
+    models::{admission_cycle::AdmissionCycle, timeline::{CandidateAction, TimelineResponse}},
+    Mutation,
+    Query,
+};
+
+pub struct TimelineService;
+
+impl TimelineService {
+    /// Current phase of the admission cycle, shown to candidates before they log in
+    pub fn current() -> TimelineResponse {
+        let cycle = AdmissionCycle::get();
+        let now = chrono::offset::Local::now().naive_local();
+
+        TimelineResponse {
+            phase: cycle.phase_at(now),
+            phase_ends_at: cycle.timeline.as_ref().and_then(|t| t.phase_ends_at(now)),
+            timeline: cycle.timeline.to_owned(),
+        }
+    }
+
+    /// Fails with `PhaseClosed` when the current phase doesn't allow the action
+    /// and the candidate has no running deadline extension
+    pub async fn ensure_allowed(
+        db: &DbConn,
+        candidate_id: i32,
+        action: CandidateAction,
+    ) -> Result<(), ServiceError> {
+        let now = chrono::offset::Local::now().naive_local();
+        Self::ensure_allowed_at(db, AdmissionCycle::get(), candidate_id, action, now).await
+    }
+
+    async fn ensure_allowed_at(
+        db: &DbConn,
+        cycle: &AdmissionCycle,
+        candidate_id: i32,
+        action: CandidateAction,
+        now: NaiveDateTime,
+    ) -> Result<(), ServiceError> {
+        if cycle.phase_at(now).allows(action) {
+            return Ok(());
+        }
+
+        let extension = Query::find_candidate_by_id(db, candidate_id)
+            .await?
+            .and_then(|c| c.deadline_extension);
+        match extension {
+            Some(until) if until > now => Ok(()),
+            _ => Err(ServiceError::PhaseClosed),
+        }
+    }
+
+    /// Lets the candidate of the application change details and portfolio until `until`,
+    /// `None` removes the extension
+    pub async fn set_extension(
+        db: &DbConn,
+        application_id: i32,
+        until: Option<NaiveDateTime>,
+    ) -> Result<(), ServiceError> {
+        let application = Query::find_application_by_id(db, application_id)
+            .await?
+            .ok_or(ServiceError::CandidateNotFound)?;
+        let candidate = Query::find_candidate_by_id(db, application.candidate_id)
+            .await?
+            .ok_or(ServiceError::CandidateNotFound)?;
+
+        Mutation::update_candidate_deadline_extension(db, candidate, until).await?;
+        match until {
+            Some(until) => info!("CANDIDATE {} DEADLINE EXTENDED UNTIL {}", application.candidate_id, until),
+            None => info!("CANDIDATE {} DEADLINE EXTENSION REMOVED", application.candidate_id),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use crate::{
+        error::ServiceError,
+        models::{admission_cycle::AdmissionCycle, timeline::{CandidateAction, Timeline}},
+        services::candidate_service::tests::put_user_data,
+        utils::db::get_memory_sqlite_connection,
+    };
+
+    use super::TimelineService;
+
+    #[tokio::test]
+    async fn test_ensure_allowed() {
+        let db = get_memory_sqlite_connection().await;
+        let (application, candidate, _) = put_user_data(&db).await;
+        let now = chrono::offset::Local::now().naive_local();
+
+        // Only portfolios can be uploaded for one more day
+        let mut cycle = AdmissionCycle::get().clone();
+        cycle.timeline = Some(Timeline {
+            registration_open: now - Duration::days(30),
+            details_editable: now - Duration::days(20),
+            portfolio_upload: now - Duration::days(10),
+            review: now + Duration::days(1),
+            closed: now + Duration::days(10),
+        });
+
+        let allowed = |action, time| TimelineService::ensure_allowed_at(&db, &cycle, candidate.id, action, time);
+        assert!(allowed(CandidateAction::EditPortfolio, now).await.is_ok());
+        assert!(matches!(allowed(CandidateAction::EditDetails, now).await, Err(ServiceError::PhaseClosed)));
+        assert!(allowed(CandidateAction::EditPortfolio, now + Duration::days(2)).await.is_err());
+
+        TimelineService::set_extension(&db, application.id, Some(now + Duration::days(5))).await.unwrap();
+        assert!(allowed(CandidateAction::EditDetails, now).await.is_ok());
+        assert!(allowed(CandidateAction::EditPortfolio, now + Duration::days(2)).await.is_ok());
+        assert!(allowed(CandidateAction::EditPortfolio, now + Duration::days(6)).await.is_err());
+
+        TimelineService::set_extension(&db, application.id, None).await.unwrap();
+        assert!(allowed(CandidateAction::EditDetails, now).await.is_err());
+
+        // Without a timeline everything is allowed
+        assert!(TimelineService::ensure_allowed(&db, candidate.id, CandidateAction::EditDetails).await.is_ok());
+    }
+}
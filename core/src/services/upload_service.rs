@@ -0,0 +1,336 @@
+use log::{info, warn};
+use sea_orm::{prelude::Uuid, DbConn};
+use tokio::io::AsyncReadExt;
+
+use crate::{
+    crypto,
+    error::ServiceError,
+    models::{document::DocumentType, timeline::CandidateAction, upload::UploadResponse},
+    storage::{self, PortfolioStore, StoreReader},
+};
+
+use super::{portfolio_service::PortfolioService, timeline_service::TimelineService};
+
+/// Upload of a document in chunks, identified by the candidate, document slug and upload id
+#[derive(Clone, Copy)]
+struct Upload<'a> {
+    candidate_id: i32,
+    slug: &'a str,
+    upload_id: Uuid,
+}
+
+impl Upload<'_> {
+    /// All uploads of the document
+    fn document_prefix(&self) -> String {
+        format!("{}/uploads/{}", PortfolioService::cache_prefix(self.candidate_id), self.slug)
+    }
+
+    fn prefix(&self) -> String {
+        format!("{}/{}", self.document_prefix(), self.upload_id)
+    }
+
+    fn size_key(&self) -> String {
+        format!("{}/SIZE", self.prefix())
+    }
+
+    fn offset_key(&self) -> String {
+        format!("{}/OFFSET", self.prefix())
+    }
+
+    fn chunks_prefix(&self) -> String {
+        format!("{}/chunks", self.prefix())
+    }
+
+    fn chunk_key(&self, offset: u64) -> String {
+        // Zero padded, so that chunks are listed in order
+        format!("{}/{:012}", self.chunks_prefix(), offset)
+    }
+}
+
+/// Resumable upload of portfolio documents in chunks.
+///
+/// Every chunk is stored as a separate object named by its offset, `OFFSET` holds the number of bytes
+/// received so far and is written only after the chunk, so a chunk interrupted midway is simply sent again.
+/// Uploads live in the candidate cache, unfinished ones are removed with it on submit
+pub struct UploadService;
+
+impl UploadService {
+    async fn read_number(store: &dyn PortfolioStore, key: &str) -> Result<u64, ServiceError> {
+        let mut number = String::new();
+        store.get(key).await?.read_to_string(&mut number).await?;
+        number.trim()
+            .parse()
+            .map_err(|_| ServiceError::PortfolioWriteError)
+    }
+
+    async fn write_number(store: &dyn PortfolioStore, key: &str, number: u64) -> Result<(), ServiceError> {
+        store.put(key, &mut number.to_string().as_bytes()).await
+    }
+
+    async fn get_status(store: &dyn PortfolioStore, upload: Upload<'_>) -> Result<UploadResponse, ServiceError> {
+        // Slug is a part of the key, it must not point anywhere else
+        if !DocumentType::is_valid_slug(upload.slug) {
+            return Err(ServiceError::UploadNotFound);
+        }
+        let size_key = upload.size_key();
+        if !store.exists(&size_key).await? {
+            return Err(ServiceError::UploadNotFound);
+        }
+
+        let offset_key = upload.offset_key();
+        let offset = if store.exists(&offset_key).await? {
+            Self::read_number(store, &offset_key).await?
+        } else {
+            0
+        };
+
+        Ok(
+            UploadResponse {
+                upload_id: upload.upload_id,
+                offset,
+                size: Self::read_number(store, &size_key).await?,
+            }
+        )
+    }
+
+    /// Reader over all received chunks in order
+    async fn open_received(
+        store: &dyn PortfolioStore,
+        upload: Upload<'_>,
+        offset: u64,
+    ) -> Result<StoreReader, ServiceError> {
+        let mut reader: StoreReader = Box::new(tokio::io::empty());
+        for key in store.list(&upload.chunks_prefix()).await? {
+            let chunk_offset: u64 = key.rsplit('/')
+                .next()
+                .and_then(|name| name.parse().ok())
+                .ok_or(ServiceError::PortfolioWriteError)?;
+            // Chunks at or past the offset were never acknowledged
+            if chunk_offset < offset {
+                reader = Box::new(reader.chain(store.get(&key).await?));
+            }
+        }
+
+        Ok(reader)
+    }
+
+    /// Starts upload of document identified by `slug` with `size` bytes.
+    /// Unfinished upload of the same document is discarded, so every document has at most one upload open
+    pub async fn init(
+        db: &DbConn,
+        candidate_id: i32,
+        slug: &str,
+        size: u64,
+    ) -> Result<UploadResponse, ServiceError> {
+        TimelineService::ensure_allowed(db, candidate_id, CandidateAction::EditPortfolio).await?;
+        let document = PortfolioService::documents(db, candidate_id)
+            .await?
+            .into_iter()
+            .find(|d| d.slug == slug)
+            .ok_or(ServiceError::DocumentNotFound)?;
+        if size > document.max_size {
+            return Err(ServiceError::UploadTooLarge);
+        }
+
+        let upload = Upload { candidate_id, slug, upload_id: Uuid::new_v4() };
+        let store = storage::from_env()?;
+        store.delete_prefix(&upload.document_prefix()).await?;
+        Self::write_number(&*store, &upload.size_key(), size).await?;
+
+        info!("PORTFOLIO {} UPLOAD {} OF {} STARTED, {} BYTES", candidate_id, upload.upload_id, slug, size);
+        Ok(UploadResponse { upload_id: upload.upload_id, offset: 0, size })
+    }
+
+    /// Where the client should continue after reconnecting
+    pub async fn status(candidate_id: i32, slug: &str, upload_id: Uuid) -> Result<UploadResponse, ServiceError> {
+        Self::get_status(&*storage::from_env()?, Upload { candidate_id, slug, upload_id }).await
+    }
+
+    /// Appends chunk starting at `offset`, which must be the number of bytes received so far
+    pub async fn append(
+        db: &DbConn,
+        candidate_id: i32,
+        slug: &str,
+        upload_id: Uuid,
+        offset: u64,
+        chunk: Vec<u8>,
+    ) -> Result<UploadResponse, ServiceError> {
+        TimelineService::ensure_allowed(db, candidate_id, CandidateAction::EditPortfolio).await?;
+        let upload = Upload { candidate_id, slug, upload_id };
+        let store = storage::from_env()?;
+        let status = Self::get_status(&*store, upload).await?;
+
+        if offset != status.offset {
+            return Err(ServiceError::UploadOffsetMismatch);
+        }
+        let new_offset = offset + chunk.len() as u64;
+        if new_offset > status.size {
+            return Err(ServiceError::UploadTooLarge);
+        }
+
+        store.put(&upload.chunk_key(offset), &mut chunk.as_slice()).await?;
+        Self::write_number(&*store, &upload.offset_key(), new_offset).await?;
+
+        Ok(UploadResponse { offset: new_offset, ..status })
+    }
+
+    /// Checks the complete upload against `sha256` (hex encoded) and moves it into cache as the document
+    pub async fn finalize(
+        db: &DbConn,
+        candidate_id: i32,
+        slug: &str,
+        upload_id: Uuid,
+        sha256: &str,
+    ) -> Result<(), ServiceError> {
+        TimelineService::ensure_allowed(db, candidate_id, CandidateAction::EditPortfolio).await?;
+        let upload = Upload { candidate_id, slug, upload_id };
+        let store = storage::from_env()?;
+        let status = Self::get_status(&*store, upload).await?;
+
+        if status.offset != status.size {
+            return Err(ServiceError::IncompleteUpload);
+        }
+
+        // Central directory of a zip is at the end of the archive, the whole file is needed for validation
+        let mut data = Vec::with_capacity(status.size as usize);
+        Self::open_received(&*store, upload, status.offset)
+            .await?
+            .read_to_end(&mut data)
+            .await?;
+
+        let (hash, _) = crypto::sha256_stream(data.as_slice()).await?;
+        if !hash.eq_ignore_ascii_case(sha256.trim()) {
+            warn!("PORTFOLIO {} UPLOAD {} CHECKSUM MISMATCH", candidate_id, upload_id);
+            return Err(ServiceError::UploadChecksumMismatch);
+        }
+
+        PortfolioService::add_document_to_cache(db, candidate_id, slug, data).await?;
+        store.delete_prefix(&upload.prefix()).await?;
+
+        info!("PORTFOLIO {} UPLOAD {} FINISHED", candidate_id, upload_id);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::prelude::Uuid;
+    use serial_test::serial;
+
+    use crate::{
+        crypto,
+        error::ServiceError,
+        services::{
+            candidate_service::tests::put_user_data,
+            portfolio_service::{tests::{clear_data_store_temp_dir, create_data_store_temp_dir, portfolio_zip}, PortfolioService},
+        },
+        utils::db::get_memory_sqlite_connection,
+    };
+
+    use super::UploadService;
+
+    const SLUG: &str = "portfolio_zip";
+
+    #[tokio::test]
+    #[serial]
+    async fn test_chunked_upload() {
+        let db = get_memory_sqlite_connection().await;
+        let (_, candidate, _) = put_user_data(&db).await;
+        let (temp_dir, _, application_cache_dir) = create_data_store_temp_dir(candidate.id).await;
+
+        let zip = portfolio_zip(7).await;
+        let sha256 = crypto::sha256_stream(zip.as_slice()).await.unwrap().0;
+
+        let upload = UploadService::init(&db, candidate.id, SLUG, zip.len() as u64).await.unwrap();
+        let upload = UploadService::append(&db, candidate.id, SLUG, upload.upload_id, 0, zip[..40].to_vec()).await.unwrap();
+        assert_eq!(upload.offset, 40);
+
+        // Chunk sent again after a lost response
+        assert!(matches!(
+            UploadService::append(&db, candidate.id, SLUG, upload.upload_id, 0, zip[..40].to_vec()).await,
+            Err(ServiceError::UploadOffsetMismatch)
+        ));
+        assert!(matches!(
+            UploadService::finalize(&db, candidate.id, SLUG, upload.upload_id, &sha256).await,
+            Err(ServiceError::IncompleteUpload)
+        ));
+
+        let status = UploadService::status(candidate.id, SLUG, upload.upload_id).await.unwrap();
+        UploadService::append(&db, candidate.id, SLUG, upload.upload_id, status.offset, zip[40..].to_vec()).await.unwrap();
+        assert!(matches!(
+            UploadService::finalize(&db, candidate.id, SLUG, upload.upload_id, &"0".repeat(64)).await,
+            Err(ServiceError::UploadChecksumMismatch)
+        ));
+        UploadService::finalize(&db, candidate.id, SLUG, upload.upload_id, &sha256).await.unwrap();
+
+        assert!(PortfolioService::is_document_cached(&db, candidate.id, SLUG).await);
+        assert_eq!(tokio::fs::read(application_cache_dir.join("PORTFOLIO.zip")).await.unwrap(), zip);
+        assert!(matches!(
+            UploadService::status(candidate.id, SLUG, upload.upload_id).await,
+            Err(ServiceError::UploadNotFound)
+        ));
+
+        assert!(matches!(
+            UploadService::init(&db, candidate.id, SLUG, 101_000_001).await,
+            Err(ServiceError::UploadTooLarge)
+        ));
+        assert!(matches!(
+            UploadService::init(&db, candidate.id, "photo", 10).await,
+            Err(ServiceError::DocumentNotFound)
+        ));
+        assert!(matches!(
+            UploadService::status(candidate.id, SLUG, Uuid::new_v4()).await,
+            Err(ServiceError::UploadNotFound)
+        ));
+        assert!(matches!(
+            UploadService::status(candidate.id, "..", upload.upload_id).await,
+            Err(ServiceError::UploadNotFound)
+        ));
+
+        clear_data_store_temp_dir(temp_dir).await;
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_init_replaces_unfinished_upload() {
+        let db = get_memory_sqlite_connection().await;
+        let (_, candidate, _) = put_user_data(&db).await;
+        let (temp_dir, _, application_cache_dir) = create_data_store_temp_dir(candidate.id).await;
+
+        let first = UploadService::init(&db, candidate.id, SLUG, 10).await.unwrap();
+        UploadService::append(&db, candidate.id, SLUG, first.upload_id, 0, vec![1; 5]).await.unwrap();
+        let second = UploadService::init(&db, candidate.id, SLUG, 10).await.unwrap();
+
+        assert!(matches!(
+            UploadService::status(candidate.id, SLUG, first.upload_id).await,
+            Err(ServiceError::UploadNotFound)
+        ));
+        assert_eq!(UploadService::status(candidate.id, SLUG, second.upload_id).await.unwrap().offset, 0);
+        let mut uploads = tokio::fs::read_dir(application_cache_dir.join("uploads").join(SLUG)).await.unwrap();
+        assert!(uploads.next_entry().await.unwrap().is_some());
+        assert!(uploads.next_entry().await.unwrap().is_none());
+
+        clear_data_store_temp_dir(temp_dir).await;
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_chunked_upload_not_zip() {
+        let db = get_memory_sqlite_connection().await;
+        let (_, candidate, _) = put_user_data(&db).await;
+        let (temp_dir, _, _) = create_data_store_temp_dir(candidate.id).await;
+
+        let data = vec![1; 10];
+        let sha256 = crypto::sha256_stream(data.as_slice()).await.unwrap().0;
+
+        let upload = UploadService::init(&db, candidate.id, SLUG, 10).await.unwrap();
+        UploadService::append(&db, candidate.id, SLUG, upload.upload_id, 0, data).await.unwrap();
+        assert!(matches!(
+            UploadService::finalize(&db, candidate.id, SLUG, upload.upload_id, &sha256).await,
+            Err(ServiceError::InvalidContent(_))
+        ));
+        assert!(!PortfolioService::is_document_cached(&db, candidate.id, SLUG).await);
+
+        clear_data_store_temp_dir(temp_dir).await;
+    }
+}
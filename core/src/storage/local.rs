@@ -0,0 +1,194 @@
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use tokio::io::{AsyncRead, AsyncWriteExt};
+
+use crate::error::ServiceError;
+
+use super::{PortfolioStore, StoreReader};
+
+/// Removes the temporary file on drop unless it was disarmed,
+/// so that nothing is left behind when the write fails or its future is dropped
+struct TmpFileGuard(Option<PathBuf>);
+
+impl Drop for TmpFileGuard {
+    fn drop(&mut self) {
+        if let Some(path) = self.0.take() {
+            std::fs::remove_file(path).ok();
+        }
+    }
+}
+
+/// Stores objects as files in a local directory
+pub struct LocalStore {
+    root: PathBuf,
+}
+
+impl LocalStore {
+    pub fn new<P: AsRef<Path>>(root: P) -> Self {
+        Self { root: root.as_ref().to_path_buf() }
+    }
+
+    /// Root directory is taken from `PORTFOLIO_STORE_PATH`
+    pub fn from_env() -> Self {
+        dotenv::dotenv().ok();
+        Self::new(std::env::var("PORTFOLIO_STORE_PATH").unwrap_or_else(|_| "".to_string()))
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.root.join(key)
+    }
+
+    fn tmp_path(path: &Path) -> PathBuf {
+        let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+        file_name.push(".tmp");
+        path.with_file_name(file_name)
+    }
+
+    async fn write_file(path: &Path, reader: &mut (dyn AsyncRead + Send + Unpin)) -> Result<(), ServiceError> {
+        let mut file = tokio::fs::File::create(path).await?;
+        tokio::io::copy(reader, &mut file).await?;
+        file.shutdown().await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl PortfolioStore for LocalStore {
+    async fn put(&self, key: &str, reader: &mut (dyn AsyncRead + Send + Unpin)) -> Result<(), ServiceError> {
+        let path = self.path(key);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        // Rename is atomic, readers never see partially written file
+        let tmp_path = Self::tmp_path(&path);
+        let mut guard = TmpFileGuard(Some(tmp_path.clone()));
+        Self::write_file(&tmp_path, reader).await?;
+        tokio::fs::rename(&tmp_path, path).await?;
+        guard.0 = None;
+
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<StoreReader, ServiceError> {
+        let file = tokio::fs::File::open(self.path(key)).await?;
+
+        Ok(Box::new(file))
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), ServiceError> {
+        match tokio::fs::remove_file(self.path(key)).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    async fn delete_prefix(&self, prefix: &str) -> Result<(), ServiceError> {
+        match tokio::fs::remove_dir_all(self.path(prefix)).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool, ServiceError> {
+        Ok(tokio::fs::metadata(self.path(key)).await.is_ok())
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, ServiceError> {
+        let mut keys = vec![];
+        let mut entries = match tokio::fs::read_dir(self.path(prefix)).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(keys),
+            Err(e) => return Err(e.into()),
+        };
+
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if entry.file_type().await?.is_file() && !name.ends_with(".tmp") {
+                keys.push(format!("{}/{}", prefix, name));
+            }
+        }
+        keys.sort();
+
+        Ok(keys)
+    }
+
+    async fn create_prefix(&self, prefix: &str) -> Result<(), ServiceError> {
+        tokio::fs::create_dir_all(self.path(prefix)).await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LocalStore;
+    use crate::storage::PortfolioStore;
+
+    async fn create_store() -> (LocalStore, std::path::PathBuf) {
+        let random_number: u32 = rand::Rng::gen(&mut rand::thread_rng());
+        let root = std::env::temp_dir().join("portfolio_test_store").join(random_number.to_string());
+        tokio::fs::create_dir_all(&root).await.unwrap();
+
+        (LocalStore::new(&root), root)
+    }
+
+    #[tokio::test]
+    async fn test_put_get_delete() {
+        let (store, root) = create_store().await;
+
+        store.put("1/cache/FILE.pdf", &mut &b"test"[..]).await.unwrap();
+        assert!(store.exists("1/cache/FILE.pdf").await.unwrap());
+        assert!(store.exists("1").await.unwrap());
+
+        let mut content = vec![];
+        let mut reader = store.get("1/cache/FILE.pdf").await.unwrap();
+        tokio::io::AsyncReadExt::read_to_end(&mut reader, &mut content).await.unwrap();
+        assert_eq!(content, b"test");
+
+        store.delete("1/cache/FILE.pdf").await.unwrap();
+        assert!(!store.exists("1/cache/FILE.pdf").await.unwrap());
+        // Deleting missing object is fine
+        store.delete("1/cache/FILE.pdf").await.unwrap();
+
+        tokio::fs::remove_dir_all(root).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_list_and_delete_prefix() {
+        let (store, root) = create_store().await;
+
+        store.create_prefix("1/cache").await.unwrap();
+        assert!(store.list("1/cache").await.unwrap().is_empty());
+
+        store.put("1/cache/B.pdf", &mut &b"b"[..]).await.unwrap();
+        store.put("1/cache/A.pdf", &mut &b"a"[..]).await.unwrap();
+        store.put("1/PORTFOLIO.age", &mut &b"age"[..]).await.unwrap();
+
+        assert_eq!(store.list("1/cache").await.unwrap(), vec!["1/cache/A.pdf", "1/cache/B.pdf"]);
+        assert_eq!(store.list("1").await.unwrap(), vec!["1/PORTFOLIO.age"]);
+
+        store.delete_prefix("1").await.unwrap();
+        assert!(!store.exists("1").await.unwrap());
+
+        tokio::fs::remove_dir_all(root).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_put_removes_tmp_file() {
+        let (store, root) = create_store().await;
+        store.create_prefix("1/cache").await.unwrap();
+
+        // Writer is kept open, so the put never finishes
+        let (mut writer, mut reader) = tokio::io::duplex(16);
+        tokio::io::AsyncWriteExt::write_all(&mut writer, b"partial").await.unwrap();
+        let put = store.put("1/cache/FILE.pdf", &mut reader);
+        assert!(tokio::time::timeout(std::time::Duration::from_millis(100), put).await.is_err());
+
+        assert!(tokio::fs::read_dir(root.join("1/cache")).await.unwrap().next_entry().await.unwrap().is_none());
+
+        tokio::fs::remove_dir_all(root).await.unwrap();
+    }
+}
@@ -0,0 +1,56 @@
+use std::sync::{Arc, OnceLock};
+
+use async_trait::async_trait;
+use tokio::io::AsyncRead;
+
+use crate::error::ServiceError;
+
+pub mod local;
+pub mod s3;
+
+pub use self::local::LocalStore;
+pub use self::s3::S3Store;
+
+pub type StoreReader = Box<dyn AsyncRead + Send + Unpin>;
+
+static S3_STORE: OnceLock<Arc<S3Store>> = OnceLock::new();
+
+/// Storage backend for candidate portfolio files
+///
+/// Keys are `/` separated paths relative to the store root, e.g. `103151/cache/PORTFOLIO.zip`
+#[async_trait]
+pub trait PortfolioStore: Send + Sync {
+    /// Writes object, replacing the existing one.
+    /// Object is visible only after the whole reader has been consumed
+    async fn put(&self, key: &str, reader: &mut (dyn AsyncRead + Send + Unpin)) -> Result<(), ServiceError>;
+    async fn get(&self, key: &str) -> Result<StoreReader, ServiceError>;
+    /// Deletes object, deleting missing object is not an error
+    async fn delete(&self, key: &str) -> Result<(), ServiceError>;
+    /// Deletes all objects under prefix
+    async fn delete_prefix(&self, prefix: &str) -> Result<(), ServiceError>;
+    /// Returns true if object or any object under `key/` exists
+    async fn exists(&self, key: &str) -> Result<bool, ServiceError>;
+    /// Returns keys of objects directly under prefix
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, ServiceError>;
+    /// Creates empty prefix, so that `exists` is true even without any objects under it
+    async fn create_prefix(&self, prefix: &str) -> Result<(), ServiceError>;
+}
+
+/// Returns store selected by `PORTFOLIO_STORE_BACKEND` (`local` or `s3`), local directory is the default.
+/// S3 client is built on first use and shared afterwards, local store is only a path and follows `PORTFOLIO_STORE_PATH`
+pub fn from_env() -> Result<Arc<dyn PortfolioStore>, ServiceError> {
+    dotenv::dotenv().ok();
+    match std::env::var("PORTFOLIO_STORE_BACKEND").as_deref() {
+        Ok("local") | Err(_) => Ok(Arc::new(LocalStore::from_env())),
+        Ok("s3") => {
+            if let Some(store) = S3_STORE.get() {
+                return Ok(store.clone());
+            }
+            let store = Arc::new(S3Store::from_env()?);
+            Ok(S3_STORE.get_or_init(|| store).clone())
+        },
+        Ok(backend) => Err(ServiceError::StorageConfigError(
+            format!("Unknown storage backend {}", backend)
+        )),
+    }
+}
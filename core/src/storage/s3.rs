@@ -0,0 +1,220 @@
+use async_trait::async_trait;
+use futures::StreamExt;
+use log::warn;
+use ::s3::{creds::Credentials, error::S3Error, Bucket, Region};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio_util::io::StreamReader;
+
+use crate::error::ServiceError;
+
+use super::{PortfolioStore, StoreReader};
+
+/// Size of multipart upload parts, S3 requires at least 5 MiB for all parts but the last
+const PART_SIZE: usize = 8 * 1024 * 1024;
+const CONTENT_TYPE: &str = "application/octet-stream";
+
+/// Aborts the multipart upload on drop unless it was completed,
+/// so that parts of failed or cancelled writes don't pile up in the bucket
+struct MultipartUpload {
+    bucket: Bucket,
+    key: String,
+    upload_id: String,
+    completed: bool,
+}
+
+impl Drop for MultipartUpload {
+    fn drop(&mut self) {
+        if self.completed {
+            return;
+        }
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let (bucket, key, upload_id) = (self.bucket.clone(), std::mem::take(&mut self.key), std::mem::take(&mut self.upload_id));
+        runtime.spawn(async move {
+            if let Err(e) = bucket.abort_upload(&key, &upload_id).await {
+                warn!("S3 MULTIPART UPLOAD {} OF {} ABORT FAILED: {}", upload_id, key, e);
+            }
+        });
+    }
+}
+
+/// Stores objects in an S3 compatible bucket (AWS, MinIO, ...)
+pub struct S3Store {
+    bucket: Bucket,
+}
+
+impl S3Store {
+    pub fn new(bucket: Bucket) -> Self {
+        Self { bucket }
+    }
+
+    /// Bucket is configured by `PORTFOLIO_S3_BUCKET`, `PORTFOLIO_S3_ENDPOINT`, `PORTFOLIO_S3_REGION`,
+    /// `PORTFOLIO_S3_ACCESS_KEY` and `PORTFOLIO_S3_SECRET_KEY`.
+    /// Path style addressing is used, so that MinIO works without wildcard DNS
+    pub fn from_env() -> Result<Self, ServiceError> {
+        dotenv::dotenv().ok();
+        let var = |name: &str| std::env::var(name)
+            .map_err(|_| ServiceError::StorageConfigError(format!("{} is not set", name)));
+
+        let region = Region::Custom {
+            region: std::env::var("PORTFOLIO_S3_REGION").unwrap_or_else(|_| "us-east-1".to_string()),
+            endpoint: var("PORTFOLIO_S3_ENDPOINT")?,
+        };
+        let credentials = Credentials::new(
+            Some(&var("PORTFOLIO_S3_ACCESS_KEY")?),
+            Some(&var("PORTFOLIO_S3_SECRET_KEY")?),
+            None,
+            None,
+            None,
+        ).map_err(|e| ServiceError::StorageConfigError(e.to_string()))?;
+
+        let bucket = Bucket::new(&var("PORTFOLIO_S3_BUCKET")?, region, credentials)?
+            .with_path_style();
+
+        Ok(Self::new(bucket))
+    }
+
+    /// Prefixes are marked with an empty object, S3 has no directories
+    fn prefix_marker(prefix: &str) -> String {
+        format!("{}/", prefix.trim_end_matches('/'))
+    }
+
+    async fn read_part(reader: &mut (dyn AsyncRead + Send + Unpin)) -> Result<Vec<u8>, ServiceError> {
+        let mut part = Vec::with_capacity(PART_SIZE);
+        reader.take(PART_SIZE as u64).read_to_end(&mut part).await?;
+
+        Ok(part)
+    }
+
+    async fn list_recursive(&self, prefix: &str) -> Result<Vec<String>, ServiceError> {
+        Ok(self.bucket
+            .list(Self::prefix_marker(prefix), None)
+            .await?
+            .into_iter()
+            .flat_map(|page| page.contents)
+            .map(|object| object.key)
+            .collect())
+    }
+}
+
+#[async_trait]
+impl PortfolioStore for S3Store {
+    async fn put(&self, key: &str, reader: &mut (dyn AsyncRead + Send + Unpin)) -> Result<(), ServiceError> {
+        let mut part = Self::read_part(reader).await?;
+        if part.len() < PART_SIZE {
+            self.bucket.put_object_with_content_type(key, &part, CONTENT_TYPE).await?;
+            return Ok(());
+        }
+
+        // Multipart upload, object appears only after it is completed
+        let response = self.bucket.initiate_multipart_upload(key, CONTENT_TYPE).await?;
+        let mut upload = MultipartUpload {
+            bucket: self.bucket.clone(),
+            key: key.to_string(),
+            upload_id: response.upload_id,
+            completed: false,
+        };
+
+        let mut parts = vec![];
+        while !part.is_empty() {
+            let last = part.len() < PART_SIZE;
+            parts.push(
+                self.bucket.put_multipart_chunk(part, key, parts.len() as u32 + 1, &upload.upload_id, CONTENT_TYPE).await?
+            );
+            part = if last { vec![] } else { Self::read_part(reader).await? };
+        }
+        self.bucket.complete_multipart_upload(key, &upload.upload_id, parts).await?;
+        upload.completed = true;
+
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<StoreReader, ServiceError> {
+        let stream = self.bucket.get_object_stream(key).await?;
+        let reader = StreamReader::new(stream.bytes.map(|chunk| {
+            chunk.map_err(std::io::Error::other)
+        }));
+
+        Ok(Box::new(reader))
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), ServiceError> {
+        self.bucket.delete_object(key).await?;
+
+        Ok(())
+    }
+
+    async fn delete_prefix(&self, prefix: &str) -> Result<(), ServiceError> {
+        for key in self.list_recursive(prefix).await? {
+            self.bucket.delete_object(key).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool, ServiceError> {
+        match self.bucket.head_object(key).await {
+            Ok(_) => Ok(true),
+            Err(S3Error::HttpFailWithBody(404, _)) => {
+                Ok(!self.list_recursive(key).await?.is_empty())
+            },
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, ServiceError> {
+        let mut keys: Vec<String> = self.bucket
+            .list(Self::prefix_marker(prefix), Some("/".to_string()))
+            .await?
+            .into_iter()
+            .flat_map(|page| page.contents)
+            .map(|object| object.key)
+            .filter(|key| !key.ends_with('/'))
+            .collect();
+        keys.sort();
+
+        Ok(keys)
+    }
+
+    async fn create_prefix(&self, prefix: &str) -> Result<(), ServiceError> {
+        self.bucket.put_object(Self::prefix_marker(prefix), &[]).await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::S3Store;
+    use crate::storage::PortfolioStore;
+
+    // Requires running MinIO (or other S3 compatible storage) configured by PORTFOLIO_S3_* variables,
+    // e.g. the one from docker-compose.yml.dev on http://localhost:9003. Run with `cargo test -- --ignored`
+    #[tokio::test]
+    #[ignore]
+    async fn test_s3_store() {
+        let store = S3Store::from_env().unwrap();
+        let prefix = format!("test-{}", rand::Rng::gen::<u32>(&mut rand::thread_rng()));
+
+        store.create_prefix(&format!("{}/cache", prefix)).await.unwrap();
+        assert!(store.exists(&prefix).await.unwrap());
+        assert!(store.list(&format!("{}/cache", prefix)).await.unwrap().is_empty());
+
+        let key = format!("{}/cache/FILE.pdf", prefix);
+        store.put(&key, &mut &b"test"[..]).await.unwrap();
+        assert!(store.exists(&key).await.unwrap());
+        assert_eq!(store.list(&format!("{}/cache", prefix)).await.unwrap(), vec![key.clone()]);
+
+        let mut content = vec![];
+        let mut reader = store.get(&key).await.unwrap();
+        tokio::io::AsyncReadExt::read_to_end(&mut reader, &mut content).await.unwrap();
+        assert_eq!(content, b"test");
+
+        store.delete(&key).await.unwrap();
+        assert!(!store.exists(&key).await.unwrap());
+
+        store.delete_prefix(&prefix).await.unwrap();
+        assert!(!store.exists(&prefix).await.unwrap());
+    }
+}
@@ -0,0 +1,289 @@
+use std::fmt;
+
+use lopdf::{Document, Object};
+use serde::{Serialize, Deserialize};
+
+use crate::error::ServiceError;
+
+pub const MAX_PDF_PAGES: usize = 100;
+pub const MAX_ZIP_ENTRIES: usize = 1000;
+/// Uncompressed size of all zip entries together
+pub const MAX_ZIP_UNCOMPRESSED_SIZE: u64 = 1_000_000_000;
+/// Entries compressed better than this are treated as zip bombs
+const MAX_COMPRESSION_RATIO: u64 = 100;
+/// Small entries compress well, e.g. text files full of spaces, the ratio is checked only above this size
+const COMPRESSION_RATIO_MIN_SIZE: u64 = 1_000_000;
+const DISALLOWED_EXTENSIONS: [&str; 17] = [
+    "exe", "com", "bat", "cmd", "msi", "scr", "dll", "ps1", "vbs", "vbe",
+    "js", "jse", "wsf", "jar", "sh", "app", "apk",
+];
+
+/// Why the content of an uploaded file was rejected
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "reason")]
+pub enum ContentIssue {
+    NotPdf,
+    /// Truncated or otherwise unreadable PDF
+    MalformedPdf,
+    EncryptedPdf,
+    NoPages,
+    TooManyPages { pages: usize },
+    PdfJavaScript,
+    NotZip,
+    /// Central directory can't be read
+    MalformedZip,
+    TooManyEntries { entries: usize },
+    TooLargeUncompressed { size: u64 },
+    SuspiciousCompression { name: String },
+    UnsafePath { name: String },
+    DisallowedExtension { name: String },
+    /// Type detected from the content is not allowed for the document
+    DisallowedType { allowed: Vec<String> },
+}
+
+impl fmt::Display for ContentIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContentIssue::NotPdf => write!(f, "file is not a PDF"),
+            ContentIssue::MalformedPdf => write!(f, "PDF is damaged or incomplete"),
+            ContentIssue::EncryptedPdf => write!(f, "PDF is encrypted"),
+            ContentIssue::NoPages => write!(f, "PDF has no pages"),
+            ContentIssue::TooManyPages { pages } => write!(f, "PDF has {} pages, at most {} are allowed", pages, MAX_PDF_PAGES),
+            ContentIssue::PdfJavaScript => write!(f, "PDF contains JavaScript"),
+            ContentIssue::NotZip => write!(f, "file is not a ZIP archive"),
+            ContentIssue::MalformedZip => write!(f, "ZIP archive is damaged or incomplete"),
+            ContentIssue::TooManyEntries { entries } => write!(f, "ZIP archive has {} files, at most {} are allowed", entries, MAX_ZIP_ENTRIES),
+            ContentIssue::TooLargeUncompressed { size } => write!(f, "ZIP archive unpacks to {} bytes, at most {} are allowed", size, MAX_ZIP_UNCOMPRESSED_SIZE),
+            ContentIssue::SuspiciousCompression { name } => write!(f, "{} is compressed suspiciously well", name),
+            ContentIssue::UnsafePath { name } => write!(f, "{} points outside of the archive", name),
+            ContentIssue::DisallowedExtension { name } => write!(f, "{} is an executable file", name),
+            ContentIssue::DisallowedType { allowed } => write!(f, "file type is not one of {}", allowed.join(", ")),
+        }
+    }
+}
+
+pub fn format_issues(issues: &[ContentIssue]) -> String {
+    issues.iter()
+        .map(|i| i.to_string())
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+fn into_result(issues: Vec<ContentIssue>) -> Result<(), ServiceError> {
+    if issues.is_empty() {
+        Ok(())
+    } else {
+        Err(ServiceError::InvalidContent(issues))
+    }
+}
+
+fn is_javascript(object: &Object) -> bool {
+    let dict = match object {
+        Object::Dictionary(dict) => dict,
+        Object::Stream(stream) => &stream.dict,
+        _ => return false,
+    };
+
+    dict.has(b"JS")
+        || dict.has(b"JavaScript")
+        || matches!(dict.get(b"S"), Ok(Object::Name(name)) if name == b"JavaScript")
+}
+
+/// Parses the whole PDF, magic bytes alone don't tell a damaged or dangerous file apart
+pub fn validate_pdf(buffer: &[u8]) -> Result<(), ServiceError> {
+    if !infer::archive::is_pdf(buffer) {
+        return into_result(vec![ContentIssue::NotPdf]);
+    }
+    let Ok(document) = Document::load_mem(buffer) else {
+        return into_result(vec![ContentIssue::MalformedPdf]);
+    };
+
+    let mut issues = vec![];
+    if document.is_encrypted() {
+        issues.push(ContentIssue::EncryptedPdf);
+    }
+    match document.get_pages().len() {
+        0 => issues.push(ContentIssue::NoPages),
+        pages if pages > MAX_PDF_PAGES => issues.push(ContentIssue::TooManyPages { pages }),
+        _ => {},
+    }
+    if document.objects.values().any(is_javascript) {
+        issues.push(ContentIssue::PdfJavaScript);
+    }
+
+    into_result(issues)
+}
+
+fn is_unsafe_path(name: &str) -> bool {
+    let name = name.replace('\\', "/");
+    name.starts_with('/')
+        || name.chars().nth(1) == Some(':')
+        || name.split('/').any(|part| part == "..")
+}
+
+fn is_disallowed_extension(name: &str) -> bool {
+    name.rsplit_once('.')
+        .map(|(_, extension)| DISALLOWED_EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// Checks the central directory of the zip without unpacking it
+pub async fn validate_zip(buffer: &[u8]) -> Result<(), ServiceError> {
+    if !infer::archive::is_zip(buffer) {
+        return into_result(vec![ContentIssue::NotZip]);
+    }
+    let Ok(reader) = async_zip::base::read::seek::ZipFileReader::new(futures::io::Cursor::new(buffer)).await else {
+        return into_result(vec![ContentIssue::MalformedZip]);
+    };
+
+    let entries = reader.file().entries();
+    let mut issues = vec![];
+    if entries.len() > MAX_ZIP_ENTRIES {
+        issues.push(ContentIssue::TooManyEntries { entries: entries.len() });
+    }
+
+    let mut total_size: u64 = 0;
+    for entry in entries.iter().map(|e| e.entry()) {
+        let name = String::from_utf8_lossy(entry.filename().as_bytes()).to_string();
+        total_size = total_size.saturating_add(entry.uncompressed_size());
+
+        if entry.uncompressed_size() > COMPRESSION_RATIO_MIN_SIZE
+            && entry.uncompressed_size() / entry.compressed_size().max(1) > MAX_COMPRESSION_RATIO {
+            issues.push(ContentIssue::SuspiciousCompression { name: name.clone() });
+        }
+        if is_unsafe_path(&name) {
+            issues.push(ContentIssue::UnsafePath { name });
+        } else if is_disallowed_extension(&name) {
+            issues.push(ContentIssue::DisallowedExtension { name });
+        }
+    }
+    if total_size > MAX_ZIP_UNCOMPRESSED_SIZE {
+        issues.push(ContentIssue::TooLargeUncompressed { size: total_size });
+    }
+
+    into_result(issues)
+}
+
+/// Detects the type of the file from its content and checks it against `allowed` MIME types.
+/// PDFs and zips are validated further
+pub async fn validate_document(buffer: &[u8], allowed: &[String]) -> Result<(), ServiceError> {
+    let mime_type = infer::get(buffer).map(|t| t.mime_type());
+    if !mime_type.is_some_and(|mime_type| allowed.iter().any(|a| a == mime_type)) {
+        return into_result(vec![ContentIssue::DisallowedType { allowed: allowed.to_vec() }]);
+    }
+
+    match mime_type {
+        Some("application/pdf") => {
+            // Parsing the whole document is CPU bound
+            let buffer = buffer.to_vec();
+            tokio::task::spawn_blocking(move || validate_pdf(&buffer)).await?
+        },
+        Some("application/zip") => validate_zip(buffer).await,
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use async_zip::{base::write::ZipFileWriter, Compression, ZipEntryBuilder};
+    use pdf_writer::{Finish, Name, Pdf, Rect, Ref, Str};
+
+    use crate::error::ServiceError;
+
+    use super::{validate_document, validate_pdf, validate_zip, ContentIssue};
+
+    pub fn pdf(javascript: bool) -> Vec<u8> {
+        let mut pdf = Pdf::new();
+        let mut catalog = pdf.catalog(Ref::new(1));
+        catalog.pages(Ref::new(2));
+        if javascript {
+            catalog.pair(Name(b"OpenAction"), Ref::new(4));
+        }
+        catalog.finish();
+        pdf.pages(Ref::new(2)).kids([Ref::new(3)]).count(1);
+        pdf.page(Ref::new(3)).parent(Ref::new(2)).media_box(Rect::new(0.0, 0.0, 595.0, 842.0));
+        if javascript {
+            pdf.indirect(Ref::new(4))
+                .dict()
+                .pair(Name(b"S"), Name(b"JavaScript"))
+                .pair(Name(b"JS"), Str(b"app.alert(1)"));
+        }
+        pdf.finish()
+    }
+
+    pub async fn zip(entries: &[(&str, Vec<u8>)]) -> Vec<u8> {
+        let mut buffer = vec![];
+        let mut writer = ZipFileWriter::new(&mut buffer);
+        for (name, data) in entries {
+            let builder = ZipEntryBuilder::new(name.to_string().into(), Compression::Deflate);
+            writer.write_entry_whole(builder, data).await.unwrap();
+        }
+        writer.close().await.unwrap();
+        buffer
+    }
+
+    fn issues<T: std::fmt::Debug>(result: Result<T, ServiceError>) -> Vec<ContentIssue> {
+        match result {
+            Err(ServiceError::InvalidContent(issues)) => issues,
+            other => panic!("Expected invalid content, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_validate_pdf() {
+        let valid = pdf(false);
+        assert!(validate_pdf(&valid).is_ok());
+
+        assert_eq!(issues(validate_pdf(&pdf(true))), vec![ContentIssue::PdfJavaScript]);
+        assert_eq!(issues(validate_pdf(&valid[..valid.len() / 2])), vec![ContentIssue::MalformedPdf]);
+        assert_eq!(issues(validate_pdf(b"PK\x03\x04")), vec![ContentIssue::NotPdf]);
+
+        let mut encrypted = lopdf::Document::load_mem(&valid).unwrap();
+        let encrypt = encrypted.add_object(lopdf::dictionary! { "Filter" => "Standard" });
+        encrypted.trailer.set("Encrypt", encrypt);
+        let mut encrypted_buffer = vec![];
+        encrypted.save_to(&mut encrypted_buffer).unwrap();
+        assert!(issues(validate_pdf(&encrypted_buffer)).contains(&ContentIssue::EncryptedPdf));
+    }
+
+    #[tokio::test]
+    async fn test_validate_zip() {
+        let valid = zip(&[("portfolio/photo.jpg", vec![1; 100]), ("README.txt", vec![2; 10])]).await;
+        assert!(validate_zip(&valid).await.is_ok());
+
+        let dangerous = zip(&[
+            ("../../etc/passwd", vec![1]),
+            ("setup.EXE", vec![1]),
+            ("bomb.txt", vec![0; 10_000_000]),
+        ]).await;
+        assert_eq!(
+            issues(validate_zip(&dangerous).await),
+            vec![
+                ContentIssue::UnsafePath { name: "../../etc/passwd".to_string() },
+                ContentIssue::DisallowedExtension { name: "setup.EXE".to_string() },
+                ContentIssue::SuspiciousCompression { name: "bomb.txt".to_string() },
+            ]
+        );
+
+        assert_eq!(issues(validate_zip(&valid[..valid.len() - 30]).await), vec![ContentIssue::MalformedZip]);
+        assert_eq!(issues(validate_zip(b"%PDF-1.7").await), vec![ContentIssue::NotZip]);
+    }
+
+    #[tokio::test]
+    async fn test_validate_document() {
+        let allowed = vec!["application/pdf".to_string(), "image/png".to_string()];
+        assert!(validate_document(&pdf(false), &allowed).await.is_ok());
+        assert!(validate_document(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR", &allowed).await.is_ok());
+
+        assert_eq!(issues(validate_document(&pdf(true), &allowed).await), vec![ContentIssue::PdfJavaScript]);
+        let zip = zip(&[("photo.jpg", vec![1])]).await;
+        assert_eq!(
+            issues(validate_document(&zip, &allowed).await),
+            vec![ContentIssue::DisallowedType { allowed: allowed.clone() }]
+        );
+        assert_eq!(
+            issues(validate_document(b"plain text", &allowed).await),
+            vec![ContentIssue::DisallowedType { allowed }]
+        );
+    }
+}
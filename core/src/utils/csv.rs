@@ -9,6 +9,9 @@ use async_trait::async_trait;
 use crate::models::candidate::{CandidateRow, FieldOfStudy, FieldsCombination};
 use crate::models::candidate_details::EncryptedCandidateDetails;
 use crate::models::school::School;
+use crate::models::admission_cycle::AdmissionCycle;
+use crate::models::review::ScoreSummary;
+use crate::services::review_service::ReviewService;
 
 impl TryFrom<(i32, ApplicationDetails)> for ApplicationRow {
     type Error = ServiceError;
@@ -105,7 +108,11 @@ pub struct CandidateCsv;
 #[async_trait]
 impl CsvExporter for CandidateCsv {
     async fn export(db: &DbConn, private_key: String) -> Result<Vec<u8>, ServiceError> {
-        let mut wtr = csv::Writer::from_writer(vec![]);
+        let cycle = AdmissionCycle::get();
+        let mut wtr = csv::WriterBuilder::new()
+            .has_headers(false)
+            .from_writer(vec![]);
+        wtr.write_record(CandidateRow::headers(cycle))?;
 
         let candidates = Query::list_candidates_full(&db).await?;
         let applications = Query::list_applications_compact(&db).await?;
@@ -129,8 +136,8 @@ impl CsvExporter for CandidateCsv {
 
 
             let (first_field, second_field) = (
-                get_our_school_field(&c.first_school).map_err(|_| ServiceError::InvalidFieldOfStudy)?,
-                get_our_school_field(&c.second_school).map_err(|_| ServiceError::InvalidFieldOfStudy)?,
+                get_our_school_field(cycle, &c.first_school)?,
+                get_our_school_field(cycle, &c.second_school)?,
             );
 
             let applications_fields_comb = get_applications_fields_comb(&related_applications);
@@ -167,18 +174,41 @@ impl CsvExporter for CandidateCsv {
     }
 }
 
+/// Final portfolio scores, one row per application.
+/// The first column is the candidate number of the admissions export, so the two can be joined
+pub struct ScoreCsv;
+
+#[async_trait]
+impl CsvExporter for ScoreCsv {
+    async fn export(db: &DbConn, private_key: String) -> Result<Vec<u8>, ServiceError> {
+        let mut wtr = csv::WriterBuilder::new()
+            .has_headers(false)
+            .from_writer(vec![]);
+        wtr.write_record(ScoreSummary::headers())?;
+
+        for summary in ReviewService::summaries(db, &private_key).await? {
+            wtr.write_record(summary.record())?;
+        }
+        wtr.into_inner()
+            .map_err(|_| ServiceError::CsvIntoInnerError)
+    }
+}
+
 fn get_applications_fields_comb(
     related_applications: &[i32],
 ) -> FieldsCombination {
-    let fields_vec = related_applications.iter().map(|id| FieldOfStudy::from(*id)).collect::<Vec<_>>();
+    let fields_vec = related_applications
+        .iter()
+        .filter_map(|id| FieldOfStudy::try_from(*id).ok())
+        .collect::<Vec<_>>();
     FieldsCombination::from_fields(
         &fields_vec.first().map(|f| f.to_owned()),
         &fields_vec.get(1).map(|f| f.to_owned()),
     )
 }
 
-fn get_our_school_field(school: &School) -> Result<Option<FieldOfStudy>, ServiceError> {
-    if school.name() == "Smíchovská střední průmyslová škola a gymnázium" {
+fn get_our_school_field(cycle: &AdmissionCycle, school: &School) -> Result<Option<FieldOfStudy>, ServiceError> {
+    if school.name() == cycle.school {
         Ok(
             Some(
                 FieldOfStudy::try_from(school.field().to_owned())?
@@ -12,7 +12,7 @@ pub async fn get_recipients(db: &DbConn, candidate_pubkey: &str) -> Result<Vec<S
 }
 
 pub async fn get_memory_sqlite_connection() -> sea_orm::DbConn {
-    use entity::{admin, candidate, parent, session};
+    use entity::{admin, admin_key_rotation, admission_round, audit_log, candidate, login_attempt, notification, parent, portfolio_version, review, session};
     use sea_orm::{Schema, Database};
     use sea_orm::{sea_query::TableCreateStatement, ConnectionTrait, DbBackend};
 
@@ -27,12 +27,26 @@ pub async fn get_memory_sqlite_connection() -> sea_orm::DbConn {
     let stmt4: TableCreateStatement = schema.create_table_from_entity(admin::Entity);
     let stmt5: TableCreateStatement = schema.create_table_from_entity(admin_session::Entity);
     let stmt6: TableCreateStatement = schema.create_table_from_entity(parent::Entity);
+    let stmt7: TableCreateStatement = schema.create_table_from_entity(admin_key_rotation::Entity);
+    let stmt8: TableCreateStatement = schema.create_table_from_entity(audit_log::Entity);
+    let stmt9: TableCreateStatement = schema.create_table_from_entity(login_attempt::Entity);
+    let stmt10: TableCreateStatement = schema.create_table_from_entity(admission_round::Entity);
+    let stmt11: TableCreateStatement = schema.create_table_from_entity(notification::Entity);
+    let stmt12: TableCreateStatement = schema.create_table_from_entity(portfolio_version::Entity);
+    let stmt13: TableCreateStatement = schema.create_table_from_entity(review::Entity);
     db.execute(db.get_database_backend().build(&stmt)).await.unwrap();
     db.execute(db.get_database_backend().build(&stmt2)).await.unwrap();
     db.execute(db.get_database_backend().build(&stmt3)).await.unwrap();
     db.execute(db.get_database_backend().build(&stmt4)).await.unwrap();
     db.execute(db.get_database_backend().build(&stmt5)).await.unwrap();
     db.execute(db.get_database_backend().build(&stmt6)).await.unwrap();
+    db.execute(db.get_database_backend().build(&stmt7)).await.unwrap();
+    db.execute(db.get_database_backend().build(&stmt8)).await.unwrap();
+    db.execute(db.get_database_backend().build(&stmt9)).await.unwrap();
+    db.execute(db.get_database_backend().build(&stmt10)).await.unwrap();
+    db.execute(db.get_database_backend().build(&stmt11)).await.unwrap();
+    db.execute(db.get_database_backend().build(&stmt12)).await.unwrap();
+    db.execute(db.get_database_backend().build(&stmt13)).await.unwrap();
     db
 }
 
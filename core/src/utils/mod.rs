@@ -1,4 +1,6 @@
 pub mod csv;
 pub mod filetype;
 pub mod db;
-pub mod date;
\ No newline at end of file
+pub mod date;
+pub mod pdf;
+pub mod content;
\ No newline at end of file
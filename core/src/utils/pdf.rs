@@ -0,0 +1,185 @@
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref, Str};
+
+/// A4 in points
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 60.0;
+
+/// Characters outside ASCII are mapped to codes from 128 through encoding differences,
+/// standard fonts contain these glyphs, so no font has to be embedded
+const EXTRA_GLYPHS: [(char, &[u8]); 33] = [
+    ('Á', b"Aacute"), ('Č', b"Ccaron"), ('Ď', b"Dcaron"), ('É', b"Eacute"), ('Ě', b"Ecaron"),
+    ('Í', b"Iacute"), ('Ň', b"Ncaron"), ('Ó', b"Oacute"), ('Ř', b"Rcaron"), ('Š', b"Scaron"),
+    ('Ť', b"Tcaron"), ('Ú', b"Uacute"), ('Ů', b"Uring"), ('Ý', b"Yacute"), ('Ž', b"Zcaron"),
+    ('á', b"aacute"), ('č', b"ccaron"), ('ď', b"dcaron"), ('é', b"eacute"), ('ě', b"ecaron"),
+    ('í', b"iacute"), ('ň', b"ncaron"), ('ó', b"oacute"), ('ř', b"rcaron"), ('š', b"scaron"),
+    ('ť', b"tcaron"), ('ú', b"uacute"), ('ů', b"uring"), ('ý', b"yacute"), ('ž', b"zcaron"),
+    ('„', b"quotedblbase"), ('“', b"quotedblleft"), ('–', b"endash"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Font {
+    Regular,
+    Bold,
+    /// Used for passwords, so that similar characters can be told apart
+    Monospace,
+}
+
+impl Font {
+    const ALL: [Font; 3] = [Font::Regular, Font::Bold, Font::Monospace];
+
+    fn resource_name(&self) -> Name<'static> {
+        match self {
+            Font::Regular => Name(b"F1"),
+            Font::Bold => Name(b"F2"),
+            Font::Monospace => Name(b"F3"),
+        }
+    }
+
+    fn base_font(&self) -> Name<'static> {
+        match self {
+            Font::Regular => Name(b"Helvetica"),
+            Font::Bold => Name(b"Helvetica-Bold"),
+            Font::Monospace => Name(b"Courier-Bold"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TextLine {
+    pub text: String,
+    pub font: Font,
+    pub size: f32,
+    /// Extra vertical space above the line
+    pub space_before: f32,
+}
+
+impl TextLine {
+    pub fn new(text: impl Into<String>, font: Font, size: f32) -> Self {
+        Self {
+            text: text.into(),
+            font,
+            size,
+            space_before: 0.0,
+        }
+    }
+
+    pub fn space_before(mut self, space: f32) -> Self {
+        self.space_before = space;
+        self
+    }
+}
+
+/// Text encoded for the fonts of `render_pages`, unsupported characters are replaced with `?`
+pub fn encode(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c {
+            ' '..='~' => c as u8,
+            _ => EXTRA_GLYPHS
+                .iter()
+                .position(|(glyph, _)| *glyph == c)
+                .map(|i| 128 + i as u8)
+                .unwrap_or(b'?'),
+        })
+        .collect()
+}
+
+/// Splits text into lines of at most `max_chars` characters at spaces
+pub fn wrap(text: &str, max_chars: usize) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > max_chars {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// Renders A4 pages of left aligned text lines into one PDF document
+pub fn render_pages(pages: &[Vec<TextLine>]) -> Vec<u8> {
+    let mut pdf = Pdf::new();
+    let mut next_ref = Ref::new(1);
+    let mut alloc = || next_ref.bump();
+
+    let catalog_id = alloc();
+    let page_tree_id = alloc();
+    let font_ids: Vec<(Font, Ref)> = Font::ALL.iter().map(|f| (*f, alloc())).collect();
+    let page_ids: Vec<(Ref, Ref)> = pages.iter().map(|_| (alloc(), alloc())).collect();
+
+    pdf.catalog(catalog_id).pages(page_tree_id);
+    pdf.pages(page_tree_id)
+        .kids(page_ids.iter().map(|(page_id, _)| *page_id))
+        .count(page_ids.len() as i32);
+
+    for (font, font_id) in font_ids.iter() {
+        let mut type1 = pdf.type1_font(*font_id);
+        type1.base_font(font.base_font());
+        type1.encoding_custom()
+            .base_encoding(Name(b"WinAnsiEncoding"))
+            .differences()
+            .consecutive(128, EXTRA_GLYPHS.iter().map(|(_, name)| Name(name)));
+    }
+
+    for (lines, (page_id, content_id)) in pages.iter().zip(page_ids.iter()) {
+        let mut page = pdf.page(*page_id);
+        page.media_box(Rect::new(0.0, 0.0, PAGE_WIDTH, PAGE_HEIGHT))
+            .parent(page_tree_id)
+            .contents(*content_id);
+        let mut resources = page.resources();
+        let mut fonts = resources.fonts();
+        for (font, font_id) in font_ids.iter() {
+            fonts.pair(font.resource_name(), *font_id);
+        }
+        fonts.finish();
+        resources.finish();
+        page.finish();
+
+        let mut content = Content::new();
+        let mut y = PAGE_HEIGHT - MARGIN;
+        for line in lines {
+            y -= line.space_before + line.size * 1.4;
+            content.begin_text()
+                .set_font(line.font.resource_name(), line.size)
+                .next_line(MARGIN, y)
+                .show(Str(&encode(&line.text)))
+                .end_text();
+        }
+        pdf.stream(*content_id, &content.finish());
+    }
+
+    pdf.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encode, render_pages, wrap, Font, TextLine};
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(encode("Ab 1"), b"Ab 1".to_vec());
+        assert_eq!(encode("Řč€"), vec![128 + 8, 128 + 16, b'?']);
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(wrap("jedna dva tři čtyři", 9), vec!["jedna dva", "tři čtyři"]);
+        assert!(wrap(" ", 10).is_empty());
+    }
+
+    #[test]
+    fn test_render_pages() {
+        let page = vec![TextLine::new("Přihláška", Font::Bold, 18.0)];
+        let pdf = render_pages(&[page.clone(), page]);
+
+        assert!(pdf.starts_with(b"%PDF"));
+        assert!(pdf.windows(8).any(|w| w == b"/Count 2"));
+    }
+}
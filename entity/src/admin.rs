@@ -12,6 +12,8 @@ pub struct Model {
     #[sea_orm(column_type = "Text")]
     pub private_key: String,
     pub password: String,
+    #[sea_orm(default_value = "superadmin")]
+    pub role: String,
     pub created_at: DateTime,
     pub updated_at: DateTime,
 }
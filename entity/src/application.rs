@@ -13,6 +13,8 @@ pub struct Model {
     pub public_key: String,
     pub private_key: String,
     pub personal_id_number: String,
+    #[sea_orm(default_value = 1)]
+    pub admission_round_id: i32,
     pub created_at: DateTime,
     pub updated_at: DateTime,
 }
@@ -29,6 +31,12 @@ pub enum Relation {
     Candidate,
     #[sea_orm(has_many = "super::session::Entity")]
     Session,
+    #[sea_orm(
+        belongs_to = "super::admission_round::Entity",
+        from = "Column::AdmissionRoundId",
+        to = "super::admission_round::Column::Id"
+    )]
+    AdmissionRound,
 }
 
 impl Related<super::candidate::Entity> for Entity {
@@ -43,4 +51,10 @@ impl Related<super::session::Entity> for Entity {
     }
 }
 
+impl Related<super::admission_round::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::AdmissionRound.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
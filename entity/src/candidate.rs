@@ -19,6 +19,7 @@ pub struct Model {
     pub email: Option<String>,
     pub sex: Option<String>,
     pub personal_identification_number: String,
+    pub personal_id_blind_index: Option<String>,
     pub school_name: Option<String>,
     pub health_insurance: Option<String>,
     pub grades_json: Option<String>,
@@ -26,6 +27,9 @@ pub struct Model {
     pub second_school: Option<String>,
     pub test_language: Option<String>,
     pub encrypted_by_id: Option<i32>,
+    #[sea_orm(default_value = 1)]
+    pub admission_round_id: i32,
+    pub deadline_extension: Option<DateTime>,
     pub created_at: DateTime,
     pub updated_at: DateTime,
 }
@@ -36,6 +40,12 @@ pub enum Relation {
     Application,
     #[sea_orm(has_many = "super::parent::Entity")]
     Parent,
+    #[sea_orm(
+        belongs_to = "super::admission_round::Entity",
+        from = "Column::AdmissionRoundId",
+        to = "super::admission_round::Column::Id"
+    )]
+    AdmissionRound,
 }
 
 impl Related<super::application::Entity> for Entity {
@@ -50,4 +60,10 @@ impl Related<super::parent::Entity> for Entity {
     }
 }
 
+impl Related<super::admission_round::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::AdmissionRound.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
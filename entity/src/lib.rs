@@ -6,4 +6,11 @@ pub mod parent;
 pub mod session;
 pub mod admin_session;
 pub mod session_trait;
-pub mod application;
\ No newline at end of file
+pub mod application;
+pub mod admin_key_rotation;
+pub mod audit_log;pub mod login_attempt;
+pub mod admission_round;
+
+pub mod notification;
+pub mod portfolio_version;
+pub mod review;
\ No newline at end of file
@@ -1,8 +1,15 @@
 //! SeaORM Entity. Generated by sea-orm-codegen 0.9.3
 
 pub use super::admin::Entity as Admin;
+pub use super::admin_key_rotation::Entity as AdminKeyRotation;
 pub use super::admin_session::Entity as AdminSession;
+pub use super::admission_round::Entity as AdmissionRound;
 pub use super::application::Entity as Application;
+pub use super::audit_log::Entity as AuditLog;
 pub use super::candidate::Entity as Candidate;
+pub use super::login_attempt::Entity as LoginAttempt;
+pub use super::notification::Entity as Notification;
 pub use super::parent::Entity as Parent;
+pub use super::portfolio_version::Entity as PortfolioVersion;
 pub use super::session::Entity as Session;
+pub use super::review::Entity as Review;
@@ -112,7 +112,7 @@ export const apiUploadCoverLetter = async (
 	progressReporter: (progress: AxiosProgressEvent) => void
 ): Promise<boolean> => {
 	try {
-		const res = await axios.post(API_URL + '/candidate/add/cover_letter', letter, {
+		const res = await axios.post(API_URL + '/candidate/documents/cover_letter', letter, {
 			withCredentials: true,
 			data: letter,
 			headers: {
@@ -128,7 +128,7 @@ export const apiUploadCoverLetter = async (
 
 export const apiDeleteCoverLetter = async (): Promise<boolean> => {
 	try {
-		await axios.delete(API_URL + '/candidate/remove/cover_letter', {
+		await axios.delete(API_URL + '/candidate/documents/cover_letter', {
 			withCredentials: true
 		});
 		return true;
@@ -142,7 +142,7 @@ export const apiUploadPortfolioLetter = async (
 	progressReporter: (progress: AxiosProgressEvent) => void
 ): Promise<boolean> => {
 	try {
-		const res = await axios.post(API_URL + '/candidate/add/portfolio_letter', letter, {
+		const res = await axios.post(API_URL + '/candidate/documents/portfolio_letter', letter, {
 			withCredentials: true,
 			data: letter,
 			headers: {
@@ -158,7 +158,7 @@ export const apiUploadPortfolioLetter = async (
 
 export const apiDeletePortfolioLetter = async (): Promise<boolean> => {
 	try {
-		await axios.delete(API_URL + '/candidate/remove/portfolio_letter', {
+		await axios.delete(API_URL + '/candidate/documents/portfolio_letter', {
 			withCredentials: true
 		});
 		return true;
@@ -172,7 +172,7 @@ export const apiUploadPortfolioZip = async (
 	progressReporter: (progress: AxiosProgressEvent) => void
 ): Promise<boolean> => {
 	try {
-		const res = await axios.post(API_URL + '/candidate/add/portfolio_zip', portfolio, {
+		const res = await axios.post(API_URL + '/candidate/documents/portfolio_zip', portfolio, {
 			withCredentials: true,
 			data: portfolio,
 			headers: {
@@ -188,7 +188,7 @@ export const apiUploadPortfolioZip = async (
 
 export const apiDeletePortfolioZip = async (): Promise<boolean> => {
 	try {
-		await axios.delete(API_URL + '/candidate/remove/portfolio_zip', {
+		await axios.delete(API_URL + '/candidate/documents/portfolio_zip', {
 			withCredentials: true
 		});
 		return true;
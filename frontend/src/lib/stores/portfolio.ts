@@ -12,7 +12,8 @@ export enum UploadStatus {
 
 export interface SubmissionProgress {
 	status?: UploadStatus;
-	files?: [number];
+	// Slugs of the uploaded documents
+	files?: string[];
 }
 export const submissionProgress = writable<SubmissionProgress>({});
 
@@ -11,6 +11,18 @@ mod m20221112_112212_create_parent_candidate_fk;
 mod m20221221_162232_create_admin_session;
 mod m20230114_114628_create_application;
 mod m20230114_114826_create_application_candidate_fk;
+mod m20230520_101500_create_admin_key_rotation;
+mod m20230521_090000_add_admin_role;
+mod m20230522_120000_create_audit_log;
+mod m20230523_100000_create_login_attempt;
+mod m20230524_090000_create_admission_round;
+mod m20230525_090000_add_candidate_blind_index;
+mod m20230526_090000_create_notification;
+mod m20230527_090000_add_candidate_deadline_extension;
+mod m20230528_090000_create_portfolio_version;
+mod m20230529_090000_add_portfolio_version_manifest;
+mod m20230530_090000_create_review;
+mod m20230530_100000_create_review_fk;
 pub struct Migrator;
 
 #[async_trait::async_trait]
@@ -23,6 +35,17 @@ impl MigratorTrait for Migrator {
             Box::new(m20221025_154422_create_session::Migration),
             Box::new(m20221221_162232_create_admin_session::Migration),
             Box::new(m20230114_114628_create_application::Migration),
+            Box::new(m20230520_101500_create_admin_key_rotation::Migration),
+            Box::new(m20230521_090000_add_admin_role::Migration),
+            Box::new(m20230522_120000_create_audit_log::Migration),
+            Box::new(m20230523_100000_create_login_attempt::Migration),
+            Box::new(m20230524_090000_create_admission_round::Migration),
+            Box::new(m20230525_090000_add_candidate_blind_index::Migration),
+            Box::new(m20230526_090000_create_notification::Migration),
+            Box::new(m20230527_090000_add_candidate_deadline_extension::Migration),
+            Box::new(m20230528_090000_create_portfolio_version::Migration),
+            Box::new(m20230529_090000_add_portfolio_version_manifest::Migration),
+            Box::new(m20230530_090000_create_review::Migration),
         ];
 
         if cfg!(debug_assertions) || cfg!(test) {
@@ -40,6 +63,7 @@ impl MigratorTrait for Migrator {
                 m20221028_194728_session_create_admin_fk::Migration,
             ));
             migrations.push(Box::new(m20230114_114826_create_application_candidate_fk::Migration));
+            migrations.push(Box::new(m20230530_100000_create_review_fk::Migration));
         }
 
         migrations
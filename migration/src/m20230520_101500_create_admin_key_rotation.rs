@@ -0,0 +1,61 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AdminKeyRotation::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AdminKeyRotation::AdminId)
+                            .integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(AdminKeyRotation::PublicKey).string().not_null())
+                    .col(ColumnDef::new(AdminKeyRotation::PrivateKey).text().not_null())
+                    .col(ColumnDef::new(AdminKeyRotation::LastCandidateId).integer().not_null())
+                    .col(ColumnDef::new(AdminKeyRotation::CreatedAt).date_time().not_null())
+                    .col(ColumnDef::new(AdminKeyRotation::UpdatedAt).date_time().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_admin_key_rotation_admin_id")
+                            .from(AdminKeyRotation::Table, AdminKeyRotation::AdminId)
+                            .to(Admin::Table, Admin::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AdminKeyRotation::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub enum AdminKeyRotation {
+    Table,
+    AdminId,
+    PublicKey,
+    PrivateKey,
+    LastCandidateId,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(Iden)]
+pub enum Admin {
+    Table,
+    Id,
+}
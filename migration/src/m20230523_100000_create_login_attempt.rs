@@ -0,0 +1,60 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(LoginAttempt::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(LoginAttempt::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(LoginAttempt::Scope).string().not_null())
+                    .col(ColumnDef::new(LoginAttempt::Key).string().not_null())
+                    .col(ColumnDef::new(LoginAttempt::FailedCount).integer().not_null())
+                    .col(ColumnDef::new(LoginAttempt::LockedUntil).date_time())
+                    .col(ColumnDef::new(LoginAttempt::LastFailedAt).date_time().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_login_attempt_scope_key")
+                    .table(LoginAttempt::Table)
+                    .col(LoginAttempt::Scope)
+                    .col(LoginAttempt::Key)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(LoginAttempt::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub enum LoginAttempt {
+    Table,
+    Id,
+    Scope,
+    Key,
+    FailedCount,
+    LockedUntil,
+    LastFailedAt,
+}
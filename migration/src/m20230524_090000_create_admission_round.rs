@@ -0,0 +1,149 @@
+use chrono::{Datelike, Local};
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AdmissionRound::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AdmissionRound::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(AdmissionRound::Name).string().not_null())
+                    .col(ColumnDef::new(AdmissionRound::CreatedAt).date_time().not_null())
+                    .col(ColumnDef::new(AdmissionRound::ClosedAt).date_time())
+                    .col(ColumnDef::new(AdmissionRound::ArchivedAt).date_time())
+                    .col(ColumnDef::new(AdmissionRound::PurgedAt).date_time())
+                    .to_owned(),
+            )
+            .await?;
+
+        // Everything created before rounds were introduced belongs to the first round.
+        // The id is left to the sequence, an explicit id would not advance it on Postgres
+        manager
+            .exec_stmt(
+                Query::insert()
+                    .into_table(AdmissionRound::Table)
+                    .columns([AdmissionRound::Name, AdmissionRound::CreatedAt])
+                    .values_panic([
+                        Local::now().year().to_string().into(),
+                        Local::now().naive_local().into(),
+                    ])
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Candidate::Table)
+                    .add_column(
+                        ColumnDef::new(Candidate::AdmissionRoundId)
+                            .integer()
+                            .not_null()
+                            .default(1),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Application::Table)
+                    .add_column(
+                        ColumnDef::new(Application::AdmissionRoundId)
+                            .integer()
+                            .not_null()
+                            .default(1),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Application::Table)
+                    .drop_column(Application::AdmissionRoundId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Candidate::Table)
+                    .drop_column(Candidate::AdmissionRoundId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(AdmissionRound::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub enum AdmissionRound {
+    Table,
+    Id,
+    Name,
+    CreatedAt,
+    ClosedAt,
+    ArchivedAt,
+    PurgedAt,
+}
+
+#[derive(Iden)]
+pub enum Candidate {
+    Table,
+    AdmissionRoundId,
+}
+
+#[derive(Iden)]
+pub enum Application {
+    Table,
+    AdmissionRoundId,
+}
+
+#[cfg(test)]
+mod tests {
+    use entity::admission_round;
+    use sea_orm_migration::{prelude::*, sea_orm::{ActiveModelTrait, Database, EntityTrait, Set}};
+
+    use crate::Migrator;
+
+    #[tokio::test]
+    async fn test_open_round_after_migration() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+
+        let first = admission_round::Entity::find_by_id(1).one(&db).await.unwrap();
+        assert!(first.is_some());
+
+        let round = admission_round::ActiveModel {
+            name: Set("next".to_string()),
+            created_at: Set(chrono::Local::now().naive_local()),
+            ..Default::default()
+        }
+            .insert(&db)
+            .await
+            .unwrap();
+        assert_eq!(round.id, 2);
+    }
+}
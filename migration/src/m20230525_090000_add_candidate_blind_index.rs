@@ -0,0 +1,57 @@
+use sea_orm_migration::prelude::*;
+
+/// Blind index columns are filled on write. Existing candidates are filled by `portfolio-cli backfill-blind-index`,
+/// because their personal data can only be decrypted with an admin private key
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Candidate::Table)
+                    .add_column(ColumnDef::new(Candidate::PersonalIdBlindIndex).string())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_candidate_personal_id_blind_index")
+                    .table(Candidate::Table)
+                    .col(Candidate::PersonalIdBlindIndex)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_candidate_personal_id_blind_index")
+                    .table(Candidate::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Candidate::Table)
+                    .drop_column(Candidate::PersonalIdBlindIndex)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub enum Candidate {
+    Table,
+    PersonalIdBlindIndex,
+}
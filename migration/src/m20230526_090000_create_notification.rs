@@ -0,0 +1,64 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Notification::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Notification::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Notification::CandidateId).integer().not_null())
+                    .col(ColumnDef::new(Notification::Kind).string().not_null())
+                    .col(ColumnDef::new(Notification::Locale).string().not_null())
+                    .col(ColumnDef::new(Notification::Status).string().not_null())
+                    .col(ColumnDef::new(Notification::Attempts).integer().not_null())
+                    .col(ColumnDef::new(Notification::LastError).string())
+                    .col(ColumnDef::new(Notification::CreatedAt).date_time().not_null())
+                    .col(ColumnDef::new(Notification::SentAt).date_time())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_notification_status")
+                    .table(Notification::Table)
+                    .col(Notification::Status)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Notification::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub enum Notification {
+    Table,
+    Id,
+    CandidateId,
+    Kind,
+    Locale,
+    Status,
+    Attempts,
+    LastError,
+    CreatedAt,
+    SentAt,
+}
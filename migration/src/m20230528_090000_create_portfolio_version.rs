@@ -0,0 +1,60 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(PortfolioVersion::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(PortfolioVersion::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(PortfolioVersion::CandidateId).integer().not_null())
+                    .col(ColumnDef::new(PortfolioVersion::Version).integer().not_null())
+                    .col(ColumnDef::new(PortfolioVersion::Hash).string().not_null())
+                    .col(ColumnDef::new(PortfolioVersion::Size).big_integer().not_null())
+                    .col(ColumnDef::new(PortfolioVersion::CreatedAt).date_time().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_portfolio_version_candidate_version")
+                    .table(PortfolioVersion::Table)
+                    .col(PortfolioVersion::CandidateId)
+                    .col(PortfolioVersion::Version)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PortfolioVersion::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub enum PortfolioVersion {
+    Table,
+    Id,
+    CandidateId,
+    Version,
+    Hash,
+    Size,
+    CreatedAt,
+}
@@ -0,0 +1,75 @@
+use sea_orm_migration::prelude::*;
+
+/// Manifest of the submitted files and hash of the stored ciphertext, used to detect corrupted or tampered portfolios
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(PortfolioVersion::Table)
+                    .add_column(ColumnDef::new(PortfolioVersion::Files).text())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(PortfolioVersion::Table)
+                    .add_column(ColumnDef::new(PortfolioVersion::CiphertextHash).string())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(PortfolioVersion::Table)
+                    .add_column(ColumnDef::new(PortfolioVersion::CiphertextSize).big_integer())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(PortfolioVersion::Table)
+                    .drop_column(PortfolioVersion::CiphertextSize)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(PortfolioVersion::Table)
+                    .drop_column(PortfolioVersion::CiphertextHash)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(PortfolioVersion::Table)
+                    .drop_column(PortfolioVersion::Files)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub enum PortfolioVersion {
+    Table,
+    Files,
+    CiphertextHash,
+    CiphertextSize,
+}
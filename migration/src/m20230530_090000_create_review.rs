@@ -0,0 +1,63 @@
+use sea_orm_migration::prelude::*;
+
+/// Reviewer assignments and portfolio scores, scores and comment are encrypted to admin keys
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Review::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Review::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Review::ApplicationId).integer().not_null())
+                    .col(ColumnDef::new(Review::AdminId).integer().not_null())
+                    .col(ColumnDef::new(Review::Scores).text())
+                    .col(ColumnDef::new(Review::Comment).text())
+                    .col(ColumnDef::new(Review::AssignedAt).date_time().not_null())
+                    .col(ColumnDef::new(Review::SubmittedAt).date_time())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_review_application_admin")
+                    .table(Review::Table)
+                    .col(Review::ApplicationId)
+                    .col(Review::AdminId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Review::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub enum Review {
+    Table,
+    Id,
+    ApplicationId,
+    AdminId,
+    Scores,
+    Comment,
+    AssignedAt,
+    SubmittedAt,
+}
@@ -0,0 +1,43 @@
+use sea_orm_migration::prelude::*;
+
+use crate::{
+    m20221024_111310_create_admin::Admin,
+    m20230114_114628_create_application::Application,
+    m20230530_090000_create_review::Review,
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.create_foreign_key(ForeignKey::create()
+            .name("review_application_fk")
+            .from(Review::Table, Review::ApplicationId)
+            .to(Application::Table, Application::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .on_update(ForeignKeyAction::Cascade)
+            .to_owned()).await?;
+
+        manager.create_foreign_key(ForeignKey::create()
+            .name("review_admin_fk")
+            .from(Review::Table, Review::AdminId)
+            .to(Admin::Table, Admin::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .on_update(ForeignKeyAction::Cascade)
+            .to_owned()).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_foreign_key(ForeignKey::drop()
+            .name("review_admin_fk")
+            .table(Review::Table)
+            .to_owned()).await?;
+
+        manager.drop_foreign_key(ForeignKey::drop()
+            .name("review_application_fk")
+            .table(Review::Table)
+            .to_owned()).await
+    }
+}